@@ -0,0 +1,6 @@
+//! Module for the apply-plan subcommand.
+
+mod cli;
+mod engine;
+pub use cli::ApplyPlanCli;
+pub use engine::get_engine;
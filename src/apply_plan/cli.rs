@@ -0,0 +1,17 @@
+use clap::Args;
+use std::path::PathBuf;
+
+#[derive(Args, Clone, Debug, PartialEq, Eq)]
+#[clap(verbatim_doc_comment)]
+/// Do the renamings of a plan written with --plan-out.
+///
+/// The plan is checked before anything is renamed, and nothing is renamed
+/// if it is invalid, e.g. if a file to rename doesn't exist anymore or a new
+/// path is already taken.
+/// Like with fmtna, the changes are recorded into the history, so that they
+/// can be reverted.
+pub struct ApplyPlanCli {
+    #[clap(verbatim_doc_comment)]
+    /// The plan file, as written with --plan-out.
+    pub plan: PathBuf,
+}
@@ -0,0 +1,22 @@
+use super::cli::ApplyPlanCli;
+use crate::cfg::Cfg;
+use crate::default::{self, DefaultArgs};
+use crate::engine::Engine;
+
+/// Returns the engine for the apply-plan subcommand, parameterized by `cli` and `cfg`.
+///
+/// The engine is fmtna's, with the renamings read from the plan file rather
+/// than planned.
+///
+/// # Parameters
+///
+/// - `cli`: The CLI arguments.
+/// - `cfg`: The configuration values.
+///
+/// # Returns
+///
+/// The parametrized engine for running the apply-plan subcommand's logic, or
+/// an error if engine creation failed.
+pub fn get_engine(cli: ApplyPlanCli, cfg: Cfg) -> anyhow::Result<Box<dyn Engine>> {
+    default::get_engine_applying_plan(DefaultArgs::default(), cfg, cli.plan)
+}
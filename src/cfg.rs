@@ -8,23 +8,94 @@ use serde::Serialize;
 /// The struct that defines the configuration file entries.
 /// It is then used with [`confy::load()`].
 pub struct Cfg {
-    /// Same as [DefaultArgs::naming_convention](crate::default::DefaultArgs::naming_convention)
+    /// Same as [ConventionArgs::naming_convention](crate::cli::ConventionArgs::naming_convention)
     pub naming_convention: NamingConvention,
 
     /// Same as [DefaultArgs::recursive](crate::default::DefaultArgs::recursive)
     pub recursive: bool,
 
-    /// Same as [DefaultArgs::keep_dots](crate::default::DefaultArgs::keep_dots)
+    /// Same as [ConventionArgs::keep_dots](crate::cli::ConventionArgs::keep_dots)
     pub keep_dots: bool,
 
-    /// Same as [DefaultArgs::keep_special_chars](crate::default::DefaultArgs::keep_special_chars)
+    /// Same as [ConventionArgs::keep_special_chars](crate::cli::ConventionArgs::keep_special_chars)
     pub keep_special_chars: bool,
 
-    /// Same as [DefaultArgs::keep_unicode](crate::default::DefaultArgs::keep_unicode)
+    /// Same as [ConventionArgs::keep_unicode](crate::cli::ConventionArgs::keep_unicode)
     pub keep_unicode: bool,
 
     /// Same as [crate::exclude::edit::EditCli::editor](crate::exclude::edit::EditCli::editor)
     pub editor: String,
+
+    /// Acronyms never to be split, e.g. `["API", "HTTP"]` (empty by default).
+    ///
+    /// Only used with [ConventionArgs::acronym_aware](crate::cli::ConventionArgs::acronym_aware),
+    /// see [split_acronyms](crate::naming_conventions::split_acronyms).
+    #[serde(default)]
+    pub acronyms: Vec<String>,
+
+    /// Whether recursion renames the directories given as arguments too, see
+    /// [DefaultArgs::no_rename_roots](crate::default::DefaultArgs::no_rename_roots)
+    /// (true by default).
+    #[serde(default = "default_recurse_renames_roots")]
+    pub recurse_renames_roots: bool,
+
+    /// Same as [ConventionArgs::preserve_dates](crate::cli::ConventionArgs::preserve_dates)
+    #[serde(default)]
+    pub preserve_dates: bool,
+}
+
+fn default_recurse_renames_roots() -> bool {
+    true
+}
+
+/// The resolution of the options that can be given both at the CLI level and
+/// in the configuration file, shared by all subcommands.
+///
+/// An option coming from the CLI always takes precedence. An option coming
+/// from the configuration file is applied only when the equivalent is not
+/// specified at the CLI level. Flags can't be unset at the CLI level, so a
+/// flag is set if it is at either level.
+impl Cfg {
+    /// Resolves [ConventionArgs::naming_convention](crate::cli::ConventionArgs::naming_convention).
+    pub fn resolve_naming_convention(&self, cli: Option<NamingConvention>) -> NamingConvention {
+        cli.unwrap_or(self.naming_convention.clone())
+    }
+
+    /// Resolves [DefaultArgs::recursive](crate::default::DefaultArgs::recursive).
+    pub fn resolve_recursive(&self, cli: bool) -> bool {
+        cli || self.recursive
+    }
+
+    /// Resolves [ConventionArgs::keep_dots](crate::cli::ConventionArgs::keep_dots).
+    pub fn resolve_keep_dots(&self, cli: bool) -> bool {
+        cli || self.keep_dots
+    }
+
+    /// Resolves [ConventionArgs::keep_special_chars](crate::cli::ConventionArgs::keep_special_chars).
+    pub fn resolve_keep_special_chars(&self, cli: bool) -> bool {
+        cli || self.keep_special_chars
+    }
+
+    /// Resolves [ConventionArgs::keep_unicode](crate::cli::ConventionArgs::keep_unicode).
+    pub fn resolve_keep_unicode(&self, cli: bool) -> bool {
+        cli || self.keep_unicode
+    }
+
+    /// Resolves the editor to edit files with, e.g. the exclude file.
+    pub fn resolve_editor(&self, cli: Option<String>) -> String {
+        cli.unwrap_or(self.editor.clone())
+    }
+
+    /// Resolves whether recursion renames the directories given as arguments,
+    /// from [DefaultArgs::no_rename_roots](crate::default::DefaultArgs::no_rename_roots).
+    pub fn resolve_recurse_renames_roots(&self, no_rename_roots: bool) -> bool {
+        !no_rename_roots && self.recurse_renames_roots
+    }
+
+    /// Resolves [ConventionArgs::preserve_dates](crate::cli::ConventionArgs::preserve_dates).
+    pub fn resolve_preserve_dates(&self, cli: bool) -> bool {
+        cli || self.preserve_dates
+    }
 }
 
 impl std::default::Default for Cfg {
@@ -36,6 +107,39 @@ impl std::default::Default for Cfg {
             keep_special_chars: false,
             keep_unicode: false,
             editor: String::from("vi"),
+            acronyms: vec![],
+            recurse_renames_roots: true,
+            preserve_dates: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cli_takes_precedence_on_config() {
+        let cfg = Cfg {
+            naming_convention: NamingConvention::KebabCase,
+            keep_dots: true,
+            editor: String::from("nano"),
+            ..Default::default()
+        };
+        assert_eq!(
+            cfg.resolve_naming_convention(Some(NamingConvention::CamelCase)),
+            NamingConvention::CamelCase
+        );
+        assert_eq!(
+            cfg.resolve_naming_convention(None),
+            NamingConvention::KebabCase
+        );
+        assert_eq!(cfg.resolve_editor(Some(String::from("vim"))), "vim");
+        assert_eq!(cfg.resolve_editor(None), "nano");
+        // Flags are set at either level.
+        assert!(cfg.resolve_keep_dots(false));
+        assert!(cfg.resolve_keep_unicode(true));
+        assert!(!cfg.resolve_keep_special_chars(false));
+        assert!(!cfg.resolve_recurse_renames_roots(true));
+    }
+}
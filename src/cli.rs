@@ -1,9 +1,16 @@
 //! Everything related to the app's CLI.
 
+use crate::apply_plan::ApplyPlanCli;
+use crate::compare::CompareCli;
 use crate::default::DefaultArgs;
+use crate::detect::DetectCli;
+use crate::doctor::DoctorCli;
 use crate::exclude::ExcludeCli;
+use crate::format::FormatCli;
+use crate::naming_conventions::{DatePattern, NamingConvention};
+use crate::rename::RenameCli;
 use crate::revert::RevertCli;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use std::fmt::Debug;
 
 // See https://github.com/clap-rs/clap/issues/975#issuecomment-1426424232
@@ -19,6 +26,9 @@ use std::fmt::Debug;
 /// For each file/path (of any kind) given as argument, change the filename
 /// (i.e. the base of the path) according to the selected naming convention.
 ///
+/// Results, i.e. the lines telling what was done to each file, are printed
+/// to stdout, while warnings, errors and prompts are printed to stderr.
+///
 /// WARNING! This program is dangerous.
 /// Changing filenames is error prone and may cause undesired consequences
 /// (some files are expected to have the name they have and not something else!).
@@ -48,4 +58,177 @@ pub enum Command {
     Exclude(ExcludeCli),
     #[allow(missing_docs)]
     Revert(RevertCli),
+    #[allow(missing_docs)]
+    Format(FormatCli),
+    #[allow(missing_docs)]
+    Detect(DetectCli),
+    #[allow(missing_docs)]
+    Compare(CompareCli),
+    #[allow(missing_docs)]
+    Doctor(DoctorCli),
+    #[allow(missing_docs)]
+    Rename(RenameCli),
+    #[allow(missing_docs)]
+    ApplyPlan(ApplyPlanCli),
+}
+
+#[derive(Args, Clone, Debug, Default, PartialEq, Eq)]
+/// Arguments controlling how names are formatted, shared by every command
+/// that formats names.
+pub struct ConventionArgs {
+    /// The naming convention to use.
+    ///
+    /// The default is "snake_case".
+    /// If one is specified in the config file, it will be used instead.
+    /// Aliases are accepted too: camel, kebab, train, snake, screaming_snake,
+    /// pascal, title, lowercase, upper and uppercase.
+    #[clap(verbatim_doc_comment)]
+    #[arg(short, long)]
+    pub naming_convention: Option<NamingConvention>,
+
+    /// Don't treat dots as separators, let them as is.
+    ///
+    /// A separator is a character indicating a break between words.
+    /// The characters "_", "-", "." and spaces are considered separators
+    /// and may change according to the chosen naming convention, unless
+    /// this flag is used.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub keep_dots: bool,
+
+    /// Keep special characters.
+    ///
+    /// By special characters we mean characters that are neither alphanumeric
+    /// nor separators ("_", "-", "." and spaces).
+    /// If not set, special characters are removed with the exception of some
+    /// accented letters that are replaced by their non-accented variants.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub keep_special_chars: bool,
+
+    /// Keep Unicode (more precisely, non-ASCII) characters.
+    ///
+    /// When not set, convert unicode characters to their closest ASCII
+    /// counterparts using <https://crates.io/crates/unidecode>.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub keep_unicode: bool,
+
+    /// Collapse runs of whitespace characters into a single space before formatting.
+    ///
+    /// Otherwise, the "lower" and "UPPER" naming conventions keep whitespace
+    /// as is, so that e.g. "a  b" is formatted into "a  b" in lowercase.
+    /// The other naming conventions never repeat separators anyway.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub squeeze_whitespace: bool,
+
+    /// Treat runs of uppercase letters as acronyms, i.e. as words on their own.
+    ///
+    /// By default, only a lowercase letter followed by an uppercase one marks
+    /// a break between words, so that e.g. "URLParser" is formatted into
+    /// "urlparser" in snake_case. With this flag, the last uppercase letter of
+    /// a run followed by a lowercase letter starts a new word, giving "url_parser"
+    /// (and "urlParser" in camelCase).
+    /// Acronyms listed in the `acronyms` entry of the config file are words of
+    /// their own as well, never split (e.g. "APIs" stays a single word).
+    /// Has no effect on the "lower" and "UPPER" naming conventions.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub acronym_aware: bool,
+
+    /// Also split words on Unicode word boundaries, when keeping Unicode.
+    ///
+    /// By default, words are only delimited by separators and case transitions,
+    /// so that scripts written without spaces nor case (e.g. Chinese or
+    /// Japanese) are not split at all.
+    /// With this flag, word boundaries as defined by Unicode (UAX #29) are used
+    /// as well, e.g. each Chinese character is a word of its own.
+    /// Only applies with --keep-unicode, and has no effect on the "lower" and
+    /// "UPPER" naming conventions.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub unicode_words: bool,
+
+    /// Remove separators at the beginning and at the end of formatted names.
+    ///
+    /// E.g. "_foo_" is formatted into "foo" instead of "_foo_" in snake_case.
+    /// The leading dot of dotfiles is kept.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub trim_separators: bool,
+
+    /// Format dotfiles like any other file, instead of keeping their leading dot.
+    ///
+    /// The leading dot is removed and the whole rest of the name, including
+    /// what would otherwise be the extension, is formatted.
+    /// E.g. ".env.local" is formatted into "env_local" in snake_case, instead
+    /// of being left as is.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub format_dotfiles: bool,
+
+    /// Collapse runs of dots in filenames before formatting them.
+    ///
+    /// Empty (or whitespace only) segments between dots are removed, e.g.
+    /// "file..txt" and "file.  .txt" are formatted into "file.txt", and
+    /// "a...b.txt" into "a_b.txt" in snake_case. A trailing dot is removed
+    /// too, and the leading dot of dotfiles is kept.
+    /// As this happens before the extension is split off, the dots left
+    /// within the stem are then formatted as usual, e.g. "a...b.txt" is
+    /// formatted into "a.b.txt" with --keep-dots and --keep-special-chars.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub normalize_dots: bool,
+
+    /// Keep this substring as is wherever it appears in names.
+    ///
+    /// The rest of the name is formatted around it, e.g. with
+    /// --protect SKU-1234, "Order SKU-1234 Invoice" is formatted into
+    /// "order_SKU-1234_invoice" in snake_case.
+    /// Can be repeated. The longest substring wins when several overlap.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, value_name = "SUBSTR")]
+    pub protect: Vec<String>,
+
+    /// Keep ISO dates (e.g. 2024-01-15) as is wherever they appear in names.
+    ///
+    /// The rest of the name is formatted around them like with --protect,
+    /// e.g. "report 2024-01-15 final" is formatted into
+    /// "report_2024-01-15_final" in snake_case, rather than
+    /// "report_2024_01_15_final".
+    /// Can be made the default with `preserve_dates = true` in the config
+    /// file.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub preserve_dates: bool,
+
+    /// Keep the substrings matching this regex as is too, like dates.
+    ///
+    /// Useful for dates written another way, e.g. '\d{2}\.\d{2}\.\d{4}' for
+    /// "15.01.2024". ISO dates are still kept. Implies --preserve-dates.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, value_name = "REGEX")]
+    pub date_pattern: Option<DatePattern>,
+
+    /// Only change the case of names, leaving everything else as is.
+    ///
+    /// Names are uppercased with the "UPPER" naming convention, and
+    /// lowercased with any other. Separators and special characters are kept,
+    /// and Unicode is still transliterated unless --keep-unicode is used.
+    /// E.g. "My-File.TXT" is formatted into "my-file.TXT".
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub case_only: bool,
+
+    /// Keep the case of letters, only replacing separators.
+    ///
+    /// E.g. "My File" is formatted into "My_File" in snake_case, instead of
+    /// "my_file". Words are still split on case transitions, e.g.
+    /// "myFile" is formatted into "my_File".
+    /// Only applies to the "snake_case" and "kebab-case" naming conventions,
+    /// the others being defined by the case of their letters.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub keep_case: bool,
 }
@@ -0,0 +1,7 @@
+//! Module for the compare subcommand.
+
+mod cli;
+mod data;
+mod engine;
+pub use cli::CompareCli;
+pub use engine::get_engine;
@@ -0,0 +1,29 @@
+use clap::Args;
+
+#[derive(Args, Clone, Debug, PartialEq, Eq)]
+#[clap(verbatim_doc_comment)]
+/// Print how names look in each naming convention, side by side.
+///
+/// One row is printed per name and one column per naming convention.
+/// This is useful to choose a naming convention. No file is touched.
+pub struct CompareCli {
+    #[clap(verbatim_doc_comment)]
+    /// The names to format.
+    #[arg(required = true)]
+    pub names: Vec<String>,
+
+    #[clap(verbatim_doc_comment)]
+    /// Format as with --keep-dots.
+    #[arg(long)]
+    pub keep_dots: bool,
+
+    #[clap(verbatim_doc_comment)]
+    /// Format as with --keep-special-chars.
+    #[arg(long)]
+    pub keep_special_chars: bool,
+
+    #[clap(verbatim_doc_comment)]
+    /// Format as with --keep-unicode.
+    #[arg(long)]
+    pub keep_unicode: bool,
+}
@@ -0,0 +1,28 @@
+use super::cli::CompareCli;
+use crate::cfg::Cfg;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Data {
+    /// Same as [CompareCli::names](crate::compare::CompareCli::names)
+    pub names: Vec<String>,
+
+    /// Same as [CompareCli::keep_dots](crate::compare::CompareCli::keep_dots)
+    pub keep_dots: bool,
+
+    /// Same as [CompareCli::keep_special_chars](crate::compare::CompareCli::keep_special_chars)
+    pub keep_special_chars: bool,
+
+    /// Same as [CompareCli::keep_unicode](crate::compare::CompareCli::keep_unicode)
+    pub keep_unicode: bool,
+}
+
+impl Data {
+    pub fn new(cli: CompareCli, cfg: Cfg) -> anyhow::Result<Self> {
+        Ok(Data {
+            names: cli.names,
+            keep_dots: cfg.resolve_keep_dots(cli.keep_dots),
+            keep_special_chars: cfg.resolve_keep_special_chars(cli.keep_special_chars),
+            keep_unicode: cfg.resolve_keep_unicode(cli.keep_unicode),
+        })
+    }
+}
@@ -0,0 +1,153 @@
+use super::cli::CompareCli;
+use super::data::Data;
+use crate::cfg::Cfg;
+use crate::engine::Engine;
+use crate::naming_conventions::{apply_nc, NamingConvention};
+use anyhow::Context;
+use clap::ValueEnum;
+use std::io;
+use std::io::Write;
+
+/// Returns the engine for the compare subcommand, parameterized by `cli` and `cfg`.
+///
+/// # Parameters
+///
+/// - `cli`: The CLI arguments.
+/// - `cfg`: The configuration values.
+///
+/// # Returns
+///
+/// The parametrized engine for running the compare subcommand's logic, or an
+/// error if engine creation failed.
+pub fn get_engine(cli: CompareCli, cfg: Cfg) -> anyhow::Result<Box<dyn Engine>> {
+    Ok(Box::new(CompareEngine::new(cli, cfg)?))
+}
+
+struct CompareEngine {
+    data: Data,
+}
+
+impl CompareEngine {
+    pub fn new(cli: CompareCli, cfg: Cfg) -> anyhow::Result<Self> {
+        let data = Data::new(cli, cfg)?;
+        Ok(Self { data })
+    }
+
+    fn write_table<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
+        let ncs = NamingConvention::value_variants();
+
+        let header = std::iter::once(String::from("name")).chain(
+            ncs.iter()
+                .filter_map(|nc| nc.to_possible_value())
+                .map(|value| value.get_name().to_string()),
+        );
+        let mut rows = vec![header.collect::<Vec<_>>()];
+        for name in &self.data.names {
+            let cells = ncs.iter().map(|nc| {
+                apply_nc(
+                    nc,
+                    name,
+                    self.data.keep_dots,
+                    self.data.keep_special_chars,
+                    self.data.keep_unicode,
+                )
+            });
+            rows.push(std::iter::once(name.clone()).chain(cells).collect());
+        }
+
+        let widths: Vec<usize> = (0..rows[0].len())
+            .map(|col| {
+                rows.iter()
+                    .map(|row| row[col].chars().count())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        for row in &rows {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(writer, "{}", line.trim_end())
+                .with_context(|| "Failed to write the comparison table.")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Engine for CompareEngine {
+    fn run(&mut self) -> anyhow::Result<()> {
+        self.write_table(&mut io::stdout().lock())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Command};
+    use clap::Parser;
+
+    fn output_of(args: &[&str]) -> String {
+        let cli = Cli::try_parse_from(args).expect("Arguments should be valid.");
+        let engine = match cli.command {
+            Some(Command::Compare(cli)) => CompareEngine::new(cli, Cfg::default())
+                .expect("CompareEngine::new should have succeeded."),
+            _ => panic!("Expected the compare subcommand."),
+        };
+        let mut out: Vec<u8> = vec![];
+        engine
+            .write_table(&mut out)
+            .expect("Writing to a Vec shouldn't fail.");
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn prints_one_row_per_name_and_one_column_per_naming_convention() {
+        let out = output_of(&["fmtna", "compare", "My File", "some-name"]);
+        let rows: Vec<Vec<&str>> = out
+            .lines()
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            rows[0],
+            [
+                "name",
+                "camelCase",
+                "kebab-case",
+                "Train-Case",
+                "snake_case",
+                "SCREAMING_SNAKE_CASE",
+                "PascalCase",
+                // "Title Case", split on its space.
+                "Title",
+                "Case",
+                "lower",
+                "UPPER"
+            ]
+        );
+        assert_eq!(
+            rows[2],
+            [
+                "some-name",
+                "someName",
+                "some-name",
+                "Some-Name",
+                "some_name",
+                "SOME_NAME",
+                "SomeName",
+                "Some",
+                "Name",
+                "some-name",
+                "SOME-NAME"
+            ]
+        );
+        assert!(out.lines().nth(1).unwrap().starts_with("My File  "));
+        assert!(out.contains("my_file"));
+        assert!(out.contains("MyFile"));
+    }
+}
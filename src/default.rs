@@ -2,6 +2,20 @@
 
 mod cli;
 mod data;
+mod diff;
 mod engine;
-pub use cli::DefaultArgs;
-pub use engine::get_engine;
+mod event;
+mod marker;
+mod plan;
+mod script;
+mod summary;
+mod timings;
+pub use cli::{
+    ConventionFor, DefaultArgs, ExtensionCase, FileKind, OnConflict, ScriptShell, TraversalOrder,
+};
+pub use data::{resolve_options, Data};
+pub use engine::{
+    get_engine, get_engine_applying_plan, get_engine_with_observer, get_engine_with_rename_pattern,
+    Observer,
+};
+pub use event::RenameEvent;
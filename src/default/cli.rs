@@ -1,10 +1,14 @@
+use std::borrow::Cow;
 use std::path::PathBuf;
+use std::str::FromStr;
 
-use clap::Args;
+use clap::{Args, ValueEnum};
+use serde::Serialize;
 
+use crate::cli::ConventionArgs;
 use crate::naming_conventions::NamingConvention;
 
-#[derive(Debug, Args)]
+#[derive(Debug, Default, Args)]
 /// Default arguments of fmtna (or arguments of the default "subcommand").
 pub struct DefaultArgs {
     /// A list of files (of any kind) for which to format the name.
@@ -13,13 +17,148 @@ pub struct DefaultArgs {
     #[clap(verbatim_doc_comment)]
     pub files: Vec<PathBuf>,
 
-    /// The naming convention to use.
+    /// Also format the files matching this glob pattern.
     ///
-    /// The default is "snake_case".
-    /// If one is specified in the config file, it will be used instead.
+    /// The pattern is expanded by fmtna itself rather than by the shell, which
+    /// is handy with shells not expanding them (e.g. cmd.exe on Windows) or
+    /// when matches are too many to be passed as arguments.
+    /// Relative patterns are relative to the current directory.
+    /// Matches are treated like files given as arguments.
+    /// Can be repeated.
+    ///
+    /// Example: --glob "photos/**/*.JPG"
     #[clap(verbatim_doc_comment)]
-    #[arg(short, long)]
-    pub naming_convention: Option<NamingConvention>,
+    #[arg(long, value_name = "PATTERN")]
+    pub glob: Vec<String>,
+
+    /// Also format the files listed in this file, one path per line.
+    ///
+    /// Blank lines and lines starting with # are ignored.
+    /// Relative paths are relative to the current directory.
+    /// Listed files are treated like files given as arguments.
+    /// Can be repeated.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, value_name = "FILE")]
+    pub input_file: Vec<PathBuf>,
+
+    /// Continue an interrupted run from the resume file it left.
+    ///
+    /// When a run is interrupted with Ctrl-C, the file being processed is
+    /// finished, the history file is written, and the files left to process
+    /// are saved to a resume file whose path is printed.
+    /// Give the same options as the interrupted run, except for the files.
+    /// The resumed run writes its own history file.
+    /// Press Ctrl-C twice to quit right away instead, without a resume file.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["files", "glob", "input_file", "parents"])]
+    pub resume: Option<PathBuf>,
+
+    /// Print the rename plan of the paths listed in FILE, without touching
+    /// the filesystem.
+    ///
+    /// FILE has one path per line, blank lines being ignored. The paths need
+    /// not exist: they are e.g. the members of an archive (as listed by
+    /// `tar -tf` or `unzip -Z1`), so that renamings can be planned before
+    /// extracting it.
+    /// The plan is printed like with --dry-run --json, one JSON object per
+    /// line and per path. A path whose new path is listed too, or is the new
+    /// path of a previous one, is a conflict.
+    /// Only the global formatting options apply, marker files are ignored.
+    #[clap(verbatim_doc_comment)]
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = [
+            "files", "glob", "input_file", "resume", "recursive", "parents", "output_dir",
+            "convention_for", "from", "by_mime", "dry_run", "check", "check_conflicts", "legend", "summary_json"
+        ]
+    )]
+    pub from_listing: Option<PathBuf>,
+
+    #[clap(flatten)]
+    /// Arguments controlling how names are formatted.
+    pub convention: ConventionArgs,
+
+    /// Use a specific naming convention for some kinds of files.
+    ///
+    /// Takes the form KIND=CONVENTION, where KIND is either `dir` (for
+    /// directories) or an extension with its leading dot (e.g. `.md`,
+    /// compared case-insensitively), and CONVENTION is one of the values
+    /// accepted by --naming-convention.
+    /// Can be repeated, in which case the first matching one applies.
+    /// Files matching none use the global naming convention.
+    ///
+    /// Example: --convention-for dir=PascalCase --convention-for .md=kebab-case
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, value_name = "KIND=CONVENTION")]
+    pub convention_for: Vec<ConventionFor>,
+
+    /// Only format the files whose name follows this naming convention.
+    ///
+    /// The naming conventions a name follows are the ones the `detect`
+    /// subcommand finds, without the extension. Other files are left alone,
+    /// though directories are still recursed into.
+    /// Useful for migrating from one naming convention to another without
+    /// touching files in an unrelated style.
+    ///
+    /// Example: --from kebab-case -n snake_case
+    #[clap(verbatim_doc_comment)]
+    #[arg(
+        long,
+        value_enum,
+        value_name = "CONVENTION",
+        conflicts_with = "output_dir"
+    )]
+    pub from: Option<NamingConvention>,
+
+    /// Only format the extension-less files whose content is of this type.
+    ///
+    /// The type is either a MIME type (e.g. `image/png`) or only its
+    /// top-level type (e.g. `image`), and is guessed from the first bytes of
+    /// the files (their "magic bytes"). Files not recognized that way are
+    /// `text/plain` if they are valid UTF-8, `application/octet-stream`
+    /// otherwise.
+    /// Files with an extension and directories are not affected, so this
+    /// composes with the other filters (e.g. --from and exclude patterns).
+    /// As every extension-less file gets read, this is opt-in.
+    ///
+    /// Example: --by-mime image
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, value_name = "TYPE", conflicts_with = "output_dir")]
+    pub by_mime: Option<String>,
+
+    /// The case of extensions.
+    ///
+    /// Extensions are not formatted, whatever the naming convention, but their
+    /// case can be changed nonetheless: `keep` leaves them as is, `lower` and
+    /// `upper` lowercase and uppercase them (e.g. "Photo.JPG" into "photo.jpg"
+    /// with `lower`).
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, value_enum, value_name = "CASE", default_value_t = ExtensionCase::Keep)]
+    pub extension_case: ExtensionCase,
+
+    /// Ignore the marker files setting formatting options per directory.
+    ///
+    /// A marker file is a file named .fmtna.toml, e.g. at the root of a
+    /// project, setting some of the following options (in TOML):
+    ///   - `naming_convention`: Same as --naming-convention.
+    ///   - `keep_dots`: Same as --keep-dots.
+    ///   - `keep_special_chars`: Same as --keep-special-chars.
+    ///   - `keep_unicode`: Same as --keep-unicode.
+    ///
+    /// By default, files are formatted with the options of the nearest marker
+    /// file found by walking up from their parent directory, so that projects
+    /// in a monorepo can each have their own naming convention.
+    /// Options are taken, from highest to lowest precedence, from:
+    ///   1. --convention-for (the naming convention only).
+    ///   2. The nearest marker file (not the ones further up).
+    ///   3. The CLI arguments.
+    ///   4. The configuration file.
+    ///
+    /// Example of .fmtna.toml: naming_convention = "kebab-case"
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub no_markers: bool,
 
     /// Recursively format filenames within directories.
     ///
@@ -31,31 +170,653 @@ pub struct DefaultArgs {
     #[arg(short, long)]
     pub recursive: bool,
 
-    /// Don't treat dots as separators, let them as is.
+    /// With --recursive, don't rename the directories given as arguments.
+    ///
+    /// Their content is formatted nonetheless. Useful to avoid renaming e.g.
+    /// the root of a project by accident.
+    /// Can be made the default with `recurse_renames_roots = false` in the
+    /// config file.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub no_rename_roots: bool,
+
+    /// Also format the names of the directories leading to the files.
+    ///
+    /// For each file given as argument, its ancestor directories are formatted
+    /// as well, up to the base directory (see --base) or the current directory,
+    /// excluded. Ancestors are formatted after what they contain, so that
+    /// paths don't get outdated, and are not recursed into.
+    /// When ancestors of different files are formatted into the same name (e.g.
+    /// "My Dir" and "my-dir" into "my_dir"), the second one conflicts with the
+    /// first, and the conflict is handled like any other.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub parents: bool,
+
+    /// The order in which files are visited when recursing.
+    ///
+    /// With `depth`, the content of a directory is entirely formatted before
+    /// moving on to its next sibling. With `breadth`, every file at a given
+    /// depth is formatted before moving on to the next depth.
+    /// In both cases, a directory is renamed before its content is visited,
+    /// and the content of a directory is visited in filename order.
+    /// Files given as arguments are visited first, the longest paths first,
+    /// so that renaming a directory doesn't make other arguments outdated.
+    /// Files are visited (and renamed) one at a time, so the order of the
+    /// lines of the history file is deterministic: it is the visiting order.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, value_enum, default_value_t = TraversalOrder::Depth)]
+    pub order: TraversalOrder,
+
+    /// The minimum length (in characters) of a formatted stem.
+    ///
+    /// Formatting may shorten names a lot (e.g. when special characters are
+    /// removed), down to an empty stem.
+    /// When the formatted stem is shorter than this length, the file is not
+    /// renamed and the problem is reported instead.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, value_name = "N")]
+    pub min_length: Option<usize>,
+
+    /// The maximum length (in characters) of a formatted stem.
+    ///
+    /// When the formatted stem is longer, it is truncated to this length.
+    /// The extension, if any, is kept as is.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, value_name = "N")]
+    pub max_length: Option<usize>,
+
+    /// When truncating a stem (see --max-length), end it with a short hash.
+    ///
+    /// The hash (6 hexadecimal digits) is computed from the original stem,
+    /// so that long names sharing a prefix don't collide once truncated.
+    /// The truncated stem, hash included, still fits in --max-length.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, requires = "max_length")]
+    pub truncate_hash: bool,
+
+    /// Stop after this number of renamings.
+    ///
+    /// The file being processed when the limit is reached is finished, then
+    /// the files left are saved to a resume file, see --resume.
+    /// Backups and overwrites count as renamings, skipped conflicts don't.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, value_name = "N")]
+    pub limit: Option<usize>,
+
+    /// Abort the run when there are more than this number of conflicts.
+    ///
+    /// A conflict is a file whose new name is already taken, whatever the
+    /// way it is then resolved. The conflict above the maximum is left
+    /// unresolved, the history file is written as usual, and the files left
+    /// are saved to a resume file (see --resume), then the program exits
+    /// with a non-zero status.
+    /// A safety rail for runs with the wrong naming convention, rather than
+    /// being asked what to do again and again.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, value_name = "N")]
+    pub max_conflicts: Option<usize>,
+
+    /// What to do when a file would be renamed into an existing file.
+    ///
+    /// By default, you are asked what to do for each conflict.
+    /// With `newest` or `largest`, the file that was modified last, or that
+    /// is the largest, is kept under the name, and the other one is moved to
+    /// the backup directory. On ties, the existing file is kept.
+    /// When the renamed file is the one backed up, it is recorded as a `(d)`
+    /// line into the backup directory, so that reverting restores it.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, value_enum, value_name = "POLICY", default_value_t = OnConflict::Ask)]
+    pub on_conflict: OnConflict,
+
+    /// Retry renamings failing transiently this many times.
+    ///
+    /// Only errors that are plausibly transient (e.g. interrupted or timed out
+    /// operations, as may happen on network filesystems) are retried.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub retry: usize,
+
+    /// How long to wait before retrying a renaming, in milliseconds.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, value_name = "MS", default_value_t = 100)]
+    pub retry_delay: u64,
+
+    /// Skip files locked by another process instead of reporting an error.
+    ///
+    /// On Windows, a file opened by another process usually can't be renamed.
+    /// By default, this is reported as an error, which you need to acknowledge.
+    /// With this flag, such a file is skipped with a warning instead, recorded
+    /// as a `(k)` line in the history file.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub skip_locked: bool,
+
+    /// Skip files on a read-only filesystem instead of stopping the run.
+    ///
+    /// By default, the run stops with an error at the first file that can't
+    /// be renamed because it is on a read-only filesystem (e.g. a read-only
+    /// mount), as no other file there could be renamed either.
+    /// With this flag, such a file is skipped with a warning instead, recorded
+    /// as a `(r)` line in the history file.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub ignore_readonly: bool,
+
+    /// Don't wait for a keypress when an error occurs.
+    ///
+    /// By default, each error is shown in a prompt you need to acknowledge.
+    /// With this flag, errors are only printed and recorded as `(e)` lines in
+    /// the history file, so that the run proceeds unattended.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub yes_to_errors: bool,
+
+    /// Undo the renamings of the run if it stops on a fatal error.
+    ///
+    /// Fatal errors are the ones ending the run, e.g. failing to back up a
+    /// conflicting file or to write the history file. The renamings already
+    /// done are then reverted, the same way `fmtna revert` would, before
+    /// exiting.
+    /// Errors about a single file (e.g. a file that doesn't exist anymore) are
+    /// not fatal and don't trigger this.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub transactional: bool,
+
+    /// Copy the files into this directory under their formatted name, instead
+    /// of renaming them.
+    ///
+    /// The original files are left untouched. Every file is copied, including
+    /// the ones already following the naming convention.
+    /// With --recursive, the files within directories are copied into the
+    /// copies of their directories, so that relative paths are preserved
+    /// (formatted). Directories are created rather than copied, and merged
+    /// with the ones already in the output directory.
+    /// Copies are recorded as `(c)` lines in the history file, so that reverting
+    /// removes them. Conflicts within the output directory are handled as usual,
+    /// except that a backed up file is recorded as a `(d)` line into the backup
+    /// directory, so that reverting restores it.
+    /// The directory is created if needed.
+    #[clap(verbatim_doc_comment)]
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with_all = ["resume", "parents", "git", "leave_symlink", "dry_run", "check", "check_conflicts"]
+    )]
+    pub output_dir: Option<PathBuf>,
+
+    /// Rename files tracked by git with `git mv`, so that renamings are staged.
+    ///
+    /// Files outside of any git work tree, or untracked, are renamed as usual.
+    /// Conflicts resolved by backing up or overwriting are also renamed as usual.
+    /// Requires git to be installed.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub git: bool,
+
+    /// After renaming a file, leave a symbolic link at its old path.
+    ///
+    /// The link points to the new filename (relatively), so that the old path
+    /// still resolves. It is recorded in the history file as a `(l)` line and
+    /// removed when reverting.
+    /// Failing to create the link is reported, but doesn't undo the renaming.
+    /// On Windows, creating symbolic links requires either administrator
+    /// privileges or the Developer Mode to be enabled.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub leave_symlink: bool,
+
+    /// Report characters that can't be transliterated to ASCII as errors.
+    ///
+    /// Unless Unicode characters are kept (see --keep-unicode), they are
+    /// transliterated to ASCII, but some characters (e.g. emojis) have no
+    /// transliteration and are silently dropped.
+    /// With this flag, a file whose stem contains such characters is not
+    /// renamed and the characters are reported instead.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub strict_ascii: bool,
+
+    /// Format filenames that are not valid Unicode anyway.
     ///
-    /// A separator is a character indicating a break between words.
-    /// The characters "_", "-", "." and spaces are considered separators
-    /// and may change according to the chosen naming convention, unless
-    /// this flag is used.
+    /// By default, a filename containing invalid Unicode is reported as an error
+    /// and left untouched.
+    /// With this flag, the invalid bytes are replaced by U+FFFD (the Unicode
+    /// replacement character) before formatting, then the file is renamed.
+    /// WARNING! The conversion is lossy: the original bytes are lost, so the
+    /// history file can't record the original name faithfully and such a
+    /// change can't be reverted automatically.
     #[clap(verbatim_doc_comment)]
     #[arg(long)]
-    pub keep_dots: bool,
+    pub allow_lossy_unicode: bool,
 
-    /// Keep special characters.
+    /// Don't exclude any file for this run, ignoring exclude.txt.
     ///
-    /// By special characters we mean characters that are neither alphanumeric
-    /// nor separators ("_", "-", "." and spaces).
-    /// If not set, special characters are removed with the exception of some
-    /// accented letters that are replaced by their non-accented variants.
+    /// Useful to format a file your exclude patterns normally protect,
+    /// without editing exclude.txt.
     #[clap(verbatim_doc_comment)]
     #[arg(long)]
-    pub keep_special_chars: bool,
+    pub no_exclude: bool,
 
-    /// Keep Unicode (more precisely, non-ASCII) characters.
+    /// Don't take the lock preventing concurrent runs.
     ///
-    /// When not set, convert unicode characters to their closest ASCII
-    /// counterparts using <https://crates.io/crates/unidecode>.
+    /// Runs renaming files hold a lock file in your config directory, so that
+    /// a second run started meanwhile fails right away instead of racing on
+    /// the same files and history. A lock left by a run that crashed is taken
+    /// over. Use this flag to run anyway, e.g. on unrelated directories.
+    /// Dry runs and checks never take the lock.
     #[clap(verbatim_doc_comment)]
     #[arg(long)]
-    pub keep_unicode: bool,
+    pub no_lock: bool,
+
+    /// Write a report of the run to the given path, in JSON.
+    ///
+    /// The report contains the options used, the path to the history file
+    /// (if one was written), the number of files per operation and
+    /// the errors that happened.
+    /// Unlike the history file, it is not meant to be used for reverting.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, value_name = "PATH")]
+    pub report: Option<PathBuf>,
+
+    /// Print nothing but a summary of the run, in JSON, on stdout.
+    ///
+    /// The summary is a single JSON object, printed after the run, with the
+    /// fields:
+    ///   - `history_file`: The path to the history file, or null.
+    ///   - `exit_status`: 0 if the run succeeded, 1 otherwise.
+    ///   - `visited`, `renamed`, `copied`, `skipped`, `backed_up`,
+    ///     `overwritten`, `locked` and `read_only`: The number of files per
+    ///     operation.
+    ///   - `conflicts`: The number of conflicts, however resolved.
+    ///   - `errors`: The errors, each with a `path`, a `category` and a
+    ///     `message`.
+    ///   - `error_counts`: The number of errors per category (e.g.
+    ///     `missing_file`, `invalid_unicode` or `rename_failed`).
+    ///
+    /// Lines of changes are not printed. Errors and warnings still are, to
+    /// stderr.
+    #[clap(verbatim_doc_comment)]
+    #[arg(
+        long,
+        conflicts_with_all = ["dry_run", "check", "check_conflicts", "changed_only", "verbose", "report_conforming", "line_format", "legend"]
+    )]
+    pub summary_json: bool,
+
+    /// Show printed paths relative to this directory.
+    ///
+    /// Every file given as argument must be within it, otherwise nothing is
+    /// done and an error is reported.
+    /// Only what is printed is affected: the history file and the report
+    /// still record absolute paths, as does --json.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, value_name = "DIR")]
+    pub base: Option<PathBuf>,
+
+    /// Only print the changes, then how many files changed.
+    ///
+    /// Skipped conflicts, locked files and warnings are not printed, unlike
+    /// renamings, symbolic links left and errors. A last line tells how many
+    /// of the visited files (excluded ones included) were renamed.
+    /// The history file is unaffected.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, conflicts_with = "report_conforming")]
+    pub changed_only: bool,
+
+    /// Print more details about each renaming.
+    ///
+    /// Under each `(d)` line, the filename is shown again with the characters
+    /// that changed highlighted, which helps noticing subtle changes like
+    /// casing or separator swaps.
+    #[clap(verbatim_doc_comment)]
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Don't use colors in the output.
+    ///
+    /// Setting the NO_COLOR environment variable does the same.
+    /// With --verbose, the characters that changed are then shown as
+    /// `[-removed-]{+added+}`.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub no_color: bool,
+
+    /// Explain the codes of the operations before the run, and count them after.
+    ///
+    /// Before the run, each code (e.g. "d" for a renaming) is printed with
+    /// its meaning and color. After the run, the number of files per
+    /// operation is printed with the same colors.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, conflicts_with_all = ["dry_run", "check", "check_conflicts"])]
+    pub legend: bool,
+
+    /// Print the lines of changes following this template.
+    ///
+    /// The placeholders {op}, {from} and {to} are replaced by the code of the
+    /// operation (e.g. "d" for a renaming) and the paths before and after the
+    /// change.
+    /// Only applies to the lines printed, the history file keeps the usual
+    /// format so that the run can be reverted. Errors and warnings are printed
+    /// as usual as well.
+    ///
+    /// Example: --line-format '{op};{from};{to}'
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, value_name = "TEMPLATE")]
+    pub line_format: Option<String>,
+
+    /// Print how long the steps of the run took, to stderr.
+    ///
+    /// Once the run is over, the time spent listing the content of
+    /// directories, computing formatted names and renaming files is printed,
+    /// as well as the total time, e.g. to find out what slows down big runs.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub timings: bool,
+
+    /// Print the effective options on one line before the run, to stderr.
+    ///
+    /// These are the naming convention, the keep flags, whether the run is
+    /// recursive and the exclude file used (or "none" with --no-exclude),
+    /// once the command line and the configuration file are combined, so
+    /// that captured output tells how it was produced.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub echo_config: bool,
+
+    /// Don't rename anything, only print what would be done.
+    ///
+    /// Nothing is asked either: conflicts and errors are printed as such.
+    /// Neither a history file nor a report is written.
+    /// Note that files within renamed directories are printed with their
+    /// current parent directory.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// With --dry-run, print one JSON object per line and per file instead.
+    ///
+    /// Each object has the fields:
+    ///   - `from`: The current path of the file.
+    ///   - `to`: The path it would be renamed into, or null.
+    ///   - `decision`: One of `rename`, `keep`, `exclude`, `conflict` or `error`.
+    ///   - `reason`: Why the decision was taken, absent for plain renames.
+    ///     For excluded files, it names the responsible exclude pattern.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, requires = "dry_run")]
+    pub json: bool,
+
+    /// With --dry-run, also check that the run could be reverted.
+    ///
+    /// Each renaming is written as it would be in the history file, then
+    /// parsed back the way `fmtna revert` would. Renamings whose line doesn't
+    /// give back the same paths (e.g. because a new filename contains " -> "
+    /// or a line break) are reported, then the program exits with a non-zero
+    /// status if there is any.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, requires = "dry_run")]
+    pub verify_revert: bool,
+
+    /// Don't rename anything, only check that all filenames conform.
+    ///
+    /// Lists the files that would be renamed (or couldn't be), then exits
+    /// with a non-zero status if there is any, like a linter would.
+    /// Useful in CI.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, conflicts_with = "dry_run")]
+    pub check: bool,
+
+    /// Don't rename anything, only list the files whose new name is taken.
+    ///
+    /// Each conflict is printed as `<path> -> <existing path>`, then the
+    /// program exits with a non-zero status if there is any.
+    /// Useful to choose how to resolve conflicts before the actual run.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, conflicts_with_all = ["dry_run", "check"])]
+    pub check_conflicts: bool,
+
+    /// Review the renamings in your editor before they are done.
+    ///
+    /// The renamings are planned first, then written to a temporary file as
+    /// `<path> -> <new path>` lines, which is opened with the editor of the
+    /// config file (it defaults to "vi").
+    /// Once the editor closed, exactly the renamings left in the file are
+    /// done: delete a line to skip its renaming, or change the new path to
+    /// rename into something else (within the same directory).
+    /// The edited plan is checked before anything is renamed, and the run
+    /// fails without renaming anything if it is invalid.
+    /// Like with --dry-run, files within renamed directories are listed with
+    /// their current parent directory, and are renamed first.
+    #[clap(verbatim_doc_comment)]
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "dry_run", "check", "check_conflicts", "output_dir", "from_listing", "legend", "summary_json"
+        ]
+    )]
+    pub edit_plan: bool,
+
+    /// Print a script doing the renamings instead of doing them.
+    ///
+    /// The renamings are planned like with --dry-run, then printed as `mv`
+    /// commands for `sh`, or `Move-Item` commands for `ps1` (PowerShell),
+    /// with paths quoted so that they are taken literally.
+    /// Files within renamed directories are renamed first, with their current
+    /// parent directory. Conflicts and errors are not part of the script,
+    /// they are printed to stderr.
+    /// Nothing is renamed, and no history file is written.
+    #[clap(verbatim_doc_comment)]
+    #[arg(
+        long,
+        value_enum,
+        value_name = "SHELL",
+        conflicts_with_all = [
+            "dry_run", "check", "check_conflicts", "edit_plan", "output_dir", "from_listing",
+            "legend", "summary_json"
+        ]
+    )]
+    pub emit_script: Option<ScriptShell>,
+
+    /// Print the renamings as a diff like git's instead of doing them.
+    ///
+    /// The renamings are planned like with --dry-run, then printed in the
+    /// format git uses for renamed files, sorted by path:
+    ///   diff --git a/<path> b/<new path>
+    ///   similarity index 100%
+    ///   rename from <path>
+    ///   rename to <new path>
+    /// which is handy to review them, e.g. in a pull request. New paths are
+    /// the final ones, within the renamed parent directory if any. Paths are
+    /// relative to --base if given.
+    /// Conflicts and errors are not part of the diff, they are printed to
+    /// stderr. Nothing is renamed, and no history file is written.
+    #[clap(verbatim_doc_comment)]
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "dry_run", "check", "check_conflicts", "edit_plan", "emit_script", "output_dir",
+            "from_listing", "legend", "summary_json"
+        ]
+    )]
+    pub emit_diff: bool,
+
+    /// Don't rename anything, only list the files excluded by a pattern.
+    ///
+    /// Files are visited like in a run, and each excluded one is printed as a
+    /// JSON object on its own line, with the fields:
+    ///   - `path`: The path of the file.
+    ///   - `pattern`: The exclude pattern matching it.
+    ///
+    /// Excluded directories are not recursed into, like in a run. Useful to
+    /// check exclude patterns over a real tree (see also `exclude test`).
+    #[clap(verbatim_doc_comment)]
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "dry_run", "check", "check_conflicts", "edit_plan", "emit_script", "emit_diff",
+            "output_dir", "from_listing", "no_exclude", "legend", "summary_json"
+        ]
+    )]
+    pub list_excluded: bool,
+
+    /// Write the renamings to FILE instead of doing them, to apply them later.
+    ///
+    /// The renamings are planned like with --dry-run, then written as
+    /// `<path> -> <new path>` lines, like with --edit-plan, with absolute
+    /// paths. Nothing is renamed, and no history file is written.
+    /// Apply the plan with `fmtna apply-plan FILE`, e.g. during a maintenance
+    /// window. The plan may be edited in between.
+    #[clap(verbatim_doc_comment)]
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = [
+            "dry_run", "check", "check_conflicts", "edit_plan", "emit_script", "emit_diff",
+            "list_excluded", "output_dir", "from_listing", "legend", "summary_json"
+        ]
+    )]
+    pub plan_out: Option<PathBuf>,
+
+    /// Also list the files already following the naming convention.
+    ///
+    /// Each of them is printed as a `(=)` line, e.g. for compliance auditing.
+    /// Files whose name only differs by case on a case-insensitive filesystem
+    /// (i.e. on Windows) are not listed.
+    /// Works with --dry-run and --check as well. Nothing is written to the
+    /// history file.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long)]
+    pub report_conforming: bool,
+
+    /// The directory where to write the history file of the run.
+    ///
+    /// Defaults to the history directory in your config directory.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, value_name = "DIR")]
+    pub history_dir: Option<PathBuf>,
+
+    /// Write the history file in the .fmtna-history directory of the current
+    /// directory.
+    ///
+    /// The directory is created if needed.
+    /// Useful to keep the history of a project with the project.
+    /// Such history files can be reverted with `fmtna revert --local --last`.
+    #[clap(verbatim_doc_comment)]
+    #[arg(long, conflicts_with = "history_dir")]
+    pub local_history: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+/// An order in which to visit files, see [DefaultArgs::order].
+pub enum TraversalOrder {
+    /// Depth-first.
+    #[default]
+    Depth,
+    /// Breadth-first.
+    Breadth,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+/// The case of extensions, see [DefaultArgs::extension_case].
+pub enum ExtensionCase {
+    /// Leave extensions as is.
+    #[default]
+    Keep,
+    /// Lowercase extensions.
+    Lower,
+    /// Uppercase extensions.
+    Upper,
+}
+
+impl ExtensionCase {
+    /// Returns `ext` in this case.
+    pub fn apply<'a>(&self, ext: &'a str) -> Cow<'a, str> {
+        match self {
+            ExtensionCase::Keep => Cow::Borrowed(ext),
+            ExtensionCase::Lower => Cow::Owned(ext.to_lowercase()),
+            ExtensionCase::Upper => Cow::Owned(ext.to_uppercase()),
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+/// A shell to write scripts for, see [DefaultArgs::emit_script].
+pub enum ScriptShell {
+    /// POSIX shell.
+    Sh,
+    /// PowerShell.
+    Ps1,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+/// What to do on conflicts, see [DefaultArgs::on_conflict].
+pub enum OnConflict {
+    /// Ask for each conflict.
+    #[default]
+    Ask,
+    /// Leave the file as is.
+    Skip,
+    /// Back up the existing file, then rename.
+    Backup,
+    /// Rename, replacing the existing file.
+    Overwrite,
+    /// Keep the file modified last, back up the other one.
+    Newest,
+    /// Keep the largest file, back up the other one.
+    Largest,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+/// A naming convention to use for a specific kind of files,
+/// see [DefaultArgs::convention_for].
+pub struct ConventionFor {
+    /// The kind of files concerned.
+    pub kind: FileKind,
+
+    /// The naming convention to use for them.
+    pub naming_convention: NamingConvention,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+/// A kind of files, see [ConventionFor].
+pub enum FileKind {
+    /// Directories.
+    Dir,
+    /// Files with the given extension (without the dot).
+    Extension(String),
+}
+
+impl FromStr for ConventionFor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, naming_convention) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Expected KIND=CONVENTION, got {}.", s))?;
+
+        let kind = match kind {
+            "dir" => FileKind::Dir,
+            _ => match kind.strip_prefix('.') {
+                Some(ext) if !ext.is_empty() => FileKind::Extension(ext.to_string()),
+                _ => {
+                    return Err(format!(
+                        "Invalid kind {}, expected `dir` or an extension like `.md`.",
+                        kind
+                    ))
+                }
+            },
+        };
+        let naming_convention = NamingConvention::from_str(naming_convention, false)
+            .map_err(|_| format!("Invalid naming convention {}.", naming_convention))?;
+
+        Ok(Self {
+            kind,
+            naming_convention,
+        })
+    }
 }
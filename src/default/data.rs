@@ -1,83 +1,320 @@
-use super::cli::DefaultArgs;
+use super::cli::{
+    ConventionFor, DefaultArgs, ExtensionCase, OnConflict, ScriptShell, TraversalOrder,
+};
 use crate::cfg::Cfg;
-use crate::naming_conventions::NamingConvention;
-use crate::paths::EXCLUDE_FILE_PATH;
+use crate::exclude::{invalid_patterns_message, read_exclude_file, ExcludePattern};
+use crate::naming_conventions::{FormatOptions, NamingConvention};
+use crate::paths::{
+    BACKUP_DIR_PATH, EXCLUDE_FILE_PATH, HISTORY_DIR_PATH, LOCAL_HISTORY_DIR_NAME, LOCK_FILE_PATH,
+    RESUME_DIR_PATH,
+};
+use crate::rename::RenamePattern;
+use crate::utils::RetryPolicy;
 use anyhow::anyhow;
 use anyhow::Context;
+use lazy_static::lazy_static;
 use path_absolutize::*;
 use regex::Regex;
+use serde::Serialize;
+use std::collections::{HashSet, VecDeque};
+use std::env;
 use std::ffi::OsStr;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug)]
+lazy_static! {
+    /// Matches the placeholders of [DefaultArgs::line_format], capturing their name.
+    pub static ref LINE_FORMAT_PLACEHOLDER_RE: Regex = Regex::new(r"\{(\w*)\}").unwrap();
+}
+
+/// The options the default subcommand runs with, i.e. its CLI arguments
+/// aggregated with the configuration, see [resolve_options].
+#[derive(Debug, Serialize)]
 pub struct Data {
-    /// Same as [Cli::files](crate::cli::Cli::files)
-    pub files: Vec<PathBuf>,
+    /// Same as [DefaultArgs::files](crate::default::DefaultArgs::files)
+    #[serde(skip)]
+    pub files: VecDeque<PathBuf>,
+
+    /// The ancestor directories added to the files by
+    /// [DefaultArgs::parents](crate::default::DefaultArgs::parents), i.e.
+    /// not to recurse into
+    #[serde(skip)]
+    pub ancestors: HashSet<PathBuf>,
+
+    /// The directories given as arguments not to rename, only to recurse
+    /// into, see [Data::rename_roots]
+    #[serde(skip)]
+    pub roots: HashSet<PathBuf>,
+
+    /// The paths listed in [DefaultArgs::from_listing](crate::default::DefaultArgs::from_listing),
+    /// if given
+    #[serde(skip)]
+    pub listing: Option<Vec<PathBuf>>,
+
+    /// Aggregation of [DefaultArgs::convention](crate::default::DefaultArgs::convention)
+    /// and the configuration file.
+    #[serde(flatten)]
+    pub format_options: FormatOptions,
+
+    /// Same as [DefaultArgs::convention_for](crate::default::DefaultArgs::convention_for)
+    pub convention_for: Vec<ConventionFor>,
+
+    /// Same as [DefaultArgs::from](crate::default::DefaultArgs::from)
+    pub from: Option<NamingConvention>,
+
+    /// Same as [DefaultArgs::by_mime](crate::default::DefaultArgs::by_mime)
+    pub by_mime: Option<String>,
+
+    /// How to build the new names, instead of formatting, when run by the
+    /// rename subcommand
+    #[serde(skip)]
+    pub rename_pattern: Option<RenamePattern>,
 
-    /// Same as [Cli::naming_convention](crate::cli::Cli::naming_convention)
-    pub naming_convention: NamingConvention,
+    /// Same as [DefaultArgs::extension_case](crate::default::DefaultArgs::extension_case)
+    pub extension_case: ExtensionCase,
 
-    /// Same as [Cli::recursive](crate::cli::Cli::recursive)
+    /// Same as [DefaultArgs::no_markers](crate::default::DefaultArgs::no_markers)
+    pub no_markers: bool,
+
+    /// Same as [DefaultArgs::recursive](crate::default::DefaultArgs::recursive)
     pub recursive: bool,
 
-    /// Same as [Cli::keep_dots](crate::cli::Cli::keep_dots)
-    pub keep_dots: bool,
+    /// Aggregation of the negation of
+    /// [DefaultArgs::no_rename_roots](crate::default::DefaultArgs::no_rename_roots)
+    /// and the configuration file.
+    pub rename_roots: bool,
+
+    /// Same as [DefaultArgs::order](crate::default::DefaultArgs::order)
+    pub order: TraversalOrder,
+
+    /// Same as [DefaultArgs::min_length](crate::default::DefaultArgs::min_length)
+    pub min_length: Option<usize>,
+
+    /// Same as [DefaultArgs::max_length](crate::default::DefaultArgs::max_length)
+    pub max_length: Option<usize>,
+
+    /// Same as [DefaultArgs::truncate_hash](crate::default::DefaultArgs::truncate_hash)
+    pub truncate_hash: bool,
+
+    /// Same as [DefaultArgs::limit](crate::default::DefaultArgs::limit)
+    pub limit: Option<usize>,
+
+    /// Same as [DefaultArgs::max_conflicts](crate::default::DefaultArgs::max_conflicts)
+    pub max_conflicts: Option<usize>,
+
+    /// Same as [DefaultArgs::on_conflict](crate::default::DefaultArgs::on_conflict)
+    pub on_conflict: OnConflict,
+
+    /// Aggregation of [DefaultArgs::retry](crate::default::DefaultArgs::retry)
+    /// and [DefaultArgs::retry_delay](crate::default::DefaultArgs::retry_delay).
+    #[serde(flatten)]
+    pub retry_policy: RetryPolicy,
+
+    /// Same as [DefaultArgs::skip_locked](crate::default::DefaultArgs::skip_locked)
+    pub skip_locked: bool,
+
+    /// Same as [DefaultArgs::ignore_readonly](crate::default::DefaultArgs::ignore_readonly)
+    pub ignore_readonly: bool,
+
+    /// Same as [DefaultArgs::yes_to_errors](crate::default::DefaultArgs::yes_to_errors)
+    pub yes_to_errors: bool,
+
+    /// Same as [DefaultArgs::transactional](crate::default::DefaultArgs::transactional)
+    pub transactional: bool,
+
+    /// Same as [DefaultArgs::output_dir](crate::default::DefaultArgs::output_dir), but absolute
+    pub output_dir: Option<PathBuf>,
+
+    /// Same as [DefaultArgs::git](crate::default::DefaultArgs::git)
+    pub git: bool,
+
+    /// Same as [DefaultArgs::leave_symlink](crate::default::DefaultArgs::leave_symlink)
+    pub leave_symlink: bool,
+
+    /// Same as [DefaultArgs::strict_ascii](crate::default::DefaultArgs::strict_ascii)
+    pub strict_ascii: bool,
+
+    /// Same as [DefaultArgs::allow_lossy_unicode](crate::default::DefaultArgs::allow_lossy_unicode)
+    pub allow_lossy_unicode: bool,
+
+    /// Same as [DefaultArgs::base](crate::default::DefaultArgs::base), but absolute
+    pub base: Option<PathBuf>,
+
+    /// Same as [DefaultArgs::changed_only](crate::default::DefaultArgs::changed_only)
+    pub changed_only: bool,
+
+    /// Same as [DefaultArgs::verbose](crate::default::DefaultArgs::verbose)
+    pub verbose: bool,
+
+    /// Same as [DefaultArgs::no_color](crate::default::DefaultArgs::no_color)
+    pub no_color: bool,
+
+    /// Same as [DefaultArgs::legend](crate::default::DefaultArgs::legend)
+    pub legend: bool,
+
+    /// Same as [DefaultArgs::line_format](crate::default::DefaultArgs::line_format)
+    pub line_format: Option<String>,
+
+    /// Same as [DefaultArgs::timings](crate::default::DefaultArgs::timings)
+    pub timings: bool,
+
+    /// Same as [DefaultArgs::echo_config](crate::default::DefaultArgs::echo_config)
+    pub echo_config: bool,
+
+    /// Same as [DefaultArgs::dry_run](crate::default::DefaultArgs::dry_run)
+    pub dry_run: bool,
+
+    /// Same as [DefaultArgs::json](crate::default::DefaultArgs::json)
+    pub json: bool,
 
-    /// Same as [Cli::keep_special_chars](crate::cli::Cli::keep_special_chars)
-    pub keep_special_chars: bool,
+    /// Same as [DefaultArgs::verify_revert](crate::default::DefaultArgs::verify_revert)
+    pub verify_revert: bool,
 
-    /// Same as [Cli::keep_unicode](crate::cli::Cli::keep_unicode)
-    pub keep_unicode: bool,
+    /// Same as [DefaultArgs::check](crate::default::DefaultArgs::check)
+    pub check: bool,
 
-    pub exclude_regexes: Vec<Regex>,
+    /// Same as [DefaultArgs::check_conflicts](crate::default::DefaultArgs::check_conflicts)
+    pub check_conflicts: bool,
+
+    /// Same as [DefaultArgs::edit_plan](crate::default::DefaultArgs::edit_plan)
+    pub edit_plan: bool,
+
+    /// Same as [DefaultArgs::emit_script](crate::default::DefaultArgs::emit_script)
+    pub emit_script: Option<ScriptShell>,
+
+    /// Same as [DefaultArgs::emit_diff](crate::default::DefaultArgs::emit_diff)
+    pub emit_diff: bool,
+
+    /// Same as [DefaultArgs::list_excluded](crate::default::DefaultArgs::list_excluded)
+    pub list_excluded: bool,
+
+    /// Same as [DefaultArgs::plan_out](crate::default::DefaultArgs::plan_out)
+    pub plan_out: Option<PathBuf>,
+
+    /// The plan file to apply instead of formatting, when run by the
+    /// apply-plan subcommand
+    #[serde(skip)]
+    pub apply_plan: Option<PathBuf>,
+
+    /// The editor to open the plan with, see [DefaultArgs::edit_plan](crate::default::DefaultArgs::edit_plan)
+    #[serde(skip)]
+    pub editor: String,
+
+    /// Same as [DefaultArgs::report_conforming](crate::default::DefaultArgs::report_conforming)
+    pub report_conforming: bool,
+
+    /// Same as [DefaultArgs::report](crate::default::DefaultArgs::report)
+    pub report: Option<PathBuf>,
+
+    /// Same as [DefaultArgs::summary_json](crate::default::DefaultArgs::summary_json)
+    pub summary_json: bool,
+
+    /// Same as [DefaultArgs::no_exclude](crate::default::DefaultArgs::no_exclude)
+    pub no_exclude: bool,
+
+    /// Same as [DefaultArgs::no_lock](crate::default::DefaultArgs::no_lock)
+    pub no_lock: bool,
+
+    /// The patterns of the files not to rename, see
+    /// [DefaultArgs::no_exclude](crate::default::DefaultArgs::no_exclude).
+    #[serde(skip)]
+    pub exclude_patterns: Vec<ExcludePattern>,
+
+    /// The exclude file [Data::exclude_patterns] were read from, if any.
+    #[serde(skip)]
+    pub exclude_file_path: Option<PathBuf>,
+
+    /// The directory where the history file of the run is written.
+    #[serde(skip)]
+    pub history_dir_path: PathBuf,
+
+    /// The directory where the resume file of the run is written, if any.
+    #[serde(skip)]
+    pub resume_dir_path: PathBuf,
+
+    /// The directory where conflicting files are backed up.
+    #[serde(skip)]
+    pub backup_dir_path: PathBuf,
+
+    /// The lock file preventing concurrent runs, see
+    /// [DefaultArgs::no_lock](crate::default::DefaultArgs::no_lock).
+    #[serde(skip)]
+    pub lock_file_path: PathBuf,
+}
+
+/// Returns the options the default subcommand would run with, once `cli` and
+/// `cfg` are aggregated, without running anything.
+///
+/// Serialized, these are the same as the ones written in the report of a
+/// run (see [DefaultArgs::report](crate::default::DefaultArgs::report)), e.g.
+/// for embedders to introspect the effective settings.
+///
+/// # Parameters
+///
+/// - `cli`: The CLI arguments.
+/// - `cfg`: The configuration values.
+///
+/// # Errors
+///
+/// Fails when the engine itself would fail to be created, e.g. because the
+/// exclude file contains invalid patterns.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use fmtna::cfg::Cfg;
+/// use fmtna::default::{resolve_options, DefaultArgs};
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let cfg = Cfg {
+///     keep_dots: true,
+///     ..Default::default()
+/// };
+/// let options = resolve_options(DefaultArgs::default(), cfg)?;
+/// assert!(options.format_options.keep_dots);
+/// # Ok(())
+/// # }
+/// ```
+pub fn resolve_options(cli: DefaultArgs, cfg: Cfg) -> anyhow::Result<Data> {
+    Data::new(cli, cfg)
 }
 
 impl Data {
+    /// Aggregates `cli` and `cfg`, see [resolve_options].
     pub fn new(cli: DefaultArgs, cfg: Cfg) -> anyhow::Result<Self> {
-        let naming_convention = cli.naming_convention.unwrap_or(cfg.naming_convention);
-        let recursive = cli.recursive || cfg.recursive;
-        let keep_dots = cli.keep_dots || cfg.keep_dots;
-        let keep_special_chars = cli.keep_special_chars || cfg.keep_special_chars;
-        let keep_unicode = cli.keep_unicode || cfg.keep_unicode;
+        let format_options = FormatOptions::new(cli.convention, &cfg);
+        let recursive = cfg.resolve_recursive(cli.recursive);
+        let rename_roots = cfg.resolve_recurse_renames_roots(cli.no_rename_roots);
 
         // NOTE: We store regexes into a vec, but the exclude file can be so big
         // that the program's memory will not suffice.
         // Furthermore, large number of patterns may negatively affect performance,
         // but not sure if it will ever by a practical concern, so keep the simple
         // way of doing things for now.
-        let mut exclude_regexes: Vec<Regex> = vec![];
-        let exclude_file_path = &*EXCLUDE_FILE_PATH;
-        if exclude_file_path.exists() {
-            let file = File::open(exclude_file_path.clone())?;
-            let reader = BufReader::new(file);
-            for (line_no, line) in reader.lines().enumerate() {
-                let line = line?;
-
-                if line.is_empty() || line.starts_with("//") {
-                    continue;
-                }
+        let exclude_file_path = (!cli.no_exclude).then(|| EXCLUDE_FILE_PATH.clone());
+        let exclude_patterns = match exclude_file_path {
+            Some(ref exclude_file_path) => load_exclude_patterns(exclude_file_path)?,
+            None => vec![],
+        };
 
-                match Regex::new(&line) {
-                    Ok(exclude_re) => {
-                        exclude_regexes.push(exclude_re);
-                    }
-                    Err(_) => {
-                        return Err(anyhow!(
-                            "Exclude pattern {} is invalid (in {}, line {}).",
-                            line,
-                            exclude_file_path.to_string_lossy(),
-                            line_no
-                        ));
-                    }
-                }
+        // Expand glob patterns.
+        let mut cli_files = cli.files;
+        for pattern in &cli.glob {
+            let matches = glob::glob(pattern)
+                .with_context(|| format!("Invalid glob pattern '{}'.", pattern))?;
+            for m in matches {
+                cli_files.push(
+                    m.with_context(|| format!("Failed to expand glob pattern '{}'.", pattern))?,
+                );
             }
         }
 
+        for input_file in &cli.input_file {
+            cli_files.extend(read_input_file(input_file)?);
+        }
+
         // Absolutize paths.
-        let files: anyhow::Result<Vec<_>> = cli
-            .files
+        let files: anyhow::Result<Vec<_>> = cli_files
             .iter()
             .map(|f| -> anyhow::Result<PathBuf> {
                 let new_f = f.absolutize().with_context(|| {
@@ -87,27 +324,252 @@ impl Data {
             })
             .collect();
         let mut files = files?;
+
+        let base = match cli.base {
+            Some(base) => {
+                let base = base
+                    .absolutize()
+                    .with_context(|| {
+                        format!("Failed to absolutize path '{}'.", base.to_string_lossy())
+                    })?
+                    .into_owned();
+                if let Some(f) = files.iter().find(|f| !f.starts_with(&base)) {
+                    return Err(anyhow!(format!(
+                        "{} is not within the base directory {}.",
+                        f.to_string_lossy(),
+                        base.to_string_lossy()
+                    )));
+                }
+                Some(base)
+            }
+            None => None,
+        };
+
+        let output_dir = cli
+            .output_dir
+            .map(|dir| {
+                dir.absolutize()
+                    .with_context(|| {
+                        format!("Failed to absolutize path '{}'.", dir.to_string_lossy())
+                    })
+                    .map(|dir| dir.into_owned())
+            })
+            .transpose()?;
+
+        let roots = if recursive && !rename_roots {
+            files
+                .iter()
+                .filter(|f| f.is_dir() && !f.is_symlink())
+                .cloned()
+                .collect()
+        } else {
+            HashSet::new()
+        };
+
+        // Add ancestor directories, up to the base directory (or the current
+        // directory) excluded.
+        let mut ancestors = HashSet::new();
+        if cli.parents {
+            let root = match base {
+                Some(ref base) => base.clone(),
+                None => {
+                    env::current_dir().with_context(|| "Failed to get the current directory.")?
+                }
+            };
+            for f in &files {
+                for ancestor in f.ancestors().skip(1) {
+                    if ancestor == root || !ancestor.starts_with(&root) {
+                        break;
+                    }
+                    if !files.iter().any(|f| f == ancestor) {
+                        ancestors.insert(ancestor.to_path_buf());
+                    }
+                }
+            }
+            files.extend(ancestors.iter().cloned());
+        }
+
         // Sort file paths by length so that files appear before (seeing the
         // vector as a stack) their parent directories. Otherwise, a directory name
         // may change before files within it are processed, making these
         // file paths outdated and failing to rename them.
         files.sort_by_key(|p| AsRef::<OsStr>::as_ref(p).len());
+        // Breadth-first takes files from the front (seeing the vector as a queue).
+        if cli.order == TraversalOrder::Breadth {
+            files.reverse();
+        }
+
+        if let Some(ref line_format) = cli.line_format {
+            let placeholders = LINE_FORMAT_PLACEHOLDER_RE.captures_iter(line_format);
+            for placeholder in placeholders {
+                if !["op", "from", "to"].contains(&&placeholder[1]) {
+                    return Err(anyhow!(format!(
+                        "Unknown placeholder {} in line format, expected {{op}}, {{from}} or {{to}}.",
+                        &placeholder[0]
+                    )));
+                }
+            }
+        }
+
+        // Resumed runs pick up the queue where the interrupted run left it.
+        let files = match cli.resume {
+            Some(ref resume_path) => fs::read_to_string(resume_path)
+                .with_context(|| {
+                    format!(
+                        "Failed to read resume file ({}).",
+                        resume_path.to_string_lossy()
+                    )
+                })?
+                .lines()
+                .map(PathBuf::from)
+                .collect(),
+            None => VecDeque::from(files),
+        };
+
+        let history_dir_path = if cli.local_history {
+            env::current_dir()
+                .with_context(|| "Failed to get the current directory.")?
+                .join(LOCAL_HISTORY_DIR_NAME)
+        } else {
+            cli.history_dir.unwrap_or(HISTORY_DIR_PATH.clone())
+        };
+
+        let listing = cli.from_listing.as_deref().map(read_listing).transpose()?;
 
         Ok(Data {
             files,
-            naming_convention,
+            ancestors,
+            roots,
+            listing,
+            format_options,
+            convention_for: cli.convention_for,
+            from: cli.from,
+            by_mime: cli.by_mime,
+            rename_pattern: None,
+            extension_case: cli.extension_case,
+            no_markers: cli.no_markers,
             recursive,
-            keep_dots,
-            keep_special_chars,
-            keep_unicode,
-            exclude_regexes,
+            rename_roots,
+            order: cli.order,
+            min_length: cli.min_length,
+            max_length: cli.max_length,
+            truncate_hash: cli.truncate_hash,
+            limit: cli.limit,
+            max_conflicts: cli.max_conflicts,
+            on_conflict: cli.on_conflict,
+            retry_policy: RetryPolicy {
+                retries: cli.retry,
+                delay_ms: cli.retry_delay,
+            },
+            skip_locked: cli.skip_locked,
+            ignore_readonly: cli.ignore_readonly,
+            yes_to_errors: cli.yes_to_errors,
+            transactional: cli.transactional,
+            output_dir,
+            git: cli.git,
+            leave_symlink: cli.leave_symlink,
+            strict_ascii: cli.strict_ascii,
+            allow_lossy_unicode: cli.allow_lossy_unicode,
+            base,
+            changed_only: cli.changed_only,
+            verbose: cli.verbose,
+            no_color: cli.no_color,
+            legend: cli.legend,
+            line_format: cli.line_format,
+            timings: cli.timings,
+            echo_config: cli.echo_config,
+            dry_run: cli.dry_run,
+            json: cli.json,
+            verify_revert: cli.verify_revert,
+            check: cli.check,
+            check_conflicts: cli.check_conflicts,
+            edit_plan: cli.edit_plan,
+            emit_script: cli.emit_script,
+            emit_diff: cli.emit_diff,
+            list_excluded: cli.list_excluded,
+            plan_out: cli.plan_out,
+            apply_plan: None,
+            editor: cfg.editor,
+            report_conforming: cli.report_conforming,
+            report: cli.report,
+            summary_json: cli.summary_json,
+            no_exclude: cli.no_exclude,
+            no_lock: cli.no_lock,
+            exclude_patterns,
+            exclude_file_path,
+            history_dir_path,
+            resume_dir_path: RESUME_DIR_PATH.clone(),
+            backup_dir_path: BACKUP_DIR_PATH.clone(),
+            lock_file_path: LOCK_FILE_PATH.clone(),
         })
     }
 }
 
+/// Reads the exclude file at `exclude_file_path`, if it exists, and compiles
+/// its patterns.
+///
+/// # Errors
+///
+/// Fails if the file can't be read or holds invalid patterns.
+fn load_exclude_patterns(exclude_file_path: &Path) -> anyhow::Result<Vec<ExcludePattern>> {
+    if !exclude_file_path.exists() {
+        return Ok(vec![]);
+    }
+
+    let (patterns, invalid_patterns) = read_exclude_file(exclude_file_path).with_context(|| {
+        format!(
+            "Failed to read exclude file ({}).",
+            exclude_file_path.to_string_lossy()
+        )
+    })?;
+    if !invalid_patterns.is_empty() {
+        return Err(anyhow!(invalid_patterns_message(
+            exclude_file_path,
+            &invalid_patterns
+        )));
+    }
+
+    Ok(patterns)
+}
+
+/// Reads the paths listed in `input_file`, see
+/// [DefaultArgs::input_file](crate::default::DefaultArgs::input_file).
+///
+/// # Errors
+///
+/// Fails if the file can't be read.
+fn read_input_file(input_file: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let content = fs::read_to_string(input_file).with_context(|| {
+        format!(
+            "Failed to read input file ({}).",
+            input_file.to_string_lossy()
+        )
+    })?;
+
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// Returns the paths listed in `listing`, see [DefaultArgs::from_listing].
+fn read_listing(listing: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let content = fs::read_to_string(listing)
+        .with_context(|| format!("Failed to read listing ({}).", listing.to_string_lossy()))?;
+
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cli::ConventionArgs;
+    use crate::naming_conventions::NamingConvention;
 
     #[derive(Debug)]
     struct TestCase {
@@ -123,11 +585,25 @@ mod tests {
             TestCase {
                 cli: DefaultArgs {
                     files: vec![],
-                    naming_convention: Some(NamingConvention::CamelCase),
+                    convention: ConventionArgs {
+                        naming_convention: Some(NamingConvention::CamelCase),
+                        keep_dots: true,
+                        keep_special_chars: true,
+                        keep_unicode: true,
+                        squeeze_whitespace: false,
+                        acronym_aware: false,
+                        unicode_words: false,
+                        trim_separators: false,
+                        format_dotfiles: false,
+                        normalize_dots: false,
+                        protect: vec![],
+                        preserve_dates: false,
+                        date_pattern: None,
+                        case_only: false,
+                        keep_case: false,
+                    },
                     recursive: true,
-                    keep_dots: true,
-                    keep_special_chars: true,
-                    keep_unicode: true,
+                    ..Default::default()
                 },
                 cfg: Cfg {
                     naming_convention: NamingConvention::SnakeCase,
@@ -136,26 +612,113 @@ mod tests {
                     keep_special_chars: false,
                     keep_unicode: false,
                     editor: String::from("vi"),
+                    acronyms: vec![],
+                    recurse_renames_roots: true,
+                    preserve_dates: false,
                 },
                 data: Data {
-                    files: vec![],
-                    naming_convention: NamingConvention::CamelCase,
+                    files: VecDeque::new(),
+                    ancestors: HashSet::new(),
+                    roots: HashSet::new(),
+                    listing: None,
+                    format_options: FormatOptions {
+                        naming_convention: NamingConvention::CamelCase,
+                        keep_dots: true,
+                        keep_special_chars: true,
+                        keep_unicode: true,
+                        squeeze_whitespace: false,
+                        acronym_aware: false,
+                        unicode_words: false,
+                        trim_separators: false,
+                        format_dotfiles: false,
+                        normalize_dots: false,
+                        protect: vec![],
+                        date_patterns: vec![],
+                        case_only: false,
+                        keep_case: false,
+                        acronyms: vec![],
+                    },
+                    convention_for: vec![],
+                    from: None,
+                    by_mime: None,
+                    rename_pattern: None,
+                    extension_case: ExtensionCase::Keep,
+                    no_markers: false,
                     recursive: true,
-                    keep_dots: true,
-                    keep_special_chars: true,
-                    keep_unicode: true,
-                    exclude_regexes: vec![],
+                    rename_roots: true,
+                    order: TraversalOrder::Depth,
+                    min_length: None,
+                    max_length: None,
+                    truncate_hash: false,
+                    limit: None,
+                    max_conflicts: None,
+                    on_conflict: OnConflict::Ask,
+                    retry_policy: RetryPolicy::default(),
+                    skip_locked: false,
+                    ignore_readonly: false,
+                    yes_to_errors: false,
+                    transactional: false,
+                    output_dir: None,
+                    git: false,
+                    leave_symlink: false,
+                    strict_ascii: false,
+                    allow_lossy_unicode: false,
+                    base: None,
+                    changed_only: false,
+                    verbose: false,
+                    no_color: false,
+                    legend: false,
+                    line_format: None,
+                    timings: false,
+                    echo_config: false,
+                    dry_run: false,
+                    json: false,
+                    verify_revert: false,
+                    check: false,
+                    check_conflicts: false,
+                    edit_plan: false,
+                    emit_script: None,
+                    emit_diff: false,
+                    list_excluded: false,
+                    plan_out: None,
+                    apply_plan: None,
+                    editor: String::from("vi"),
+                    report_conforming: false,
+                    report: None,
+                    summary_json: false,
+                    no_exclude: false,
+                    no_lock: false,
+                    exclude_patterns: vec![],
+                    exclude_file_path: Some(EXCLUDE_FILE_PATH.clone()),
+                    history_dir_path: HISTORY_DIR_PATH.clone(),
+                    resume_dir_path: RESUME_DIR_PATH.clone(),
+                    backup_dir_path: BACKUP_DIR_PATH.clone(),
+                    lock_file_path: LOCK_FILE_PATH.clone(),
                 },
             },
             // When option not defined via Cli, backup to Cfg
             TestCase {
                 cli: DefaultArgs {
                     files: vec![],
-                    naming_convention: None,
+                    convention: ConventionArgs {
+                        naming_convention: None,
+                        keep_dots: false,
+                        keep_special_chars: false,
+                        keep_unicode: false,
+                        squeeze_whitespace: false,
+                        acronym_aware: false,
+                        unicode_words: false,
+                        trim_separators: false,
+                        format_dotfiles: false,
+                        normalize_dots: false,
+                        protect: vec![],
+                        preserve_dates: false,
+                        date_pattern: None,
+                        case_only: false,
+                        keep_case: false,
+                    },
                     recursive: false,
-                    keep_dots: false,
-                    keep_special_chars: false,
-                    keep_unicode: false,
+                    ..Default::default()
                 },
                 cfg: Cfg {
                     naming_convention: NamingConvention::SnakeCase,
@@ -164,26 +727,113 @@ mod tests {
                     keep_special_chars: true,
                     keep_unicode: true,
                     editor: String::from("vi"),
+                    acronyms: vec![],
+                    recurse_renames_roots: true,
+                    preserve_dates: false,
                 },
                 data: Data {
-                    files: vec![],
-                    naming_convention: NamingConvention::SnakeCase,
+                    files: VecDeque::new(),
+                    ancestors: HashSet::new(),
+                    roots: HashSet::new(),
+                    listing: None,
+                    format_options: FormatOptions {
+                        naming_convention: NamingConvention::SnakeCase,
+                        keep_dots: false,
+                        keep_special_chars: true,
+                        keep_unicode: true,
+                        squeeze_whitespace: false,
+                        acronym_aware: false,
+                        unicode_words: false,
+                        trim_separators: false,
+                        format_dotfiles: false,
+                        normalize_dots: false,
+                        protect: vec![],
+                        date_patterns: vec![],
+                        case_only: false,
+                        keep_case: false,
+                        acronyms: vec![],
+                    },
+                    convention_for: vec![],
+                    from: None,
+                    by_mime: None,
+                    rename_pattern: None,
+                    extension_case: ExtensionCase::Keep,
+                    no_markers: false,
                     recursive: true,
-                    keep_dots: false,
-                    keep_special_chars: true,
-                    keep_unicode: true,
-                    exclude_regexes: vec![],
+                    rename_roots: true,
+                    order: TraversalOrder::Depth,
+                    min_length: None,
+                    max_length: None,
+                    truncate_hash: false,
+                    limit: None,
+                    max_conflicts: None,
+                    on_conflict: OnConflict::Ask,
+                    retry_policy: RetryPolicy::default(),
+                    skip_locked: false,
+                    ignore_readonly: false,
+                    yes_to_errors: false,
+                    transactional: false,
+                    output_dir: None,
+                    git: false,
+                    leave_symlink: false,
+                    strict_ascii: false,
+                    allow_lossy_unicode: false,
+                    base: None,
+                    changed_only: false,
+                    verbose: false,
+                    no_color: false,
+                    legend: false,
+                    line_format: None,
+                    timings: false,
+                    echo_config: false,
+                    dry_run: false,
+                    json: false,
+                    verify_revert: false,
+                    check: false,
+                    check_conflicts: false,
+                    edit_plan: false,
+                    emit_script: None,
+                    emit_diff: false,
+                    list_excluded: false,
+                    plan_out: None,
+                    apply_plan: None,
+                    editor: String::from("vi"),
+                    report_conforming: false,
+                    report: None,
+                    summary_json: false,
+                    no_exclude: false,
+                    no_lock: false,
+                    exclude_patterns: vec![],
+                    exclude_file_path: Some(EXCLUDE_FILE_PATH.clone()),
+                    history_dir_path: HISTORY_DIR_PATH.clone(),
+                    resume_dir_path: RESUME_DIR_PATH.clone(),
+                    backup_dir_path: BACKUP_DIR_PATH.clone(),
+                    lock_file_path: LOCK_FILE_PATH.clone(),
                 },
             },
             // A mix of options coming from Cli and others from Cfg
             TestCase {
                 cli: DefaultArgs {
                     files: vec![],
-                    naming_convention: Some(NamingConvention::CamelCase),
+                    convention: ConventionArgs {
+                        naming_convention: Some(NamingConvention::CamelCase),
+                        keep_dots: false,
+                        keep_special_chars: false,
+                        keep_unicode: true,
+                        squeeze_whitespace: false,
+                        acronym_aware: false,
+                        unicode_words: false,
+                        trim_separators: false,
+                        format_dotfiles: false,
+                        normalize_dots: false,
+                        protect: vec![],
+                        preserve_dates: false,
+                        date_pattern: None,
+                        case_only: false,
+                        keep_case: false,
+                    },
                     recursive: true,
-                    keep_dots: false,
-                    keep_special_chars: false,
-                    keep_unicode: true,
+                    ..Default::default()
                 },
                 cfg: Cfg {
                     naming_convention: NamingConvention::SnakeCase,
@@ -192,15 +842,88 @@ mod tests {
                     keep_special_chars: true,
                     keep_unicode: false,
                     editor: String::from("vi"),
+                    acronyms: vec![],
+                    recurse_renames_roots: true,
+                    preserve_dates: false,
                 },
                 data: Data {
-                    files: vec![],
-                    naming_convention: NamingConvention::CamelCase,
+                    files: VecDeque::new(),
+                    ancestors: HashSet::new(),
+                    roots: HashSet::new(),
+                    listing: None,
+                    format_options: FormatOptions {
+                        naming_convention: NamingConvention::CamelCase,
+                        keep_dots: false,
+                        keep_special_chars: true,
+                        keep_unicode: true,
+                        squeeze_whitespace: false,
+                        acronym_aware: false,
+                        unicode_words: false,
+                        trim_separators: false,
+                        format_dotfiles: false,
+                        normalize_dots: false,
+                        protect: vec![],
+                        date_patterns: vec![],
+                        case_only: false,
+                        keep_case: false,
+                        acronyms: vec![],
+                    },
+                    convention_for: vec![],
+                    from: None,
+                    by_mime: None,
+                    rename_pattern: None,
+                    extension_case: ExtensionCase::Keep,
+                    no_markers: false,
                     recursive: true,
-                    keep_dots: false,
-                    keep_special_chars: true,
-                    keep_unicode: true,
-                    exclude_regexes: vec![],
+                    rename_roots: true,
+                    order: TraversalOrder::Depth,
+                    min_length: None,
+                    max_length: None,
+                    truncate_hash: false,
+                    limit: None,
+                    max_conflicts: None,
+                    on_conflict: OnConflict::Ask,
+                    retry_policy: RetryPolicy::default(),
+                    skip_locked: false,
+                    ignore_readonly: false,
+                    yes_to_errors: false,
+                    transactional: false,
+                    output_dir: None,
+                    git: false,
+                    leave_symlink: false,
+                    strict_ascii: false,
+                    allow_lossy_unicode: false,
+                    base: None,
+                    changed_only: false,
+                    verbose: false,
+                    no_color: false,
+                    legend: false,
+                    line_format: None,
+                    timings: false,
+                    echo_config: false,
+                    dry_run: false,
+                    json: false,
+                    verify_revert: false,
+                    check: false,
+                    check_conflicts: false,
+                    edit_plan: false,
+                    emit_script: None,
+                    emit_diff: false,
+                    list_excluded: false,
+                    plan_out: None,
+                    apply_plan: None,
+                    editor: String::from("vi"),
+                    report_conforming: false,
+                    report: None,
+                    summary_json: false,
+                    no_exclude: false,
+                    no_lock: false,
+                    exclude_patterns: vec![],
+                    exclude_file_path: Some(EXCLUDE_FILE_PATH.clone()),
+                    history_dir_path: HISTORY_DIR_PATH.clone(),
+                    resume_dir_path: RESUME_DIR_PATH.clone(),
+                    backup_dir_path: BACKUP_DIR_PATH.clone(),
+                    lock_file_path: LOCK_FILE_PATH.clone(),
                 },
             },
         ];
@@ -215,9 +938,11 @@ mod tests {
                 data.files, test_case.data.files
             );
             assert_eq!(
-                data.naming_convention, test_case.data.naming_convention,
+                data.format_options.naming_convention,
+                test_case.data.format_options.naming_convention,
                 "Expected {:?}, but got {:?}",
-                data.naming_convention, test_case.data.naming_convention
+                data.format_options.naming_convention,
+                test_case.data.format_options.naming_convention
             );
             assert_eq!(
                 data.recursive, test_case.data.recursive,
@@ -225,20 +950,147 @@ mod tests {
                 data.recursive, test_case.data.recursive
             );
             assert_eq!(
-                data.keep_dots, test_case.data.keep_dots,
+                data.format_options.keep_dots, test_case.data.format_options.keep_dots,
                 "Expected {:?}, but got {:?}",
-                data.keep_dots, test_case.data.keep_dots
+                data.format_options.keep_dots, test_case.data.format_options.keep_dots
             );
             assert_eq!(
-                data.keep_special_chars, test_case.data.keep_special_chars,
+                data.format_options.keep_special_chars,
+                test_case.data.format_options.keep_special_chars,
                 "Expected {:?}, but got {:?}",
-                data.keep_special_chars, test_case.data.keep_special_chars
+                data.format_options.keep_special_chars,
+                test_case.data.format_options.keep_special_chars
             );
             assert_eq!(
-                data.keep_unicode, test_case.data.keep_unicode,
+                data.format_options.keep_unicode, test_case.data.format_options.keep_unicode,
                 "Expected {:?}, but got {:?}",
-                data.keep_unicode, test_case.data.keep_unicode
+                data.format_options.keep_unicode, test_case.data.format_options.keep_unicode
             );
         }
     }
+
+    #[test]
+    fn files_must_be_within_the_base_directory() {
+        let base = crate::paths::tests::TMP_DIR_PATH.join("base");
+        let mk_cli = |file: PathBuf| DefaultArgs {
+            files: vec![file],
+            base: Some(base.clone()),
+            ..Default::default()
+        };
+
+        let data = Data::new(mk_cli(base.join("some_file")), Cfg::default())
+            .expect("Data::new should have succeeded.");
+        assert_eq!(data.base.as_ref(), Some(&base));
+
+        let res = Data::new(mk_cli(base.join("..").join("other_file")), Cfg::default());
+        assert!(res.is_err(), "Expected Data::new to fail.");
+    }
+
+    #[serial_test::serial]
+    #[test]
+    fn glob_patterns_are_expanded_into_files() {
+        let dir = crate::paths::tests::mk_tmp_dir("glob");
+        for f in ["a.txt", "b.txt", "c.md"] {
+            std::fs::File::create(dir.join(f)).unwrap();
+        }
+
+        let cli = DefaultArgs {
+            files: vec![dir.join("c.md")],
+            glob: vec![format!(
+                "{}/*.txt",
+                glob::Pattern::escape(&dir.to_string_lossy())
+            )],
+            ..Default::default()
+        };
+        let data = Data::new(cli, Cfg::default()).expect("Data::new should have succeeded.");
+
+        let mut files = Vec::from(data.files);
+        files.sort();
+        assert_eq!(
+            files,
+            vec![dir.join("a.txt"), dir.join("b.txt"), dir.join("c.md")]
+        );
+    }
+
+    #[serial_test::serial]
+    #[test]
+    fn no_exclude_ignores_the_exclude_file() {
+        let dir = crate::paths::tests::mk_tmp_dir("no_exclude");
+        let exclude_file_path = dir.join("exclude.txt");
+        fs::write(&exclude_file_path, "^Keep Me\\.txt$\n").unwrap();
+        let patterns = load_exclude_patterns(&exclude_file_path).unwrap();
+        assert_eq!(patterns.len(), 1);
+        assert!(patterns[0].is_match(Path::new("Keep Me.txt")));
+
+        let cli = DefaultArgs {
+            no_exclude: true,
+            ..Default::default()
+        };
+        let data = Data::new(cli, Cfg::default()).expect("Data::new should have succeeded.");
+        assert!(data.exclude_patterns.is_empty());
+    }
+
+    #[serial_test::serial]
+    #[test]
+    fn input_files_are_read_along_with_arguments() {
+        let dir = crate::paths::tests::mk_tmp_dir("input_file");
+        let input_file = dir.join("files.txt");
+        fs::write(
+            &input_file,
+            format!(
+                "# Reports\n{}\n\n   \n  # Photos\n{}\n",
+                dir.join("Report.pdf").to_string_lossy(),
+                dir.join("My Photo.jpg").to_string_lossy()
+            ),
+        )
+        .unwrap();
+
+        let cli = DefaultArgs {
+            files: vec![dir.join("Notes.txt")],
+            input_file: vec![input_file],
+            no_exclude: true,
+            ..Default::default()
+        };
+        let data = Data::new(cli, Cfg::default()).expect("Data::new should have succeeded.");
+        let mut files: Vec<_> = data.files.into_iter().collect();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                dir.join("My Photo.jpg"),
+                dir.join("Notes.txt"),
+                dir.join("Report.pdf")
+            ]
+        );
+    }
+
+    #[test]
+    fn resolved_options_aggregate_cli_and_config() {
+        let cli = DefaultArgs {
+            convention: ConventionArgs {
+                naming_convention: Some(NamingConvention::KebabCase),
+                ..Default::default()
+            },
+            max_length: Some(32),
+            ..Default::default()
+        };
+        let cfg = Cfg {
+            naming_convention: NamingConvention::CamelCase,
+            recursive: true,
+            keep_unicode: true,
+            ..Default::default()
+        };
+
+        let options = resolve_options(cli, cfg).expect("resolve_options should have succeeded.");
+
+        assert_eq!(
+            options.format_options.naming_convention,
+            NamingConvention::KebabCase
+        );
+        assert!(options.recursive);
+        assert!(options.format_options.keep_unicode);
+        assert!(!options.format_options.keep_dots);
+        assert_eq!(options.max_length, Some(32));
+        assert_eq!(options.retry_policy.retries, 0);
+    }
 }
@@ -0,0 +1,64 @@
+use super::plan::Renaming;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Returns `renamings` as a diff in the format of git for renamed files,
+/// i.e. for each of them:
+///
+/// ```text
+/// diff --git a/<path> b/<new path>
+/// similarity index 100%
+/// rename from <path>
+/// rename to <new path>
+/// ```
+///
+/// New paths are the ones files end up at once all the renamings are done,
+/// i.e. within their renamed parent directory, if any.
+///
+/// # Parameters
+///
+/// - `renamings`: The renamings, each new path being within the current
+///   parent directory of the file.
+pub fn rename_diff(renamings: &[Renaming]) -> String {
+    let renamed: HashMap<&Path, &Path> = renamings
+        .iter()
+        .map(|(path, new_path)| (path.as_path(), new_path.as_path()))
+        .collect();
+    let mut diff = String::new();
+    for (path, _) in renamings {
+        let new_path = final_path(path, &renamed);
+        diff.push_str(&format!(
+            "diff --git a/{} b/{}\n\
+             similarity index 100%\n\
+             rename from {}\n\
+             rename to {}\n",
+            without_root(path),
+            without_root(&new_path),
+            path.to_string_lossy(),
+            new_path.to_string_lossy()
+        ));
+    }
+
+    diff
+}
+
+/// Returns the path `path` ends up at once the renamings of `renamed` are
+/// done, its ancestors included.
+fn final_path(path: &Path, renamed: &HashMap<&Path, &Path>) -> PathBuf {
+    let filename = match renamed.get(path) {
+        Some(new_path) => new_path.file_name(),
+        None => path.file_name(),
+    };
+    match (path.parent(), filename) {
+        (Some(parent), Some(filename)) => final_path(parent, renamed).join(filename),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Returns `path` without its leading separators, so that it can be
+/// prefixed like git does (e.g. "a/tmp/file" rather than "a//tmp/file").
+fn without_root(path: &Path) -> String {
+    path.to_string_lossy()
+        .trim_start_matches(std::path::is_separator)
+        .to_string()
+}
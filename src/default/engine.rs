@@ -1,19 +1,87 @@
-use super::cli::DefaultArgs;
-use super::data::Data;
+use super::cli::{DefaultArgs, FileKind, OnConflict, ScriptShell, TraversalOrder};
+use super::data::{Data, LINE_FORMAT_PLACEHOLDER_RE};
+use super::diff::rename_diff;
+use super::event::RenameEvent;
+use super::marker::{Marker, MARKER_FILE_NAME};
+use super::plan::{
+    parse_edited_plan, parse_saved_plan, plan_file_content, Decision, ExclusionRecord, PlanProblem,
+    PlanRecord, Renaming,
+};
+use super::script::rename_script;
+use super::summary::{ErrorCategory, Summary};
+use super::timings::Timings;
 use crate::cfg::Cfg;
 use crate::engine::Engine;
-use crate::naming_conventions::apply_nc;
-use crate::paths::HISTORY_DIR_PATH;
+use crate::exclude::ExcludePattern;
+use crate::git;
+use crate::lock::RunLock;
+use crate::naming_conventions::{
+    detect_ncs, join_filename, target_path_for, truncate_name, truncate_name_with_hash,
+    untransliterable_chars, FormatOptions,
+};
 use crate::prompt::{already_exist_prompt, error_prompt, AlreadyExistPromptOptions};
-use crate::utils::{backup, file_is_empty, get_now_str, overwrite, skip};
+use crate::rename::RenamePattern;
+use crate::revert::{self, parse_history_line};
+use crate::utils::{
+    apply_conflict_plan, diff_chars, file_is_empty, get_now_str, is_same_file, mime_type_matches,
+    open_in_editor, plan_backup, plan_overwrite, plan_skip, rename_with_retry, sniff_mime_type,
+    symlink, ConflictPlan, ConflictResolution, DiffChunk,
+};
+use anyhow::anyhow;
 use anyhow::Context;
-use crossterm::style::Stylize;
+use clap::ValueEnum;
+use crossterm::style::{self, Color, Colored, Stylize};
+use regex::Captures;
+use serde::Serialize;
+use signal_hook::consts::SIGINT;
+use signal_hook::SigId;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::env;
 use std::fs;
 use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::io::{self, BufWriter, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 use walkdir::WalkDir;
 
+/// The codes of the operations recorded in history files, with their meaning
+/// and the color of their lines, see [DefaultArgs::legend].
+const OPS: [(char, &str, Color); 9] = [
+    ('d', "renamed", Color::DarkGrey),
+    ('c', "copied into the output directory", Color::DarkGrey),
+    ('s', "skipped, as the new name is taken", Color::DarkBlue),
+    (
+        'b',
+        "renamed, after backing up the file with the new name",
+        Color::DarkGreen,
+    ),
+    (
+        'o',
+        "renamed, overwriting the file with the new name",
+        Color::DarkYellow,
+    ),
+    ('l', "symbolic link left at the old path", Color::DarkCyan),
+    (
+        'k',
+        "skipped, as locked by another process",
+        Color::DarkYellow,
+    ),
+    (
+        'r',
+        "skipped, as on a read-only filesystem",
+        Color::DarkYellow,
+    ),
+    ('e', "error", Color::DarkRed),
+];
+
+/// The reason why a file is kept, when it already follows the naming convention.
+const ALREADY_FORMATTED: &str = "Already formatted.";
+
 /// Returns the engine for the default subcommand, parameterized by `cli` and `cfg`.
 ///
 /// # Parameters
@@ -29,11 +97,104 @@ pub fn get_engine(cli: DefaultArgs, cfg: Cfg) -> anyhow::Result<Box<dyn Engine>>
     Ok(Box::new(DefaultEngine::new(cli, cfg)?))
 }
 
-struct DefaultEngine {
+/// A callback invoked with each [RenameEvent] of a run.
+pub type Observer = Box<dyn FnMut(&RenameEvent)>;
+
+/// Like [get_engine], but `observer` is also invoked for each file processed
+/// by the run, right after its line is printed.
+///
+/// Useful to embed fmtna in another program (e.g. a GUI) that needs to know
+/// what happened to each file without parsing the output.
+/// The observer is not invoked with --dry-run, --check nor --check-conflicts,
+/// as nothing happens to files then.
+///
+/// # Parameters
+///
+/// - `cli`: The CLI arguments.
+/// - `cfg`: The configuration values.
+/// - `observer`: The callback to invoke with each event.
+///
+/// # Returns
+///
+/// The parametrized engine for running the default subcommand's logic, or an
+/// error if engine creation failed.
+pub fn get_engine_with_observer(
+    cli: DefaultArgs,
+    cfg: Cfg,
+    observer: Observer,
+) -> anyhow::Result<Box<dyn Engine>> {
+    Ok(Box::new(
+        DefaultEngine::new(cli, cfg)?.with_observer(observer),
+    ))
+}
+
+/// Like [get_engine], but the new names are built by `rename_pattern`
+/// rather than by formatting, see [RenameCli](crate::rename::RenameCli).
+///
+/// # Parameters
+///
+/// - `cli`: The CLI arguments.
+/// - `cfg`: The configuration values.
+/// - `rename_pattern`: How to build the new names.
+///
+/// # Returns
+///
+/// The parametrized engine for running the rename subcommand's logic, or an
+/// error if engine creation failed.
+pub fn get_engine_with_rename_pattern(
+    cli: DefaultArgs,
+    cfg: Cfg,
+    rename_pattern: RenamePattern,
+) -> anyhow::Result<Box<dyn Engine>> {
+    Ok(Box::new(
+        DefaultEngine::new(cli, cfg)?.with_rename_pattern(rename_pattern),
+    ))
+}
+
+/// Like [get_engine], but the renamings are read from the plan file
+/// `plan_path` rather than planned, see
+/// [ApplyPlanCli](crate::apply_plan::ApplyPlanCli).
+///
+/// # Parameters
+///
+/// - `cli`: The CLI arguments.
+/// - `cfg`: The configuration values.
+/// - `plan_path`: The path of the plan file, as written with
+///   [DefaultArgs::plan_out].
+///
+/// # Returns
+///
+/// The parametrized engine for running the apply-plan subcommand's logic, or
+/// an error if engine creation failed.
+pub fn get_engine_applying_plan(
+    cli: DefaultArgs,
+    cfg: Cfg,
+    plan_path: PathBuf,
+) -> anyhow::Result<Box<dyn Engine>> {
+    Ok(Box::new(
+        DefaultEngine::new(cli, cfg)?.with_plan_to_apply(plan_path),
+    ))
+}
+
+struct DefaultEngine<O: Write = io::Stdout, E: Write = io::Stderr> {
     data: Data,
     action: Option<Action>,
+    summary: Summary,
+    /// Where results, i.e. recap lines of changes, are printed.
+    out: O,
+    /// Where diagnostics, i.e. warnings and errors, are printed.
+    err: E,
+    /// Invoked with each event, besides printing.
+    observer: Option<Observer>,
+    /// The copies of the directories copied so far, see [DefaultArgs::output_dir].
+    output_dirs: HashMap<PathBuf, PathBuf>,
+    timings: Timings,
+    /// The format options set by the nearest marker file above each directory
+    /// seen so far, see [DefaultArgs::no_markers].
+    markers: RefCell<HashMap<PathBuf, Option<FormatOptions>>>,
 }
 
+#[derive(Clone, Copy)]
 enum Action {
     Skip,
     Backup,
@@ -46,32 +207,121 @@ enum ChangeStemResult {
     FailedToRetrieveFileStem,
     FileHasInvalidUnicode,
     FileHasNoParentDirectory,
+    NewStemTooShort(String),
+    UntransliterableChars(Vec<char>),
+    InvalidMarker(String),
     NewFileAlreadyExist(PathBuf),
+    /// The file is locked by another process (Windows only).
+    FileIsLocked(std::io::Error),
+    /// The file is on a read-only filesystem.
+    ReadOnly(std::io::Error),
     FailedToRename(std::io::Error),
+    FailedToCopy(std::io::Error),
     NoNeedToRename,
+    /// Like `NoNeedToRename`, but only because paths are case-insensitive
+    /// on the platform.
+    OnlyCaseDiffers,
+    /// The name doesn't follow [Data::from], so the file is left alone.
+    NotFromConvention,
+    /// The content of the extension-less file isn't of the type of
+    /// [Data::by_mime], so the file is left alone.
+    NotOfMimeType,
+    /// The name doesn't match [Data::rename_pattern], so the file is left
+    /// alone.
+    NotMatchingPattern,
+    /// The new name built by [Data::rename_pattern] is not a valid filename.
+    InvalidNewName(String),
     Ok(PathBuf),
+    /// Like `Ok`, but the original filename contained invalid Unicode
+    /// that was replaced lossily before formatting.
+    OkLossy(PathBuf),
+    /// The copy of the directory already exists in the output directory.
+    DirAlreadyCopied(PathBuf),
+}
+
+impl ChangeStemResult {
+    /// Returns the category of the error `self` is, for the variants
+    /// reported as errors.
+    fn error_category(&self) -> ErrorCategory {
+        match self {
+            ChangeStemResult::FileDoesntExist => ErrorCategory::MissingFile,
+            ChangeStemResult::FailedToRetrieveFileStem
+            | ChangeStemResult::FileHasNoParentDirectory
+            | ChangeStemResult::InvalidNewName(_) => ErrorCategory::InvalidPath,
+            ChangeStemResult::FileHasInvalidUnicode => ErrorCategory::InvalidUnicode,
+            ChangeStemResult::NewStemTooShort(_) => ErrorCategory::TooShort,
+            ChangeStemResult::UntransliterableChars(_) => ErrorCategory::Untransliterable,
+            ChangeStemResult::InvalidMarker(_) => ErrorCategory::InvalidMarker,
+            ChangeStemResult::FileIsLocked(_) => ErrorCategory::Locked,
+            ChangeStemResult::FailedToRename(_) => ErrorCategory::RenameFailed,
+            ChangeStemResult::FailedToCopy(_) => ErrorCategory::CopyFailed,
+            _ => ErrorCategory::Other,
+        }
+    }
 }
 
 impl DefaultEngine {
     pub fn new(cli: DefaultArgs, cfg: Cfg) -> anyhow::Result<Self> {
         let data = Data::new(cli, cfg)?;
-        Ok(Self { data, action: None })
+        if data.no_color {
+            style::force_color_output(false);
+        }
+        let action = match data.on_conflict {
+            OnConflict::Skip => Some(Action::Skip),
+            OnConflict::Backup => Some(Action::Backup),
+            OnConflict::Overwrite => Some(Action::Overwrite),
+            OnConflict::Ask | OnConflict::Newest | OnConflict::Largest => None,
+        };
+        Ok(Self {
+            data,
+            action,
+            summary: Summary::default(),
+            out: io::stdout(),
+            err: io::stderr(),
+            observer: None,
+            output_dirs: HashMap::new(),
+            timings: Timings::default(),
+            markers: RefCell::new(HashMap::new()),
+        })
+    }
+}
+
+impl<O: Write, E: Write> DefaultEngine<O, E> {
+    fn with_observer(mut self, observer: Observer) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    fn with_rename_pattern(mut self, rename_pattern: RenamePattern) -> Self {
+        self.data.rename_pattern = Some(rename_pattern);
+        self
+    }
+
+    fn with_plan_to_apply(mut self, plan_path: PathBuf) -> Self {
+        self.data.apply_plan = Some(plan_path);
+        self
     }
 
     fn change_stem_of_file(&self, file: &Path) -> ChangeStemResult {
-        if !file.exists() {
+        // A symbolic link to a file that doesn't exist is still there to be
+        // renamed.
+        if !file.exists() && !file.is_symlink() {
             return ChangeStemResult::FileDoesntExist;
         }
 
-        let file_stem = file.file_stem();
-        if file_stem.is_none() {
+        if file.file_stem().is_none() {
             return ChangeStemResult::FailedToRetrieveFileStem;
         }
-        let file_stem = file_stem.unwrap().to_str();
-        if file_stem.is_none() {
+        let filename = match file.file_name() {
+            Some(filename) => filename.to_string_lossy(),
+            None => return ChangeStemResult::FailedToRetrieveFileStem,
+        };
+        // A borrowed Cow means no bytes had to be replaced, i.e. the filename
+        // was valid Unicode.
+        let is_lossy = matches!(filename, Cow::Owned(_));
+        if is_lossy && !self.data.allow_lossy_unicode {
             return ChangeStemResult::FileHasInvalidUnicode;
         }
-        let file_stem = file_stem.unwrap();
 
         let parent_dir = file.parent();
         if parent_dir.is_none() {
@@ -79,23 +329,21 @@ impl DefaultEngine {
         }
         let parent_dir = parent_dir.unwrap();
 
-        let mut new_filename = apply_nc(
-            &self.data.naming_convention,
-            file_stem,
-            self.data.keep_dots,
-            self.data.keep_special_chars,
-            self.data.keep_unicode,
-        );
+        let new_filename = match self.new_filename(file, &filename) {
+            Ok(new_filename) => new_filename,
+            Err(res) => return res,
+        };
 
-        // because paths are case-insensitive on Windows
-        if cfg!(windows) && new_filename.to_lowercase() == file_stem.to_lowercase() {
-            return ChangeStemResult::NoNeedToRename;
+        if let Some(output_parent) = self.output_parent(parent_dir) {
+            return self.copy_file(file, &output_parent.join(new_filename), is_lossy);
         }
 
-        if let Some(ext) = file.extension() {
-            new_filename.push('.');
-            new_filename.push_str(&ext.to_string_lossy());
+        let only_case_differs = new_filename.to_lowercase() == filename.to_lowercase();
+        // because paths are case-insensitive on Windows
+        if cfg!(windows) && only_case_differs {
+            return ChangeStemResult::OnlyCaseDiffers;
         }
+
         let mut new_file = parent_dir.to_owned();
         new_file.push(new_filename);
 
@@ -103,193 +351,3712 @@ impl DefaultEngine {
             return ChangeStemResult::NoNeedToRename;
         }
 
-        if new_file.exists() {
+        // On other case-insensitive filesystems (e.g. on macOS), the new path
+        // then points to the file itself, which is not a conflict.
+        if (new_file.exists() || new_file.is_symlink())
+            && !(only_case_differs && is_same_file(file, &new_file))
+        {
             return ChangeStemResult::NewFileAlreadyExist(new_file);
         }
 
-        let res = fs::rename(file, &new_file);
+        if self.plans_only() {
+            return if is_lossy {
+                ChangeStemResult::OkLossy(new_file)
+            } else {
+                ChangeStemResult::Ok(new_file)
+            };
+        }
+
+        let res = Timings::time(&self.timings.fs_ops, || {
+            if self.data.git && git::is_tracked(file) {
+                git::git_mv(file, &new_file)
+            } else {
+                rename_with_retry(file, &new_file, &self.data.retry_policy)
+            }
+        });
         if let Err(err) = res {
+            if is_locked(&err) {
+                return ChangeStemResult::FileIsLocked(err);
+            }
+            if err.kind() == io::ErrorKind::ReadOnlyFilesystem {
+                return ChangeStemResult::ReadOnly(err);
+            }
             return ChangeStemResult::FailedToRename(err);
         }
 
+        if is_lossy {
+            return ChangeStemResult::OkLossy(new_file);
+        }
+
         ChangeStemResult::Ok(new_file)
     }
 
-    fn should_exclude(&self, file: &Path) -> bool {
-        if let Some(filename) = file.file_name() {
-            let filename = filename.to_string_lossy();
-            for re in &self.data.exclude_regexes {
-                if re.is_match(&filename) {
-                    return true;
-                }
+    /// Returns the new filename of `file`, whose filename is `filename`, or
+    /// why it is left alone or can't be renamed.
+    fn new_filename(&self, file: &Path, filename: &str) -> Result<String, ChangeStemResult> {
+        if let Some(rename_pattern) = &self.data.rename_pattern {
+            return self.renamed_filename(file, filename, rename_pattern);
+        }
+
+        let normalized = self.data.format_options.normalize_filename(filename);
+        let (stem, ext) = self.data.format_options.split_filename(&normalized);
+        let format_options = match self.format_options_for(file, ext) {
+            Ok(format_options) => format_options,
+            Err(err) => return Err(ChangeStemResult::InvalidMarker(format!("{:#}", err))),
+        };
+        if let Some(from) = &self.data.from {
+            let ncs = detect_ncs(
+                stem,
+                format_options.keep_dots,
+                format_options.keep_special_chars,
+                format_options.keep_unicode,
+            );
+            if !ncs.contains(from) {
+                return Err(ChangeStemResult::NotFromConvention);
+            }
+        }
+        if let Some(by_mime) = &self.data.by_mime {
+            // Unreadable files are left for the renaming to report.
+            if ext.is_none()
+                && file.is_file()
+                && sniff_mime_type(file).is_ok_and(|mime| !mime_type_matches(mime, by_mime))
+            {
+                return Err(ChangeStemResult::NotOfMimeType);
+            }
+        }
+        if self.data.strict_ascii && !format_options.keep_unicode {
+            let chars = untransliterable_chars(stem);
+            if !chars.is_empty() {
+                return Err(ChangeStemResult::UntransliterableChars(chars));
+            }
+        }
+        let mut new_stem = Timings::time(&self.timings.naming, || format_options.format_name(stem));
+        if let Some(max_length) = self.data.max_length {
+            new_stem = if self.data.truncate_hash {
+                let sep = format_options.naming_convention.word_separator();
+                truncate_name_with_hash(&new_stem, stem, max_length, sep)
+            } else {
+                truncate_name(&new_stem, max_length)
+            };
+        }
+        if let Some(min_length) = self.data.min_length {
+            if new_stem.chars().count() < min_length {
+                return Err(ChangeStemResult::NewStemTooShort(new_stem));
             }
+        }
+        let ext = ext.map(|ext| self.data.extension_case.apply(ext));
+
+        Ok(join_filename(&new_stem, ext.as_deref()))
+    }
 
-            return false;
+    /// Returns the new filename of `file` built by `rename_pattern`, see
+    /// [get_engine_with_rename_pattern].
+    fn renamed_filename(
+        &self,
+        file: &Path,
+        filename: &str,
+        rename_pattern: &RenamePattern,
+    ) -> Result<String, ChangeStemResult> {
+        let Some(mut new_filename) = rename_pattern.apply(filename) else {
+            return Err(ChangeStemResult::NotMatchingPattern);
+        };
+        if rename_pattern.apply_convention {
+            let normalized = self.data.format_options.normalize_filename(&new_filename);
+            let (stem, ext) = self.data.format_options.split_filename(&normalized);
+            let format_options = self
+                .format_options_for(file, ext)
+                .map_err(|err| ChangeStemResult::InvalidMarker(format!("{:#}", err)))?;
+            new_filename = join_filename(&format_options.format_name(stem), ext);
+        }
+        if new_filename.is_empty()
+            || new_filename == "."
+            || new_filename == ".."
+            || new_filename.contains(std::path::is_separator)
+        {
+            return Err(ChangeStemResult::InvalidNewName(new_filename));
         }
 
-        true
+        Ok(new_filename)
     }
 
-    fn process_file<W: Write>(&mut self, f: PathBuf, history_writer: &mut W) -> anyhow::Result<()> {
-        if self.should_exclude(&f) {
-            return Ok(());
+    /// Returns the directory where to copy the files within `dir`, or `None`
+    /// if not copying, see [DefaultArgs::output_dir].
+    fn output_parent(&self, dir: &Path) -> Option<PathBuf> {
+        let output_dir = self.data.output_dir.as_ref()?;
+        Some(self.output_dirs.get(dir).unwrap_or(output_dir).clone())
+    }
+
+    /// Copies `file` to `new_file`, in the output directory.
+    ///
+    /// Directories are created rather than copied. One that already exists is
+    /// not a conflict, the content of `file` is copied into it.
+    fn copy_file(&self, file: &Path, new_file: &Path, is_lossy: bool) -> ChangeStemResult {
+        if file.is_dir() && !file.is_symlink() && new_file.is_dir() {
+            return ChangeStemResult::DirAlreadyCopied(new_file.to_owned());
+        }
+        if new_file.exists() {
+            return ChangeStemResult::NewFileAlreadyExist(new_file.to_owned());
+        }
+
+        if let Err(err) = Timings::time(&self.timings.fs_ops, || copy_or_create_dir(file, new_file))
+        {
+            return ChangeStemResult::FailedToCopy(err);
         }
 
-        match self.change_stem_of_file(&f) {
-            ChangeStemResult::FileDoesntExist => {
-                let f_str = f.to_string_lossy();
-                let err_mess = "File doesn't exist.";
+        if is_lossy {
+            return ChangeStemResult::OkLossy(new_file.to_owned());
+        }
+
+        ChangeStemResult::Ok(new_file.to_owned())
+    }
 
-                error_prompt(&f_str, err_mess)?;
+    /// Returns the format options to use for `file`, whose extension is `ext`,
+    /// taking marker files and [Data::convention_for] into account.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the nearest marker file is invalid.
+    fn format_options_for(
+        &self,
+        file: &Path,
+        ext: Option<&str>,
+    ) -> anyhow::Result<Cow<'_, FormatOptions>> {
+        let format_options = match file.parent() {
+            Some(dir) if !self.data.no_markers => self.marker_options(dir)?,
+            _ => None,
+        };
+        let format_options = match format_options {
+            Some(format_options) => Cow::Owned(format_options),
+            None => Cow::Borrowed(&self.data.format_options),
+        };
 
-                let recap_line = format!("(e) {}: {}", f_str, err_mess);
-                println!("{}", recap_line.clone().dark_red());
-                writeln!(history_writer, "{}", recap_line)
-                    .with_context(|| "Failed to write to history file.")?;
+        let matching = self.data.convention_for.iter().find(|cf| match cf.kind {
+            FileKind::Dir => file.is_dir(),
+            FileKind::Extension(ref cf_ext) => {
+                ext.is_some_and(|ext| ext.eq_ignore_ascii_case(cf_ext))
             }
-            ChangeStemResult::FailedToRetrieveFileStem => {
-                let f_str = f.to_string_lossy();
-                let err_mess = "Failed to find the stem.";
+        });
 
-                error_prompt(&f_str, err_mess)?;
+        Ok(match matching {
+            Some(cf) => Cow::Owned(FormatOptions {
+                naming_convention: cf.naming_convention.clone(),
+                ..format_options.into_owned()
+            }),
+            None => format_options,
+        })
+    }
+
+    /// Returns the format options set by the nearest marker file in `dir` or
+    /// above, or `None` if there is none.
+    fn marker_options(&self, dir: &Path) -> anyhow::Result<Option<FormatOptions>> {
+        if let Some(format_options) = self.markers.borrow().get(dir) {
+            return Ok(format_options.clone());
+        }
 
-                let recap_line = format!("(e) {}: {}", f_str, err_mess);
-                println!("{}", recap_line.clone().dark_red());
-                writeln!(history_writer, "{}", recap_line)
-                    .with_context(|| "Failed to write to history file.")?;
+        let marker_path = dir.join(MARKER_FILE_NAME);
+        let format_options = if marker_path.is_file() {
+            Some(Marker::read(&marker_path)?.apply(&self.data.format_options))
+        } else {
+            match dir.parent() {
+                Some(parent) => self.marker_options(parent)?,
+                None => None,
             }
-            ChangeStemResult::FileHasInvalidUnicode => {
-                let f_str = f.to_string_lossy();
-                let err_mess = "File contains invalid unicode characters.";
+        };
+        self.markers
+            .borrow_mut()
+            .insert(dir.to_owned(), format_options.clone());
+
+        Ok(format_options)
+    }
+
+    /// Returns why `file` should be excluded, or `None` if it shouldn't.
+    fn exclusion_reason(&self, file: &Path) -> Option<String> {
+        if file.file_name().is_none() {
+            return Some(String::from("File has no filename."));
+        }
 
-                error_prompt(&f_str, err_mess)?;
+        self.exclude_pattern_matching(file)
+            .map(|pattern| format!("Excluded by pattern {}.", pattern))
+    }
+
+    /// Returns the first exclude pattern matching `file`, if any.
+    fn exclude_pattern_matching(&self, file: &Path) -> Option<&ExcludePattern> {
+        self.data
+            .exclude_patterns
+            .iter()
+            .find(|pattern| pattern.is_match(file))
+    }
 
-                let recap_line = format!("(e) {}: {}", f_str, err_mess);
-                println!("{}", recap_line.clone().dark_red());
-                writeln!(history_writer, "{}", recap_line)
-                    .with_context(|| "Failed to write to history file.")?;
+    /// Returns the message reporting `res` as an error, or `None` if `res`
+    /// is not an error.
+    fn error_message(&self, res: &ChangeStemResult) -> Option<String> {
+        match res {
+            ChangeStemResult::FileDoesntExist => Some(String::from("File doesn't exist.")),
+            ChangeStemResult::FailedToRetrieveFileStem => {
+                Some(String::from("Failed to find the stem."))
+            }
+            ChangeStemResult::FileHasInvalidUnicode => {
+                Some(String::from("File contains invalid unicode characters."))
             }
             ChangeStemResult::FileHasNoParentDirectory => {
-                let f_str = f.to_string_lossy();
-                let err_mess = "File has no parent directory";
+                Some(String::from("File has no parent directory"))
+            }
+            ChangeStemResult::NewStemTooShort(new_stem) => Some(format!(
+                "Formatted stem \"{}\" is shorter than the minimum length ({}).",
+                new_stem,
+                self.data.min_length.unwrap_or_default()
+            )),
+            ChangeStemResult::UntransliterableChars(chars) => Some(format!(
+                "Characters {:?} can't be transliterated to ASCII.",
+                chars
+            )),
+            ChangeStemResult::InvalidMarker(err) => Some(err.clone()),
+            ChangeStemResult::FileIsLocked(err) if !self.data.skip_locked => Some(format!(
+                "Failed to rename, the file is locked by another process. {}",
+                err
+            )),
+            ChangeStemResult::FailedToRename(err) => Some(format!("Failed to rename. {}", err)),
+            ChangeStemResult::FailedToCopy(err) => Some(format!("Failed to copy. {}", err)),
+            ChangeStemResult::InvalidNewName(new_filename) => Some(format!(
+                "The template gives \"{}\", which isn't a valid filename.",
+                new_filename
+            )),
+            ChangeStemResult::NewFileAlreadyExist(_)
+            | ChangeStemResult::FileIsLocked(_)
+            | ChangeStemResult::ReadOnly(_)
+            | ChangeStemResult::NoNeedToRename
+            | ChangeStemResult::OnlyCaseDiffers
+            | ChangeStemResult::NotFromConvention
+            | ChangeStemResult::NotOfMimeType
+            | ChangeStemResult::NotMatchingPattern
+            | ChangeStemResult::Ok(_)
+            | ChangeStemResult::OkLossy(_)
+            | ChangeStemResult::DirAlreadyCopied(_) => None,
+        }
+    }
+
+    /// Returns the next file to process, according to [Data::order].
+    fn next_file(&mut self) -> Option<PathBuf> {
+        match self.data.order {
+            TraversalOrder::Depth => self.data.files.pop_back(),
+            TraversalOrder::Breadth => self.data.files.pop_front(),
+        }
+    }
 
-                error_prompt(&f_str, err_mess)?;
+    /// Puts `f` back, so that it is the next file returned by
+    /// [next_file](DefaultEngine::next_file).
+    fn unget_file(&mut self, f: PathBuf) {
+        match self.data.order {
+            TraversalOrder::Depth => self.data.files.push_back(f),
+            TraversalOrder::Breadth => self.data.files.push_front(f),
+        }
+    }
 
-                let recap_line = format!("(e) {}: {}", f_str, err_mess);
-                println!("{}", recap_line.clone().dark_red());
-                writeln!(history_writer, "{}", recap_line)
-                    .with_context(|| "Failed to write to history file.")?;
+    /// Pushes the files directly within `dir`, the current path of the
+    /// processed file `f`, onto the files to process, if recursing into it.
+    ///
+    /// Deeper files are pushed once their parent directory is processed,
+    /// so that they are pushed with their up-to-date path. This also means
+    /// excluded directories are not descended into.
+    fn push_children(&mut self, f: &Path, dir: &Path) {
+        if self.data.ancestors.contains(f) {
+            return;
+        }
+        if self.data.recursive && !dir.is_symlink() && dir.is_dir() {
+            let children = Timings::time(&self.timings.traversal, || {
+                WalkDir::new(dir)
+                    .min_depth(1)
+                    .max_depth(1)
+                    .sort_by_file_name()
+                    .into_iter()
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.into_path())
+                    .collect::<Vec<_>>()
+            });
+            // Either way, children come out in filename order.
+            match self.data.order {
+                TraversalOrder::Depth => self.data.files.extend(children.into_iter().rev()),
+                TraversalOrder::Breadth => self.data.files.extend(children),
             }
+        }
+    }
+
+    fn process_file<W: Write>(&mut self, f: PathBuf, history_writer: &mut W) -> anyhow::Result<()> {
+        self.summary.visited += 1;
+        if self.exclusion_reason(&f).is_some() {
+            return Ok(());
+        }
+        if self.data.roots.contains(&f) {
+            self.push_children(&f, &f);
+            return Ok(());
+        }
+
+        let res = self.change_stem_of_file(&f);
+        self.process_result(f, res, history_writer)
+    }
+
+    /// Reports `res`, the result of formatting the name of `f`, resolving
+    /// conflicts along the way.
+    fn process_result<W: Write>(
+        &mut self,
+        f: PathBuf,
+        res: ChangeStemResult,
+        history_writer: &mut W,
+    ) -> anyhow::Result<()> {
+        if let Some(err_mess) = self.error_message(&res) {
+            return self.report_error(&f, res.error_category(), &err_mess, history_writer);
+        }
+
+        match res {
             ChangeStemResult::NewFileAlreadyExist(new_f) => {
-                if let Some(ref action) = self.action {
-                    match action {
-                        Action::Skip => skip(&f, &new_f, history_writer)?,
-                        Action::Backup => backup(&f, &new_f, history_writer)?,
-                        Action::Overwrite => overwrite(&new_f, &new_f, history_writer)?,
-                    }
+                self.summary.conflicts += 1;
+                if self.too_many_conflicts() {
+                    // Left unresolved, for the resume file.
+                    self.unget_file(f);
                     return Ok(());
                 }
-
-                let f_str = f.to_string_lossy();
-                let new_f_str = new_f.to_string_lossy();
-                match already_exist_prompt(&f_str, &new_f_str)? {
-                    AlreadyExistPromptOptions::Skip => {
-                        skip(&f, &new_f, history_writer)?;
-                    }
-                    AlreadyExistPromptOptions::AlwaysSkip => {
-                        skip(&f, &new_f, history_writer)?;
-                        self.action = Some(Action::Skip);
+                let action = match self.action {
+                    Some(action) => action,
+                    None if matches!(
+                        self.data.on_conflict,
+                        OnConflict::Newest | OnConflict::Largest
+                    ) =>
+                    {
+                        match self.renamed_file_wins(&f, &new_f) {
+                            Ok(true) => Action::Backup,
+                            // Originals are left untouched when copying.
+                            Ok(false) if self.data.output_dir.is_some() => Action::Skip,
+                            Ok(false) => {
+                                return self.back_up_renamed_file(&f, &new_f, history_writer)
+                            }
+                            Err(err) => {
+                                let err_mess =
+                                    format!("Failed to compare with {}. {}", new_f.display(), err);
+                                return self.report_error(
+                                    &f,
+                                    ErrorCategory::Other,
+                                    &err_mess,
+                                    history_writer,
+                                );
+                            }
+                        }
                     }
-                    AlreadyExistPromptOptions::Backup => {
-                        backup(&f, &new_f, history_writer)?;
+                    None => {
+                        let f_str = self.display_path(&f);
+                        let new_f_str = self.display_path(&new_f);
+                        let (action, always) = match already_exist_prompt(&f_str, &new_f_str)? {
+                            AlreadyExistPromptOptions::Skip => (Action::Skip, false),
+                            AlreadyExistPromptOptions::AlwaysSkip => (Action::Skip, true),
+                            AlreadyExistPromptOptions::Backup => (Action::Backup, false),
+                            AlreadyExistPromptOptions::AlwaysBackup => (Action::Backup, true),
+                            AlreadyExistPromptOptions::Overwrite => (Action::Overwrite, false),
+                            AlreadyExistPromptOptions::AlwaysOverwrite => (Action::Overwrite, true),
+                        };
+                        if always {
+                            self.action = Some(action);
+                        }
+                        action
                     }
-                    AlreadyExistPromptOptions::AlwaysBackup => {
-                        backup(&f, &new_f, history_writer)?;
-                        self.action = Some(Action::Backup);
+                };
+
+                let plan = match action {
+                    Action::Skip => plan_skip(&f, &new_f),
+                    Action::Backup => {
+                        plan_backup(&f, &new_f, &self.data.backup_dir_path, &get_now_str())?
                     }
-                    AlreadyExistPromptOptions::Overwrite => {
-                        overwrite(&f, &new_f, history_writer)?;
+                    Action::Overwrite => plan_overwrite(&f, &new_f),
+                };
+                if self.data.output_dir.is_some() {
+                    return self.copy_with_conflict_plan(&plan, history_writer);
+                }
+                Timings::time(&self.timings.fs_ops, || {
+                    apply_conflict_plan(&plan, &self.data.retry_policy)
+                })?;
+                let (op, color) = match plan.resolution {
+                    ConflictResolution::Skip => ('s', Color::DarkBlue),
+                    ConflictResolution::Backup { .. } => ('b', Color::DarkGreen),
+                    ConflictResolution::Overwrite => ('o', Color::DarkYellow),
+                };
+                self.recap(Recap::Change(op, &f, &new_f), color, history_writer)?;
+                match plan.resolution {
+                    ConflictResolution::Skip => self.summary.skipped += 1,
+                    ConflictResolution::Backup { .. } => {
+                        self.summary.backed_up += 1;
+                        self.leave_symlink(&f, &new_f, history_writer)?;
                     }
-                    AlreadyExistPromptOptions::AlwaysOverwrite => {
-                        overwrite(&f, &new_f, history_writer)?;
-                        self.action = Some(Action::Overwrite);
+                    ConflictResolution::Overwrite => {
+                        self.summary.overwritten += 1;
+                        self.leave_symlink(&f, &new_f, history_writer)?;
                     }
-                };
+                }
+            }
+            ChangeStemResult::FileIsLocked(_) => {
+                self.recap(
+                    Recap::Note('k', &f, "Skipped, the file is locked by another process."),
+                    Color::DarkYellow,
+                    history_writer,
+                )?;
+                self.summary.locked += 1;
+            }
+            // No other file of the filesystem could be renamed either, so
+            // don't bother the user with an error per file.
+            ChangeStemResult::ReadOnly(err) if !self.data.ignore_readonly => {
+                return Err(anyhow!(
+                    "Failed to rename {}, it is on a read-only filesystem. {}\n\
+                     Use --ignore-readonly to skip such files instead.",
+                    self.display_path(&f),
+                    err
+                ));
+            }
+            ChangeStemResult::ReadOnly(_) => {
+                self.recap(
+                    Recap::Note('r', &f, "Skipped, the file is on a read-only filesystem."),
+                    Color::DarkYellow,
+                    history_writer,
+                )?;
+                self.summary.read_only += 1;
+            }
+            // The name conforms (case aside, if case-insensitive), but what is
+            // within may not. Excluded directories never get here.
+            ChangeStemResult::NoNeedToRename => {
+                if self.data.report_conforming {
+                    writeln!(
+                        self.out,
+                        "{}",
+                        conforming_line(&self.display_path(&f)).dark_green()
+                    )?;
+                }
+                self.push_children(&f, &f);
+            }
+            ChangeStemResult::OnlyCaseDiffers
+            | ChangeStemResult::NotFromConvention
+            | ChangeStemResult::NotOfMimeType
+            | ChangeStemResult::NotMatchingPattern => {
+                self.push_children(&f, &f);
+            }
+            ChangeStemResult::DirAlreadyCopied(new_f) => {
+                self.push_children(&f, &f);
+                self.output_dirs.insert(f, new_f);
+            }
+            ChangeStemResult::Ok(new_f) => self.report_renaming(&f, &new_f, history_writer)?,
+            ChangeStemResult::OkLossy(new_f) => {
+                let warn_line = format!(
+                    "(w) {}: Filename contains invalid Unicode, it was converted lossily.",
+                    self.display_path(&f)
+                );
+                if !self.data.changed_only {
+                    writeln!(self.err, "{}", warn_line.dark_yellow())?;
+                }
+                self.report_renaming(&f, &new_f, history_writer)?;
             }
-            ChangeStemResult::FailedToRename(err) => {
-                let f_str = f.to_string_lossy();
-                let err_mess = format!("Failed to rename. {}", err);
+            // Errors, already reported above.
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether `f` should take the name of the existing file `new_f`,
+    /// according to [DefaultArgs::on_conflict].
+    fn renamed_file_wins(&self, f: &Path, new_f: &Path) -> io::Result<bool> {
+        let (meta, existing_meta) = (fs::metadata(f)?, fs::metadata(new_f)?);
+        Ok(match self.data.on_conflict {
+            OnConflict::Newest => meta.modified()? > existing_meta.modified()?,
+            _ => meta.len() > existing_meta.len(),
+        })
+    }
+
+    /// Moves `f` to the backup directory, as it lost the conflict with the
+    /// existing file `new_f`, see [DefaultArgs::on_conflict].
+    fn back_up_renamed_file<W: Write>(
+        &mut self,
+        f: &Path,
+        new_f: &Path,
+        history_writer: &mut W,
+    ) -> anyhow::Result<()> {
+        let plan = plan_backup(f, new_f, &self.data.backup_dir_path, &get_now_str())?;
+        let ConflictResolution::Backup { backup_path } = plan.resolution else {
+            unreachable!("plan_backup always plans a backup.");
+        };
+        let res = Timings::time(&self.timings.fs_ops, || {
+            rename_with_retry(f, &backup_path, &self.data.retry_policy)
+        });
+        if let Err(err) = res {
+            let err_mess = format!("Failed to backup! {}", err);
+            return self.report_error(f, ErrorCategory::RenameFailed, &err_mess, history_writer);
+        }
+        self.recap(
+            Recap::Change('d', f, &backup_path),
+            Color::DarkGreen,
+            history_writer,
+        )?;
+        self.summary.backed_up += 1;
+
+        Ok(())
+    }
+
+    /// Returns what would be done with `f`, without doing it.
+    ///
+    /// In recursive mode, the files within `f` are pushed onto the files to
+    /// process, as found at their current path.
+    fn plan_file(&mut self, f: PathBuf) -> PlanRecord {
+        if let Some(reason) = self.exclusion_reason(&f) {
+            return PlanRecord {
+                from: f,
+                to: None,
+                decision: Decision::Exclude,
+                reason: Some(reason),
+            };
+        }
 
-                error_prompt(&f_str, &err_mess)?;
+        let res = self.change_stem_of_file(&f);
+        if let Some(err_mess) = self.error_message(&res) {
+            return PlanRecord {
+                from: f,
+                to: None,
+                decision: Decision::Error,
+                reason: Some(err_mess),
+            };
+        }
 
-                let recap_line = format!("(e) {}: {}", f_str, err_mess);
-                println!("{}", recap_line.clone().dark_red());
-                writeln!(history_writer, "{}", recap_line)
-                    .with_context(|| "Failed to write to history file.")?;
+        let (to, decision, reason) = match res {
+            ChangeStemResult::NewFileAlreadyExist(new_f) => {
+                let reason = format!("{} already exists.", new_f.to_string_lossy());
+                (Some(new_f), Decision::Conflict, Some(reason))
             }
             ChangeStemResult::NoNeedToRename => {
-                if self.data.recursive && !f.is_symlink() && f.is_dir() {
-                    for entry in WalkDir::new(f)
-                        .min_depth(1)
-                        .into_iter()
-                        .filter_map(|e| e.ok())
-                    {
-                        self.data.files.push(entry.path().to_owned());
-                    }
+                (None, Decision::Keep, Some(String::from(ALREADY_FORMATTED)))
+            }
+            ChangeStemResult::OnlyCaseDiffers => (
+                None,
+                Decision::Keep,
+                Some(String::from(
+                    "Only the case differs, which doesn't matter on this platform.",
+                )),
+            ),
+            ChangeStemResult::NotFromConvention => (
+                None,
+                Decision::Keep,
+                Some(String::from(
+                    "Doesn't follow the naming convention to format from.",
+                )),
+            ),
+            ChangeStemResult::NotOfMimeType => (
+                None,
+                Decision::Keep,
+                Some(String::from("Content isn't of the type to format.")),
+            ),
+            ChangeStemResult::NotMatchingPattern => (
+                None,
+                Decision::Keep,
+                Some(String::from("Doesn't match the pattern to rename by.")),
+            ),
+            ChangeStemResult::Ok(new_f) => (Some(new_f), Decision::Rename, None),
+            ChangeStemResult::OkLossy(new_f) => (
+                Some(new_f),
+                Decision::Rename,
+                Some(String::from(
+                    "Filename contains invalid Unicode, it would be converted lossily.",
+                )),
+            ),
+            // Errors, handled above.
+            _ => (None, Decision::Error, None),
+        };
+
+        if decision != Decision::Conflict {
+            self.push_children(&f, &f);
+        }
+
+        PlanRecord {
+            from: f,
+            to,
+            decision,
+            reason,
+        }
+    }
+
+    /// Prints what would be done with each file, see [DefaultArgs::dry_run].
+    fn plan_files(&mut self) -> anyhow::Result<()> {
+        let mut not_revertable = 0;
+        while let Some(f) = self.next_file() {
+            let record = self.plan_file(f);
+            self.print_plan_record(&record)?;
+            if self.data.verify_revert && !is_revertable(&record) {
+                not_revertable += 1;
+                writeln!(
+                    self.err,
+                    "{}",
+                    format!(
+                        "(w) {}: The renaming couldn't be reverted, its history line wouldn't be parsed back correctly.",
+                        self.display_path(&record.from)
+                    )
+                    .dark_yellow()
+                )?;
+            }
+        }
+
+        if not_revertable > 0 {
+            return Err(anyhow!(
+                "{} renaming(s) couldn't be reverted.",
+                not_revertable
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Prints the files excluded by a pattern as JSON, without renaming
+    /// anything, see [DefaultArgs::list_excluded].
+    fn list_excluded(&mut self) -> anyhow::Result<()> {
+        while let Some(f) = self.next_file() {
+            self.summary.visited += 1;
+            if f.file_name().is_none() {
+                continue;
+            }
+            match self.exclude_pattern_matching(&f) {
+                Some(pattern) => {
+                    let record = ExclusionRecord {
+                        pattern: pattern.to_string(),
+                        path: f,
+                    };
+                    writeln!(self.out, "{}", serde_json::to_string(&record)?)?;
                 }
+                None => self.push_children(&f, &f),
             }
-            ChangeStemResult::Ok(new_f) => {
-                let f_str = f.to_string_lossy();
-                let new_f_str = new_f.to_string_lossy();
+        }
 
-                let recap_line = format!("(d) {} -> {}", f_str, new_f_str);
-                println!("{}", recap_line.clone().dark_grey());
-                writeln!(history_writer, "{}", recap_line)
-                    .with_context(|| "Failed to write to history file.")?;
+        Ok(())
+    }
 
-                if self.data.recursive && !new_f.is_symlink() && new_f.is_dir() {
-                    for entry in WalkDir::new(new_f)
-                        .min_depth(1)
-                        .into_iter()
-                        .filter_map(|e| e.ok())
-                    {
-                        self.data.files.push(entry.path().to_owned());
+    /// Prints the plan of the paths of `listing` as JSON, without touching
+    /// the filesystem, see [DefaultArgs::from_listing].
+    fn plan_listing(&mut self, listing: &[PathBuf]) -> anyhow::Result<()> {
+        let listed: HashSet<&Path> = listing.iter().map(PathBuf::as_path).collect();
+        let mut new_paths = HashSet::new();
+        for path in listing {
+            let record = match target_path_for(path, &self.data.format_options) {
+                Ok(None) => PlanRecord {
+                    from: path.clone(),
+                    to: None,
+                    decision: Decision::Keep,
+                    reason: Some(String::from(ALREADY_FORMATTED)),
+                },
+                Ok(Some(new_path)) => {
+                    let reason = if listed.contains(new_path.as_path()) {
+                        Some(format!("{} is listed too.", new_path.to_string_lossy()))
+                    } else if new_paths.contains(&new_path) {
+                        Some(format!(
+                            "{} is already the new path of another one.",
+                            new_path.to_string_lossy()
+                        ))
+                    } else {
+                        None
+                    };
+                    new_paths.insert(new_path.clone());
+                    PlanRecord {
+                        from: path.clone(),
+                        to: Some(new_path),
+                        decision: if reason.is_some() {
+                            Decision::Conflict
+                        } else {
+                            Decision::Rename
+                        },
+                        reason,
                     }
                 }
+                Err(err) => PlanRecord {
+                    from: path.clone(),
+                    to: None,
+                    decision: Decision::Error,
+                    reason: Some(err.to_string()),
+                },
+            };
+            writeln!(self.out, "{}", serde_json::to_string(&record)?)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether files are only planned to be renamed, for the plan
+    /// to be printed, checked or edited.
+    fn plans_only(&self) -> bool {
+        self.data.dry_run
+            || self.data.check
+            || self.data.check_conflicts
+            || self.data.edit_plan
+            || self.data.emit_script.is_some()
+            || self.data.emit_diff
+            || self.data.list_excluded
+            || self.data.plan_out.is_some()
+    }
+
+    /// Plans the renamings of all the files, writing to stderr why the
+    /// others won't be renamed if they conflict or can't be.
+    fn plan_renamings(&mut self) -> anyhow::Result<Vec<Renaming>> {
+        let mut planned = vec![];
+        while let Some(f) = self.next_file() {
+            let record = self.plan_file(f);
+            match (record.decision, record.to) {
+                (Decision::Rename, Some(to)) => planned.push((record.from, to)),
+                (Decision::Conflict | Decision::Error, _) => writeln!(
+                    self.err,
+                    "{}",
+                    format!(
+                        "(w) {}: Not planned to be renamed. {}",
+                        self.display_path(&record.from),
+                        record.reason.unwrap_or_default()
+                    )
+                    .dark_yellow()
+                )?,
+                _ => {}
             }
         }
 
+        Ok(planned)
+    }
+
+    /// Prints a script doing the renamings, see [DefaultArgs::emit_script].
+    fn emit_script(&mut self, shell: ScriptShell) -> anyhow::Result<()> {
+        let mut renamings = self.plan_renamings()?;
+        // Files within renamed directories are listed with their current
+        // parent directory, so they must be renamed first.
+        renamings.sort_by_key(|(path, _)| Reverse(path.components().count()));
+        write!(self.out, "{}", rename_script(shell, &renamings))?;
+
+        Ok(())
+    }
+
+    /// Prints the renamings as a diff, see [DefaultArgs::emit_diff].
+    fn emit_diff(&mut self) -> anyhow::Result<()> {
+        let mut renamings: Vec<Renaming> = self
+            .plan_renamings()?
+            .iter()
+            .map(|(path, new_path)| {
+                (
+                    PathBuf::from(self.display_path(path).as_ref()),
+                    PathBuf::from(self.display_path(new_path).as_ref()),
+                )
+            })
+            .collect();
+        renamings.sort();
+        write!(self.out, "{}", rename_diff(&renamings))?;
+
+        Ok(())
+    }
+
+    /// Plans the renamings, lets the user edit them in their editor, then
+    /// does the edited ones, see [DefaultArgs::edit_plan].
+    fn edit_plan(&mut self, history_path: &Path) -> anyhow::Result<()> {
+        let planned = self.plan_renamings()?;
+        if planned.is_empty() {
+            writeln!(self.out, "Nothing to rename.")?;
+            return Ok(());
+        }
+
+        let plan_path = env::temp_dir().join(format!("fmtna_plan_{}.txt", get_now_str()));
+        fs::write(&plan_path, plan_file_content(&planned)).with_context(|| {
+            format!(
+                "Failed to write plan file ({}).",
+                plan_path.to_string_lossy()
+            )
+        })?;
+        let edited = open_in_editor(&self.data.editor, &plan_path).and_then(|()| {
+            fs::read_to_string(&plan_path).with_context(|| {
+                format!(
+                    "Failed to read plan file ({}).",
+                    plan_path.to_string_lossy()
+                )
+            })
+        });
+        let _ = fs::remove_file(&plan_path);
+        let renamings = parse_edited_plan(&edited?, &planned)
+            .map_err(|problems| plan_problems_error("edited plan", problems))?;
+
+        self.do_renamings(renamings, history_path)
+    }
+
+    /// Writes the planned renamings to `plan_path`, see [DefaultArgs::plan_out].
+    fn plan_out(&mut self, plan_path: &Path) -> anyhow::Result<()> {
+        let planned = self.plan_renamings()?;
+        fs::write(plan_path, plan_file_content(&planned)).with_context(|| {
+            format!(
+                "Failed to write plan file ({}).",
+                plan_path.to_string_lossy()
+            )
+        })?;
+        writeln!(
+            self.out,
+            "Wrote the plan of {} renaming(s) to {}.",
+            planned.len(),
+            plan_path.to_string_lossy()
+        )?;
+
         Ok(())
     }
-}
 
-impl Engine for DefaultEngine {
-    fn run(&mut self) -> anyhow::Result<()> {
-        // Create a backup file
-        // ^^^^^^^^^^^^^^^^^^^^
-        let mut history_path = HISTORY_DIR_PATH.clone();
-        history_path.push(get_now_str());
-        // Don't check if already exists as it shouldn't given the very precise time used for
-        // the name.
-        let history_file = File::create_new(history_path.clone())?;
-        let mut history_writer = BufWriter::new(history_file);
+    /// Does the renamings of the plan file at `plan_path`, see
+    /// [get_engine_applying_plan].
+    fn apply_plan(&mut self, plan_path: &Path, history_path: &Path) -> anyhow::Result<()> {
+        let content = fs::read_to_string(plan_path).with_context(|| {
+            format!(
+                "Failed to read plan file ({}).",
+                plan_path.to_string_lossy()
+            )
+        })?;
+        let renamings =
+            parse_saved_plan(&content).map_err(|problems| plan_problems_error("plan", problems))?;
 
-        // Process files
-        // ^^^^^^^^^^^^^
-        while let Some(f) = self.data.files.pop() {
-            self.process_file(f, &mut history_writer)?;
+        self.do_renamings(renamings, history_path)
+    }
+
+    /// Does `renamings`, as checked beforehand, writing the changes into the
+    /// history file at `history_path`.
+    fn do_renamings(
+        &mut self,
+        mut renamings: Vec<Renaming>,
+        history_path: &Path,
+    ) -> anyhow::Result<()> {
+        // Files within renamed directories are listed with their current
+        // parent directory, so they must be renamed first.
+        renamings.sort_by_key(|(path, _)| Reverse(path.components().count()));
+
+        fs::create_dir_all(&self.data.history_dir_path).with_context(|| {
+            format!(
+                "Failed to create history directory ({}).",
+                self.data.history_dir_path.to_string_lossy()
+            )
+        })?;
+        let mut history_writer = BufWriter::new(File::create_new(history_path)?);
+        for (path, new_path) in &renamings {
+            let res = Timings::time(&self.timings.fs_ops, || {
+                if self.data.git && git::is_tracked(path) {
+                    git::git_mv(path, new_path)
+                } else {
+                    rename_with_retry(path, new_path, &self.data.retry_policy)
+                }
+            });
+            match res {
+                Ok(()) => {
+                    self.recap(
+                        Recap::Change('d', path, new_path),
+                        Color::DarkGrey,
+                        &mut history_writer,
+                    )?;
+                    self.summary.renamed += 1;
+                }
+                Err(err) => {
+                    let err_mess = format!("Failed to rename. {}", err);
+                    self.summary
+                        .add_error(path, ErrorCategory::RenameFailed, &err_mess);
+                    self.recap(
+                        Recap::Note('e', path, &err_mess),
+                        Color::DarkRed,
+                        &mut history_writer,
+                    )?;
+                }
+            }
         }
 
-        // Flush the BufWriter before checking if the history file is empty or not
         history_writer.flush()?;
+        if file_is_empty(history_path)? {
+            fs::remove_file(history_path)?;
+        }
 
-        // Remove backup file if nothing was written to it.
-        // Could theorically avoid making it in the first place,
-        // but too unconvenient.
-        if file_is_empty(&history_path)? {
-            fs::remove_file(&history_path)?;
+        Ok(())
+    }
+
+    /// Prints the files that don't conform, i.e. that would be renamed or
+    /// couldn't be, and fails if there is any.
+    fn check(&mut self) -> anyhow::Result<()> {
+        let mut nb_offending = 0;
+        while let Some(f) = self.next_file() {
+            let record = self.plan_file(f);
+            let from_str = self.display_path(&record.from);
+            match record.decision {
+                Decision::Keep if self.conforms(&record) => {
+                    writeln!(self.out, "{}", conforming_line(&from_str))?;
+                    continue;
+                }
+                Decision::Keep | Decision::Exclude => continue,
+                Decision::Rename | Decision::Conflict => {
+                    let to = record.to.as_deref().unwrap_or(&record.from);
+                    writeln!(self.out, "{} -> {}", from_str, self.display_path(to))?;
+                }
+                Decision::Error => {
+                    let reason = record.reason.as_deref().unwrap_or_default();
+                    writeln!(self.out, "{}: {}", from_str, reason)?;
+                }
+            }
+            nb_offending += 1;
+        }
+
+        if nb_offending > 0 {
+            return Err(anyhow!("{} file(s) don't conform.", nb_offending));
+        }
+
+        Ok(())
+    }
+
+    /// Lists the files whose new path already exists, without renaming
+    /// anything, then fails if there is any.
+    fn check_conflicts(&mut self) -> anyhow::Result<()> {
+        let mut nb_conflicts = 0;
+        while let Some(f) = self.next_file() {
+            let record = self.plan_file(f);
+            if let (Decision::Conflict, Some(to)) = (record.decision, &record.to) {
+                writeln!(
+                    self.out,
+                    "{} -> {}",
+                    self.display_path(&record.from),
+                    self.display_path(to)
+                )?;
+                nb_conflicts += 1;
+            }
+        }
+
+        if nb_conflicts > 0 {
+            return Err(anyhow!("{} conflict(s) found.", nb_conflicts));
         }
 
         Ok(())
     }
+
+    fn print_plan_record(&mut self, record: &PlanRecord) -> anyhow::Result<()> {
+        if self.data.json {
+            writeln!(self.out, "{}", serde_json::to_string(record)?)?;
+            return Ok(());
+        }
+
+        let from_str = self.display_path(&record.from);
+        let to_str = record
+            .to
+            .as_ref()
+            .map(|to| self.display_path(to))
+            .unwrap_or_default();
+        let reason = record.reason.as_deref().unwrap_or_default();
+        match record.decision {
+            Decision::Rename => {
+                writeln!(
+                    self.out,
+                    "{}",
+                    format!("(d) {} -> {}", from_str, to_str).dark_grey()
+                )?;
+                if record.reason.is_some() {
+                    writeln!(
+                        self.out,
+                        "{}",
+                        format!("(w) {}: {}", from_str, reason).dark_yellow()
+                    )?;
+                }
+            }
+            Decision::Conflict => {
+                writeln!(
+                    self.out,
+                    "{}",
+                    format!("(c) {} -> {}: {}", from_str, to_str, reason).dark_blue()
+                )?;
+            }
+            Decision::Error => {
+                writeln!(
+                    self.out,
+                    "{}",
+                    format!("(e) {}: {}", from_str, reason).dark_red()
+                )?;
+            }
+            Decision::Keep if self.conforms(record) => {
+                writeln!(self.out, "{}", conforming_line(&from_str).dark_green())?;
+            }
+            Decision::Keep | Decision::Exclude => {}
+        }
+
+        Ok(())
+    }
+
+    /// Whether `record` is to be reported as conforming, i.e. if the file
+    /// already follows the naming convention and --report-conforming is set.
+    fn conforms(&self, record: &PlanRecord) -> bool {
+        self.data.report_conforming && record.reason.as_deref() == Some(ALREADY_FORMATTED)
+    }
+
+    /// Returns `p` as it should be shown to the user, i.e. relative to the
+    /// base directory if one was given (see [DefaultArgs::base]).
+    fn display_path<'a>(&self, p: &'a Path) -> Cow<'a, str> {
+        match self.data.base.as_deref().map(|base| p.strip_prefix(base)) {
+            Some(Ok(relative)) if relative.as_os_str().is_empty() => Cow::Borrowed("."),
+            Some(Ok(relative)) => relative.to_string_lossy(),
+            _ => p.to_string_lossy(),
+        }
+    }
+
+    /// Prints `recap` with the color `color`, writes it into `history_writer`,
+    /// and passes it to the observer, if any.
+    ///
+    /// Only the printed line has its paths shown relative to the base
+    /// directory, the history file always records absolute paths.
+    fn recap<W: Write>(
+        &mut self,
+        recap: Recap,
+        color: Color,
+        history_writer: &mut W,
+    ) -> anyhow::Result<()> {
+        // Only changes (and errors) are shown with --changed-only.
+        if !self.data.changed_only || matches!(recap.op(), 'd' | 'c' | 'b' | 'o' | 'l' | 'e') {
+            let line = match (&recap, &self.data.line_format) {
+                (Recap::Change(op, from, to), Some(line_format)) => fill_line_format(
+                    line_format,
+                    *op,
+                    &self.display_path(from),
+                    &self.display_path(to),
+                ),
+                _ => recap.line(|p| self.display_path(p)),
+            }
+            .with(color);
+            if recap.op() == 'e' {
+                writeln!(self.err, "{}", line)?;
+            } else if !self.data.summary_json {
+                writeln!(self.out, "{}", line)?;
+            }
+        }
+        writeln!(history_writer, "{}", recap.line(Path::to_string_lossy))
+            .with_context(|| "Failed to write to history file.")?;
+        if let Some(observer) = &mut self.observer {
+            observer(&recap.event());
+        }
+
+        Ok(())
+    }
+
+    /// Writes the meaning of each operation code, see [DefaultArgs::legend].
+    fn write_legend(&mut self) -> io::Result<()> {
+        writeln!(self.out, "Legend:")?;
+        for (op, meaning, color) in OPS {
+            writeln!(
+                self.out,
+                "  {}",
+                format!("({}) {}", op, meaning).with(color)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes the number of files per operation, see [DefaultArgs::legend].
+    fn write_op_counts(&mut self) -> io::Result<()> {
+        let counts = OPS.into_iter().filter_map(|(op, _, color)| {
+            let count = match op {
+                'd' => self.summary.renamed,
+                'c' => self.summary.copied,
+                's' => self.summary.skipped,
+                'b' => self.summary.backed_up,
+                'o' => self.summary.overwritten,
+                'k' => self.summary.locked,
+                'r' => self.summary.read_only,
+                'e' => self.summary.errors.len(),
+                _ => return None,
+            };
+            Some(format!("{} ({})", count, op).with(color).to_string())
+        });
+        let counts: Vec<_> = counts.collect();
+        writeln!(self.out, "Summary: {}", counts.join(", "))?;
+        if !self.summary.error_counts.is_empty() {
+            let error_counts: Vec<_> = self
+                .summary
+                .error_counts
+                .iter()
+                .map(|(category, count)| format!("{} {}", count, category))
+                .collect();
+            writeln!(
+                self.out,
+                "{}",
+                format!("Errors: {}", error_counts.join(", ")).dark_red()
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the filename of `new_f`, with the characters that changed
+    /// from the filename of `f` highlighted, see [DefaultArgs::verbose].
+    fn name_diff(&self, f: &Path, new_f: &Path) -> String {
+        let filename = |p: &Path| {
+            p.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned()
+        };
+        let colored = !self.data.no_color && !Colored::ansi_color_disabled_memoized();
+
+        diff_chars(&filename(f), &filename(new_f))
+            .into_iter()
+            .map(|chunk| match chunk {
+                DiffChunk::Same(s) => s,
+                DiffChunk::Removed(s) if colored => s.red().crossed_out().to_string(),
+                DiffChunk::Added(s) if colored => s.green().to_string(),
+                DiffChunk::Removed(s) => format!("[-{}-]", s),
+                DiffChunk::Added(s) => format!("{{+{}+}}", s),
+            })
+            .collect()
+    }
+
+    /// Returns whether as many files as [DefaultArgs::limit] allows have
+    /// been renamed.
+    fn limit_reached(&self) -> bool {
+        self.data
+            .limit
+            .is_some_and(|limit| self.summary.changed() >= limit)
+    }
+
+    /// Returns whether there were more conflicts than
+    /// [DefaultArgs::max_conflicts].
+    fn too_many_conflicts(&self) -> bool {
+        self.data
+            .max_conflicts
+            .is_some_and(|max_conflicts| self.summary.conflicts > max_conflicts)
+    }
+
+    /// Renames the files, recording the changes in a history file at
+    /// `history_path`.
+    fn rename_files(&mut self, history_path: &Path) -> anyhow::Result<()> {
+        // Create a backup file
+        // ^^^^^^^^^^^^^^^^^^^^
+        fs::create_dir_all(&self.data.history_dir_path).with_context(|| {
+            format!(
+                "Failed to create history directory ({}).",
+                self.data.history_dir_path.to_string_lossy()
+            )
+        })?;
+        // Don't check if already exists as it shouldn't given the very precise time used for
+        // the name.
+        let history_file = File::create_new(history_path)?;
+        let mut history_writer = BufWriter::new(history_file);
+
+        if let Some(output_dir) = &self.data.output_dir {
+            fs::create_dir_all(output_dir).with_context(|| {
+                format!(
+                    "Failed to create output directory ({}).",
+                    output_dir.to_string_lossy()
+                )
+            })?;
+        }
+
+        if self.data.legend {
+            self.write_legend()?;
+        }
+
+        // On a first Ctrl-C, finish the file being processed then stop. On a
+        // second one, quit right away.
+        let interruptions = Interruptions::handle()?;
+
+        // Process files
+        // ^^^^^^^^^^^^^
+        while let Some(f) = self.next_file() {
+            if let Err(err) = self.process_file(f, &mut history_writer) {
+                if self.data.transactional {
+                    history_writer.flush()?;
+                    return Err(self.roll_back(history_path, err));
+                }
+                return Err(err);
+            }
+            if interruptions.happened() || self.limit_reached() || self.too_many_conflicts() {
+                break;
+            }
+        }
+        if self.data.changed_only {
+            writeln!(
+                self.out,
+                "{} of {} files changed.",
+                self.summary.changed(),
+                self.summary.visited
+            )?;
+        }
+        if self.data.legend {
+            self.write_op_counts()?;
+        }
+
+        // Flush the BufWriter before checking if the history file is empty or not
+        history_writer.flush()?;
+
+        // Remove backup file if nothing was written to it.
+        // Could theorically avoid making it in the first place,
+        // but too unconvenient.
+        let history_is_empty = file_is_empty(history_path)?;
+        if history_is_empty {
+            fs::remove_file(history_path)?;
+        }
+
+        // Write the report
+        // ^^^^^^^^^^^^^^^^
+        if let Some(ref report_path) = self.data.report {
+            let report = Report {
+                history_file: if history_is_empty {
+                    None
+                } else {
+                    Some(history_path)
+                },
+                options: &self.data,
+                summary: &self.summary,
+            };
+            let report_file = File::create(report_path).with_context(|| {
+                format!(
+                    "Failed to create report file ({}).",
+                    report_path.to_string_lossy()
+                )
+            })?;
+            serde_json::to_writer_pretty(BufWriter::new(report_file), &report)
+                .with_context(|| "Failed to write to report file.")?;
+        }
+
+        // Save the files left
+        // ^^^^^^^^^^^^^^^^^^^
+        if !self.data.files.is_empty() {
+            fs::create_dir_all(&self.data.resume_dir_path).with_context(|| {
+                format!(
+                    "Failed to create resume directory ({}).",
+                    self.data.resume_dir_path.to_string_lossy()
+                )
+            })?;
+            let resume_path = self
+                .data
+                .resume_dir_path
+                .join(history_path.file_name().unwrap_or_default());
+            self.write_resume_file(&resume_path)?;
+            let mess = format!(
+                "{} files left. Continue with --resume {}",
+                self.data.files.len(),
+                resume_path.to_string_lossy()
+            );
+            if interruptions.happened() {
+                return Err(anyhow!("Interrupted, {}", mess));
+            }
+            if self.too_many_conflicts() {
+                return Err(anyhow!(
+                    "Aborted after more than {} conflicts, maybe the naming convention doesn't suit these files. \
+                     List the conflicts with --check-conflicts, or choose how to resolve them with --on-conflict. {}",
+                    self.data.max_conflicts.unwrap_or_default(),
+                    mess
+                ));
+            }
+            let limit_line = format!(
+                "Limit of {} renamings reached, {}",
+                self.data.limit.unwrap_or_default(),
+                mess
+            );
+            // Keep stdout for the summary only.
+            if self.data.summary_json {
+                writeln!(self.err, "{}", limit_line)?;
+            } else {
+                writeln!(self.out, "{}", limit_line)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prints the summary of the run as JSON, see [DefaultArgs::summary_json].
+    fn print_summary_json(
+        &mut self,
+        history_path: &Path,
+        res: &anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let summary_json = SummaryJson {
+            history_file: history_path.exists().then_some(history_path),
+            exit_status: if res.is_ok() { 0 } else { 1 },
+            summary: &self.summary,
+        };
+        writeln!(self.out, "{}", serde_json::to_string(&summary_json)?)?;
+
+        Ok(())
+    }
+
+    /// Reverts the changes recorded in the history file at `history_path`,
+    /// after the run failed with `err`, see [DefaultArgs::transactional].
+    ///
+    /// Returns the error to end the run with.
+    fn roll_back(&self, history_path: &Path, err: anyhow::Error) -> anyhow::Error {
+        let mut revert_engine = revert::get_rollback_engine(
+            history_path.to_owned(),
+            self.data.history_dir_path.clone(),
+            self.data.backup_dir_path.clone(),
+            self.data.lock_file_path.clone(),
+        );
+        match revert_engine.run() {
+            Ok(()) => err.context("The run failed, its changes were rolled back."),
+            Err(revert_err) => err.context(format!(
+                "The run failed, and rolling back its changes failed too ({}). Revert {} manually.",
+                revert_err,
+                history_path.to_string_lossy()
+            )),
+        }
+    }
+
+    /// Writes the files left to process to `resume_path`, one per line and
+    /// in the order of the queue, see [DefaultArgs::resume].
+    fn write_resume_file(&self, resume_path: &Path) -> anyhow::Result<()> {
+        let resume_file = File::create(resume_path).with_context(|| {
+            format!(
+                "Failed to create resume file ({}).",
+                resume_path.to_string_lossy()
+            )
+        })?;
+        let mut resume_writer = BufWriter::new(resume_file);
+        for f in &self.data.files {
+            writeln!(resume_writer, "{}", f.to_string_lossy())
+                .with_context(|| "Failed to write to resume file.")?;
+        }
+        resume_writer
+            .flush()
+            .with_context(|| "Failed to write to resume file.")
+    }
+
+    fn report_error<W: Write>(
+        &mut self,
+        f: &Path,
+        category: ErrorCategory,
+        err_mess: &str,
+        history_writer: &mut W,
+    ) -> anyhow::Result<()> {
+        if !self.data.yes_to_errors {
+            error_prompt(&self.display_path(f), err_mess)?;
+        }
+        self.summary.add_error(f, category, err_mess);
+        self.recap(
+            Recap::Note('e', f, err_mess),
+            Color::DarkRed,
+            history_writer,
+        )
+    }
+
+    fn report_renaming<W: Write>(
+        &mut self,
+        f: &Path,
+        new_f: &Path,
+        history_writer: &mut W,
+    ) -> anyhow::Result<()> {
+        if self.data.output_dir.is_some() {
+            return self.report_copy(f, new_f, history_writer);
+        }
+
+        self.recap(
+            Recap::Change('d', f, new_f),
+            Color::DarkGrey,
+            history_writer,
+        )?;
+        if self.data.verbose {
+            writeln!(self.out, "    {}", self.name_diff(f, new_f))?;
+        }
+        self.summary.renamed += 1;
+        self.leave_symlink(f, new_f, history_writer)?;
+        self.push_children(f, new_f);
+
+        Ok(())
+    }
+
+    /// Reports that `f` was copied into `new_f`, see [DefaultArgs::output_dir].
+    fn report_copy<W: Write>(
+        &mut self,
+        f: &Path,
+        new_f: &Path,
+        history_writer: &mut W,
+    ) -> anyhow::Result<()> {
+        self.recap(
+            Recap::Change('c', f, new_f),
+            Color::DarkGrey,
+            history_writer,
+        )?;
+        if self.data.verbose {
+            writeln!(self.out, "    {}", self.name_diff(f, new_f))?;
+        }
+        self.summary.copied += 1;
+        if new_f.is_dir() {
+            self.push_children(f, f);
+            self.output_dirs.insert(f.to_owned(), new_f.to_owned());
+        }
+
+        Ok(())
+    }
+
+    /// Resolves a conflict within the output directory as planned by `plan`,
+    /// copying instead of renaming, see [DefaultArgs::output_dir].
+    ///
+    /// The existing file is backed up with a plain renaming, so that
+    /// reverting restores it.
+    fn copy_with_conflict_plan<W: Write>(
+        &mut self,
+        plan: &ConflictPlan,
+        history_writer: &mut W,
+    ) -> anyhow::Result<()> {
+        let (f, new_f) = (&plan.path, &plan.new_path);
+        match plan.resolution {
+            ConflictResolution::Skip => {
+                self.recap(
+                    Recap::Change('s', f, new_f),
+                    Color::DarkBlue,
+                    history_writer,
+                )?;
+                self.summary.skipped += 1;
+                return Ok(());
+            }
+            ConflictResolution::Backup { ref backup_path } => {
+                Timings::time(&self.timings.fs_ops, || {
+                    rename_with_retry(new_f, backup_path, &self.data.retry_policy)
+                })
+                .with_context(|| {
+                    format!(
+                        "Failed to backup! Couldn't move {} to {}.",
+                        new_f.display(),
+                        backup_path.display()
+                    )
+                })?;
+                self.recap(
+                    Recap::Change('d', new_f, backup_path),
+                    Color::DarkGreen,
+                    history_writer,
+                )?;
+            }
+            ConflictResolution::Overwrite => {}
+        }
+
+        if let Err(err) = Timings::time(&self.timings.fs_ops, || copy_or_create_dir(f, new_f)) {
+            let err_mess = format!("Failed to copy. {}", err);
+            return self.report_error(f, ErrorCategory::CopyFailed, &err_mess, history_writer);
+        }
+        self.report_copy(f, new_f, history_writer)
+    }
+
+    /// Creates a symbolic link at `f` pointing to `new_f`, the path `f` was
+    /// renamed into, if asked to.
+    fn leave_symlink<W: Write>(
+        &mut self,
+        f: &Path,
+        new_f: &Path,
+        history_writer: &mut W,
+    ) -> anyhow::Result<()> {
+        if !self.data.leave_symlink {
+            return Ok(());
+        }
+
+        // Both are in the same directory, so the filename suffices and keeps
+        // the link valid if the directory is moved.
+        let target = new_f.file_name().map_or(new_f, Path::new);
+        if let Err(err) = symlink(target, f) {
+            let err_mess = format!("Failed to leave a symbolic link. {}", err);
+            return self.report_error(f, ErrorCategory::Other, &err_mess, history_writer);
+        }
+
+        self.recap(
+            Recap::Change('l', f, new_f),
+            Color::DarkCyan,
+            history_writer,
+        )
+    }
+
+    /// Writes the effective options on one line, see [Data::echo_config].
+    fn echo_config(&mut self) -> io::Result<()> {
+        let format_options = &self.data.format_options;
+        let naming_convention = format_options
+            .naming_convention
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default();
+        let exclude_file = match self.data.exclude_file_path {
+            Some(ref path) => path.to_string_lossy(),
+            None => Cow::Borrowed("none"),
+        };
+        writeln!(
+            self.err,
+            "Options: naming_convention={} keep_dots={} keep_special_chars={} keep_unicode={} recursive={} exclude_file={}",
+            naming_convention,
+            format_options.keep_dots,
+            format_options.keep_special_chars,
+            format_options.keep_unicode,
+            self.data.recursive,
+            exclude_file
+        )
+    }
+}
+
+impl<O: Write, E: Write> Engine for DefaultEngine<O, E> {
+    fn run(&mut self) -> anyhow::Result<()> {
+        let start = Instant::now();
+        let renames = self.data.edit_plan
+            || self.data.apply_plan.is_some()
+            || !(self.plans_only() || self.data.listing.is_some());
+        let _lock = if renames && !self.data.no_lock {
+            Some(RunLock::acquire(&self.data.lock_file_path, &mut self.err)?)
+        } else {
+            None
+        };
+        if self.data.echo_config {
+            self.echo_config()?;
+        }
+        let res = if let Some(listing) = self.data.listing.take() {
+            self.plan_listing(&listing)
+        } else if self.data.list_excluded {
+            self.list_excluded()
+        } else if self.data.dry_run {
+            self.plan_files()
+        } else if self.data.check {
+            self.check()
+        } else if self.data.check_conflicts {
+            self.check_conflicts()
+        } else if let Some(shell) = self.data.emit_script {
+            self.emit_script(shell)
+        } else if self.data.emit_diff {
+            self.emit_diff()
+        } else if let Some(plan_path) = self.data.plan_out.clone() {
+            self.plan_out(&plan_path)
+        } else if let Some(plan_path) = self.data.apply_plan.clone() {
+            self.apply_plan(&plan_path, &self.data.history_dir_path.join(get_now_str()))
+        } else if self.data.edit_plan {
+            self.edit_plan(&self.data.history_dir_path.join(get_now_str()))
+        } else {
+            let history_path = self.data.history_dir_path.join(get_now_str());
+            let res = self.rename_files(&history_path);
+            if self.data.summary_json {
+                self.print_summary_json(&history_path, &res)?;
+            }
+            res
+        };
+
+        if self.data.timings {
+            self.timings.write(start.elapsed(), &mut self.err)?;
+        }
+
+        res
+    }
+}
+
+/// A line recapping what happened to a file, as printed and recorded in the
+/// history file.
+enum Recap<'a> {
+    /// `(op) from -> to`
+    Change(char, &'a Path, &'a Path),
+    /// `(op) path: message`
+    Note(char, &'a Path, &'a str),
+}
+
+impl<'a> Recap<'a> {
+    /// Returns the code of the operation.
+    fn op(&self) -> char {
+        match *self {
+            Recap::Change(op, ..) | Recap::Note(op, ..) => op,
+        }
+    }
+
+    /// Returns the event to pass to the observer.
+    fn event(&self) -> RenameEvent {
+        match *self {
+            Recap::Change(op, from, to) => RenameEvent::change(op, from, to),
+            Recap::Note(op, path, message) => RenameEvent::note(op, path, message),
+        }
+    }
+
+    /// Returns the line, with paths shown by `display`.
+    fn line(&self, display: impl Fn(&'a Path) -> Cow<'a, str>) -> String {
+        match *self {
+            Recap::Change(op, from, to) => format!("({}) {} -> {}", op, display(from), display(to)),
+            Recap::Note(op, path, message) => format!("({}) {}: {}", op, display(path), message),
+        }
+    }
+}
+
+/// Returns the line of a change following `line_format`, see
+/// [DefaultArgs::line_format].
+fn fill_line_format(line_format: &str, op: char, from: &str, to: &str) -> String {
+    LINE_FORMAT_PLACEHOLDER_RE
+        .replace_all(line_format, |caps: &Captures| match &caps[1] {
+            "op" => op.to_string(),
+            "from" => from.to_string(),
+            "to" => to.to_string(),
+            _ => caps[0].to_string(),
+        })
+        .into_owned()
+}
+
+/// Returns whether the renaming planned by `record`, if any, would be
+/// recorded in the history file in a way that `fmtna revert` parses back,
+/// see [DefaultArgs::verify_revert].
+fn is_revertable(record: &PlanRecord) -> bool {
+    let to = match (&record.decision, &record.to) {
+        (Decision::Rename, Some(to)) => to,
+        _ => return true,
+    };
+
+    let line = Recap::Change('d', &record.from, to).line(Path::to_string_lossy);
+    !line.contains('\n')
+        && parse_history_line(&line).is_some_and(|(op, from, parsed_to)| {
+            op == "d" && from == record.from && parsed_to == *to
+        })
+}
+
+/// Returns the line reporting that the file shown as `f_str` already follows
+/// the naming convention, see [DefaultArgs::report_conforming].
+fn conforming_line(f_str: &str) -> String {
+    format!("(=) {}", f_str)
+}
+
+/// Copies `file` to `new_file`, or creates the directory `new_file` if `file`
+/// is a directory (its content is not copied).
+fn copy_or_create_dir(file: &Path, new_file: &Path) -> io::Result<()> {
+    if file.is_dir() {
+        fs::create_dir(new_file)
+    } else {
+        fs::copy(file, new_file).map(|_| ())
+    }
+}
+
+/// Returns the error reporting the `problems` found in the plan file, named
+/// `plan_name` in the message.
+fn plan_problems_error(plan_name: &str, problems: Vec<PlanProblem>) -> anyhow::Error {
+    let mut mess = format!(
+        "Found {} problem(s) in the {}, nothing was renamed:",
+        problems.len(),
+        plan_name
+    );
+    for (line_no, problem) in problems {
+        mess.push_str(&format!("\n    line {}: {}", line_no, problem));
+    }
+
+    anyhow!(mess)
+}
+
+/// Returns whether `err` means that the file is locked by another process.
+fn is_locked(err: &std::io::Error) -> bool {
+    // ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION
+    cfg!(windows) && matches!(err.raw_os_error(), Some(32) | Some(33))
+}
+
+/// The handling of Ctrl-C during a run, removed when dropped so as not to
+/// outlive it.
+///
+/// A first Ctrl-C is only recorded, a second one quits right away.
+struct Interruptions {
+    interrupted: Arc<AtomicBool>,
+    sig_ids: Vec<SigId>,
+}
+
+impl Interruptions {
+    fn handle() -> anyhow::Result<Self> {
+        let mut interruptions = Interruptions {
+            interrupted: Arc::new(AtomicBool::new(false)),
+            sig_ids: vec![],
+        };
+        // If registering the second handler fails, the first one is removed
+        // when dropping interruptions.
+        interruptions.sig_ids.push(
+            signal_hook::flag::register_conditional_shutdown(
+                SIGINT,
+                130,
+                Arc::clone(&interruptions.interrupted),
+            )
+            .with_context(|| "Failed to handle interruptions.")?,
+        );
+        interruptions.sig_ids.push(
+            signal_hook::flag::register(SIGINT, Arc::clone(&interruptions.interrupted))
+                .with_context(|| "Failed to handle interruptions.")?,
+        );
+
+        Ok(interruptions)
+    }
+
+    /// Returns whether Ctrl-C was pressed since handling it.
+    fn happened(&self) -> bool {
+        self.interrupted.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for Interruptions {
+    fn drop(&mut self) {
+        for sig_id in self.sig_ids.drain(..) {
+            signal_hook::low_level::unregister(sig_id);
+        }
+    }
+}
+
+/// The summary of a run printed with [DefaultArgs::summary_json].
+#[derive(Serialize)]
+struct SummaryJson<'a> {
+    history_file: Option<&'a Path>,
+    exit_status: i32,
+    #[serde(flatten)]
+    summary: &'a Summary,
+}
+
+/// A report of a run, see [DefaultArgs::report](crate::default::DefaultArgs::report).
+#[derive(Serialize)]
+struct Report<'a> {
+    history_file: Option<&'a Path>,
+    options: &'a Data,
+    #[serde(flatten)]
+    summary: &'a Summary,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::ConventionArgs;
+    use crate::default::{ConventionFor, ExtensionCase};
+    use crate::naming_conventions::NamingConvention;
+    use crate::paths::tests::mk_tmp_dir;
+    use regex::Regex;
+    use serial_test::serial;
+
+    fn mk_engine(cli: DefaultArgs) -> DefaultEngine {
+        let mut engine = DefaultEngine::new(cli, Cfg::default())
+            .expect("DefaultEngine::new should have succeeded.");
+        // Don't depend on the user's exclude file, nor pollute their history.
+        engine.data.exclude_patterns = vec![];
+        engine.data.exclude_file_path = None;
+        engine.data.history_dir_path = mk_tmp_dir("history");
+        engine.data.resume_dir_path = mk_tmp_dir("resume_files");
+        engine.data.backup_dir_path = mk_tmp_dir("backups");
+        engine.data.lock_file_path = mk_tmp_dir("lock").join("fmtna.lock");
+        engine
+    }
+
+    /// Returns an engine with the same data as one returned by [mk_engine],
+    /// capturing its output.
+    fn mk_captured_engine(data: Data) -> DefaultEngine<Vec<u8>, Vec<u8>> {
+        DefaultEngine {
+            data,
+            action: None,
+            summary: Summary::default(),
+            out: vec![],
+            err: vec![],
+            observer: None,
+            output_dirs: HashMap::new(),
+            timings: Timings::default(),
+            markers: RefCell::new(HashMap::new()),
+        }
+    }
+
+    #[cfg(unix)]
+    #[serial]
+    #[test]
+    fn invalid_unicode_filenames_are_renamed_lossily_only_if_allowed() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = mk_tmp_dir("invalid_unicode_filenames");
+        let file = dir.join(OsStr::from_bytes(b"Bad\xffName.txt"));
+        File::create(&file).unwrap();
+
+        let engine = mk_engine(DefaultArgs::default());
+        assert!(matches!(
+            engine.change_stem_of_file(&file),
+            ChangeStemResult::FileHasInvalidUnicode
+        ));
+        assert!(file.exists());
+
+        let engine = mk_engine(DefaultArgs {
+            allow_lossy_unicode: true,
+            ..Default::default()
+        });
+        match engine.change_stem_of_file(&file) {
+            ChangeStemResult::OkLossy(new_file) => {
+                assert_eq!(new_file, dir.join("bad_name.txt"));
+                assert!(new_file.exists());
+                assert!(!file.exists());
+            }
+            res => panic!("Expected OkLossy, but got {:?}", res),
+        }
+    }
+
+    #[serial]
+    #[test]
+    fn stems_shorter_than_min_length_are_not_renamed() {
+        let dir = mk_tmp_dir("stems_shorter_than_min_length");
+        let only_special = dir.join("!#&.txt");
+        File::create(&only_special).unwrap();
+        let short = dir.join("AB!.txt");
+        File::create(&short).unwrap();
+
+        // Below the threshold
+        let engine = mk_engine(DefaultArgs {
+            min_length: Some(1),
+            ..Default::default()
+        });
+        match engine.change_stem_of_file(&only_special) {
+            ChangeStemResult::NewStemTooShort(new_stem) => assert_eq!(new_stem, ""),
+            res => panic!("Expected NewStemTooShort, but got {:?}", res),
+        }
+        assert!(only_special.exists());
+
+        let engine = mk_engine(DefaultArgs {
+            min_length: Some(3),
+            ..Default::default()
+        });
+        match engine.change_stem_of_file(&short) {
+            ChangeStemResult::NewStemTooShort(new_stem) => assert_eq!(new_stem, "ab"),
+            res => panic!("Expected NewStemTooShort, but got {:?}", res),
+        }
+        assert!(short.exists());
+
+        // At the threshold
+        let engine = mk_engine(DefaultArgs {
+            min_length: Some(2),
+            ..Default::default()
+        });
+        match engine.change_stem_of_file(&short) {
+            ChangeStemResult::Ok(new_file) => assert_eq!(new_file, dir.join("ab.txt")),
+            res => panic!("Expected Ok, but got {:?}", res),
+        }
+    }
+
+    #[serial]
+    #[test]
+    fn long_stems_sharing_a_prefix_are_truncated_to_distinct_names() {
+        let dir = mk_tmp_dir("long_stems_sharing_a_prefix");
+        let files = [
+            dir.join("Quarterly Financial Report Draft 1.txt"),
+            dir.join("Quarterly Financial Report Draft 2.txt"),
+        ];
+        for f in &files {
+            File::create(f).unwrap();
+        }
+
+        let engine = mk_engine(DefaultArgs {
+            max_length: Some(16),
+            truncate_hash: true,
+            ..Default::default()
+        });
+        let new_files = files.clone().map(|f| match engine.change_stem_of_file(&f) {
+            ChangeStemResult::Ok(new_file) => new_file,
+            res => panic!("Expected Ok, but got {:?}", res),
+        });
+
+        assert_ne!(new_files[0], new_files[1]);
+        for new_file in &new_files {
+            assert!(new_file.exists());
+            assert_eq!(new_file.extension().unwrap(), "txt");
+            let new_stem = new_file.file_stem().unwrap().to_string_lossy();
+            assert_eq!(new_stem.chars().count(), 16);
+            assert!(new_stem.starts_with("quarterly_"));
+        }
+    }
+
+    #[serial]
+    #[test]
+    fn dry_run_records_explain_the_decisions() {
+        let dir = mk_tmp_dir("dry_run_records");
+        let excluded = dir.join("Build Output.log");
+        File::create(&excluded).unwrap();
+        let renamed = dir.join("Some File.txt");
+        File::create(&renamed).unwrap();
+
+        let mut engine = mk_engine(DefaultArgs {
+            dry_run: true,
+            json: true,
+            ..Default::default()
+        });
+        engine.data.exclude_patterns = vec![ExcludePattern::new(r".*\.log").unwrap()];
+
+        let record = serde_json::to_value(engine.plan_file(excluded.clone())).unwrap();
+        assert_eq!(record["decision"], "exclude");
+        assert_eq!(record["to"], serde_json::Value::Null);
+        assert_eq!(record["reason"], r"Excluded by pattern .*\.log.");
+
+        let record = serde_json::to_value(engine.plan_file(renamed.clone())).unwrap();
+        assert_eq!(record["decision"], "rename");
+        assert_eq!(
+            record["to"],
+            dir.join("some_file.txt").to_string_lossy().as_ref()
+        );
+        assert!(record.get("reason").is_none());
+
+        // Nothing was done.
+        assert!(excluded.exists());
+        assert!(renamed.exists());
+    }
+
+    #[serial]
+    #[test]
+    fn conventions_can_be_chosen_per_kind_of_file() {
+        let dir = mk_tmp_dir("conventions_per_kind_of_file");
+        let sub_dir = dir.join("some sub dir");
+        fs::create_dir(&sub_dir).unwrap();
+        let md_file = dir.join("Release Notes.MD");
+        File::create(&md_file).unwrap();
+        let other_file = dir.join("Release Notes.txt");
+        File::create(&other_file).unwrap();
+
+        let engine = mk_engine(DefaultArgs {
+            convention_for: vec![
+                "dir=PascalCase".parse().unwrap(),
+                ".md=kebab-case".parse().unwrap(),
+            ],
+            ..Default::default()
+        });
+        let expected = [
+            (sub_dir, dir.join("SomeSubDir")),
+            (md_file, dir.join("release-notes.MD")),
+            (other_file, dir.join("release_notes.txt")),
+        ];
+        for (f, expected_new_f) in expected {
+            match engine.change_stem_of_file(&f) {
+                ChangeStemResult::Ok(new_f) => assert_eq!(new_f, expected_new_f),
+                res => panic!("Expected Ok, but got {:?}", res),
+            }
+        }
+    }
+
+    #[test]
+    fn convention_for_is_parsed() {
+        assert_eq!(
+            ".md=kebab-case".parse::<ConventionFor>(),
+            Ok(ConventionFor {
+                kind: FileKind::Extension(String::from("md")),
+                naming_convention: NamingConvention::KebabCase,
+            })
+        );
+        assert!("dir".parse::<ConventionFor>().is_err());
+        assert!("md=kebab-case".parse::<ConventionFor>().is_err());
+        assert!("dir=Kebab".parse::<ConventionFor>().is_err());
+    }
+
+    #[serial]
+    #[test]
+    fn untransliterable_chars_are_errors_only_in_strict_ascii_mode() {
+        let dir = mk_tmp_dir("untransliterable_chars");
+        let f = dir.join("Done ✓.txt");
+        File::create(&f).unwrap();
+
+        let engine = mk_engine(DefaultArgs {
+            strict_ascii: true,
+            ..Default::default()
+        });
+        match engine.change_stem_of_file(&f) {
+            ChangeStemResult::UntransliterableChars(chars) => assert_eq!(chars, vec!['✓']),
+            res => panic!("Expected UntransliterableChars, but got {:?}", res),
+        }
+        assert!(f.exists());
+
+        // Silently dropped otherwise.
+        let engine = mk_engine(DefaultArgs::default());
+        match engine.change_stem_of_file(&f) {
+            ChangeStemResult::Ok(new_f) => assert!(new_f.to_string_lossy().is_ascii()),
+            res => panic!("Expected Ok, but got {:?}", res),
+        }
+    }
+
+    #[cfg(unix)]
+    #[serial]
+    #[test]
+    fn old_path_resolves_to_the_renamed_file_when_leaving_symlinks() {
+        let dir = mk_tmp_dir("leave_symlink");
+        let f = dir.join("Some File.txt");
+        fs::write(&f, "content").unwrap();
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: vec![f.clone()],
+            leave_symlink: true,
+            ..Default::default()
+        });
+        engine.run().unwrap();
+
+        let new_f = dir.join("some_file.txt");
+        assert!(new_f.is_file() && !new_f.is_symlink());
+        assert!(f.is_symlink());
+        assert_eq!(fs::read_link(&f).unwrap(), Path::new("some_file.txt"));
+        assert_eq!(fs::read_to_string(&f).unwrap(), "content");
+    }
+
+    #[serial]
+    #[test]
+    fn renamings_are_staged_in_git_mode() {
+        use std::process::Command;
+
+        let git = |dir: &Path, args: &[&str]| {
+            let output = Command::new("git")
+                .current_dir(dir)
+                .args([
+                    "-c",
+                    "user.name=fmtna",
+                    "-c",
+                    "user.email=fmtna@example.com",
+                ])
+                .args(args)
+                .output()
+                .expect("git should be installed.");
+            assert!(
+                output.status.success(),
+                "git {:?} failed: {:?}",
+                args,
+                output
+            );
+            String::from_utf8(output.stdout).unwrap()
+        };
+
+        let repo = mk_tmp_dir("git_mode");
+        git(&repo, &["init", "--quiet"]);
+        let tracked = repo.join("Tracked File.txt");
+        File::create(&tracked).unwrap();
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "--quiet", "-m", "Initial commit"]);
+        let untracked = repo.join("Untracked File.txt");
+        File::create(&untracked).unwrap();
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: vec![tracked, untracked],
+            git: true,
+            ..Default::default()
+        });
+        engine.run().unwrap();
+
+        assert!(repo.join("tracked_file.txt").exists());
+        assert!(repo.join("untracked_file.txt").exists());
+        let status = git(&repo, &["status", "--porcelain"]);
+        assert!(
+            status.contains("R  \"Tracked File.txt\" -> tracked_file.txt"),
+            "Unexpected git status: {}",
+            status
+        );
+        assert!(status.contains("?? untracked_file.txt"));
+    }
+
+    #[cfg(windows)]
+    #[serial]
+    #[test]
+    fn locked_files_are_skipped_if_asked_to() {
+        use std::fs::OpenOptions;
+        use std::os::windows::fs::OpenOptionsExt;
+
+        let dir = mk_tmp_dir("locked_files");
+        let f = dir.join("Locked File.txt");
+        File::create(&f).unwrap();
+        // Not shared with anyone, i.e. locked.
+        let _handle = OpenOptions::new()
+            .read(true)
+            .share_mode(0)
+            .open(&f)
+            .unwrap();
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: vec![f.clone()],
+            skip_locked: true,
+            ..Default::default()
+        });
+        engine.run().unwrap();
+
+        assert!(f.exists());
+        assert_eq!(engine.summary.locked, 1);
+        assert!(engine.summary.errors.is_empty());
+    }
+
+    #[serial]
+    #[test]
+    fn excluded_directories_are_not_descended_into() {
+        let dir = mk_tmp_dir("excluded_directories");
+        let conforming = dir.join("conforming");
+        let excluded = conforming.join("node_modules");
+        let excluded_file = excluded.join("Some File.txt");
+        let other_file = conforming.join("Other File.txt");
+        fs::create_dir_all(&excluded).unwrap();
+        File::create(&excluded_file).unwrap();
+        File::create(&other_file).unwrap();
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: vec![conforming.clone()],
+            recursive: true,
+            ..Default::default()
+        });
+        engine.data.exclude_patterns = vec![ExcludePattern::new("^node_modules$").unwrap()];
+        engine.run().unwrap();
+
+        assert!(excluded_file.exists());
+        assert!(!other_file.exists());
+        assert!(conforming.join("other_file.txt").exists());
+    }
+
+    #[serial]
+    #[test]
+    fn files_are_visited_in_the_chosen_order() {
+        let dir = mk_tmp_dir("visitation_order");
+        let root = dir.join("root");
+        fs::create_dir_all(root.join("a")).unwrap();
+        File::create(root.join("a").join("x")).unwrap();
+        File::create(root.join("b")).unwrap();
+
+        let visit = |order: TraversalOrder| {
+            let mut engine = mk_engine(DefaultArgs {
+                files: vec![root.clone()],
+                recursive: true,
+                order,
+                ..Default::default()
+            });
+            let mut visited = vec![];
+            while let Some(f) = engine.next_file() {
+                visited.push(f.strip_prefix(&dir).unwrap().to_owned());
+                engine.process_file(f, &mut std::io::sink()).unwrap();
+            }
+            visited
+        };
+
+        let paths = |ps: &[&str]| ps.iter().map(PathBuf::from).collect::<Vec<_>>();
+        assert_eq!(
+            visit(TraversalOrder::Depth),
+            paths(&["root", "root/a", "root/a/x", "root/b"])
+        );
+        assert_eq!(
+            visit(TraversalOrder::Breadth),
+            paths(&["root", "root/a", "root/b", "root/a/x"])
+        );
+    }
+
+    #[serial]
+    #[test]
+    fn check_lists_non_conforming_files_and_fails() {
+        let dir = mk_tmp_dir("check");
+        let conforming = dir.join("conforming.txt");
+        File::create(&conforming).unwrap();
+        let non_conforming = dir.join("Non Conforming.txt");
+        File::create(&non_conforming).unwrap();
+
+        let engine = mk_engine(DefaultArgs {
+            files: vec![dir.clone()],
+            recursive: true,
+            check: true,
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        assert!(engine.check().is_err());
+        assert_eq!(
+            String::from_utf8(engine.out).unwrap(),
+            format!(
+                "{} -> {}\n",
+                non_conforming.to_string_lossy(),
+                dir.join("non_conforming.txt").to_string_lossy()
+            )
+        );
+        assert!(non_conforming.exists());
+
+        fs::remove_file(&non_conforming).unwrap();
+        let engine = mk_engine(DefaultArgs {
+            files: vec![dir.clone()],
+            recursive: true,
+            check: true,
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        assert!(engine.check().is_ok());
+        assert!(engine.out.is_empty());
+    }
+
+    #[serial]
+    #[test]
+    fn check_lists_conforming_files_if_asked() {
+        let dir = mk_tmp_dir("report_conforming");
+        for f in [
+            "conforming.txt",
+            "Non Conforming.txt",
+            "other_conforming.md",
+        ] {
+            File::create(dir.join(f)).unwrap();
+        }
+
+        let engine = mk_engine(DefaultArgs {
+            files: vec![dir.clone()],
+            recursive: true,
+            check: true,
+            report_conforming: true,
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        assert!(engine.check().is_err());
+        assert_eq!(
+            String::from_utf8(engine.out).unwrap(),
+            format!(
+                "(=) {}\n{} -> {}\n(=) {}\n(=) {}\n",
+                dir.to_string_lossy(),
+                dir.join("Non Conforming.txt").to_string_lossy(),
+                dir.join("non_conforming.txt").to_string_lossy(),
+                dir.join("conforming.txt").to_string_lossy(),
+                dir.join("other_conforming.md").to_string_lossy(),
+            )
+        );
+    }
+
+    #[serial]
+    #[test]
+    fn paths_are_shown_relative_to_the_base() {
+        let dir = mk_tmp_dir("base");
+        fs::create_dir(dir.join("sub")).unwrap();
+        File::create(dir.join("sub").join("Some File.txt")).unwrap();
+
+        let engine = mk_engine(DefaultArgs {
+            files: vec![dir.join("sub")],
+            recursive: true,
+            base: Some(dir.clone()),
+            check: true,
+            report_conforming: true,
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        assert!(engine.check().is_err());
+        assert_eq!(
+            String::from_utf8(engine.out).unwrap(),
+            format!(
+                "(=) sub\n{} -> {}\n",
+                Path::new("sub").join("Some File.txt").to_string_lossy(),
+                Path::new("sub").join("some_file.txt").to_string_lossy()
+            )
+        );
+    }
+
+    #[serial]
+    #[test]
+    fn check_conflicts_lists_conflicts_and_fails() {
+        let dir = mk_tmp_dir("check_conflicts");
+        let conflicting = dir.join("Some File.txt");
+        let existing = dir.join("some_file.txt");
+        let other = dir.join("Other File.txt");
+        for f in [&conflicting, &existing, &other] {
+            File::create(f).unwrap();
+        }
+
+        let engine = mk_engine(DefaultArgs {
+            files: vec![dir.clone()],
+            recursive: true,
+            check_conflicts: true,
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        assert!(engine.check_conflicts().is_err());
+        assert_eq!(
+            String::from_utf8(engine.out).unwrap(),
+            format!(
+                "{} -> {}\n",
+                conflicting.to_string_lossy(),
+                existing.to_string_lossy()
+            )
+        );
+        for f in [&conflicting, &existing, &other] {
+            assert!(f.exists());
+        }
+    }
+
+    #[serial]
+    #[test]
+    fn parents_formats_ancestors_up_to_the_base() {
+        let dir = mk_tmp_dir("parents");
+        let inner_dir = dir.join("Outer Dir").join("Inner Dir");
+        fs::create_dir_all(&inner_dir).unwrap();
+        let file = inner_dir.join("Some File.txt");
+        File::create(&file).unwrap();
+        let sibling = dir.join("Outer Dir").join("Other File.txt");
+        File::create(&sibling).unwrap();
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: vec![file],
+            recursive: true,
+            parents: true,
+            base: Some(dir.clone()),
+            ..Default::default()
+        });
+        engine.run().expect("The run should have succeeded.");
+
+        assert!(dir
+            .join("outer_dir")
+            .join("inner_dir")
+            .join("some_file.txt")
+            .exists());
+        // Ancestors are not recursed into.
+        assert!(dir.join("outer_dir").join("Other File.txt").exists());
+    }
+
+    #[serial]
+    #[test]
+    fn changed_only_shows_nothing_but_changes() {
+        let dir = mk_tmp_dir("changed_only");
+        for f in ["A File.txt", "b_file.txt", "c.txt", "D File.txt"] {
+            File::create(dir.join(f)).unwrap();
+        }
+
+        let engine = mk_engine(DefaultArgs {
+            files: vec![dir.clone()],
+            recursive: true,
+            changed_only: true,
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        engine.run().expect("The run should have succeeded.");
+
+        let ansi_escapes = Regex::new(r"\x1b\[[0-9;]*m").unwrap();
+        let out = String::from_utf8(engine.out).unwrap();
+        assert_eq!(
+            ansi_escapes.replace_all(&out, ""),
+            format!(
+                "(d) {} -> {}\n(d) {} -> {}\n2 of 5 files changed.\n",
+                dir.join("A File.txt").to_string_lossy(),
+                dir.join("a_file.txt").to_string_lossy(),
+                dir.join("D File.txt").to_string_lossy(),
+                dir.join("d_file.txt").to_string_lossy(),
+            )
+        );
+    }
+
+    #[serial]
+    #[test]
+    fn leading_and_trailing_whitespace_is_removed() {
+        let dir = mk_tmp_dir("surrounding_whitespace");
+        let test_cases = [
+            (" A.txt", "a.txt"),
+            ("B .txt", "b.txt"),
+            ("c\t.txt", "c.txt"),
+            ("\tD.txt", "d.txt"),
+            ("e.txt ", "e.txt"),
+            ("f ", "f"),
+        ];
+        for (filename, _) in test_cases {
+            File::create(dir.join(filename)).unwrap();
+        }
+
+        for nc in [NamingConvention::SnakeCase, NamingConvention::Lower] {
+            let engine = mk_engine(DefaultArgs {
+                convention: ConventionArgs {
+                    naming_convention: Some(nc.clone()),
+                    keep_special_chars: true,
+                    ..Default::default()
+                },
+                dry_run: true,
+                ..Default::default()
+            });
+            for (filename, expected_filename) in test_cases {
+                let res = engine.change_stem_of_file(&dir.join(filename));
+                assert!(
+                    matches!(res, ChangeStemResult::Ok(ref new_file) if *new_file == dir.join(expected_filename)),
+                    "Expected {:?} to be renamed into {:?} in {:?}, but got {:?}.",
+                    filename,
+                    expected_filename,
+                    nc,
+                    res
+                );
+            }
+        }
+    }
+
+    #[serial]
+    #[test]
+    fn errors_are_only_recorded_when_yes_to_errors() {
+        let dir = mk_tmp_dir("yes_to_errors");
+        let files = ["A.txt", "B.txt", "Valid File.txt"].map(|f| dir.join(f));
+        for f in &files {
+            File::create(f).unwrap();
+        }
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: files.to_vec(),
+            min_length: Some(2),
+            yes_to_errors: true,
+            ..Default::default()
+        });
+        engine.run().expect("The run should have succeeded.");
+
+        let history_file = fs::read_dir(&engine.data.history_dir_path)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let history = fs::read_to_string(history_file).unwrap();
+        let errors = history.lines().filter(|l| l.starts_with("(e)")).count();
+        assert_eq!(errors, 2);
+        assert_eq!(engine.summary.errors.len(), 2);
+        assert!(dir.join("valid_file.txt").exists());
+    }
+
+    #[serial]
+    #[test]
+    fn verbose_highlights_what_changed_in_filenames() {
+        let dir = mk_tmp_dir("verbose_diff");
+        let file = dir.join("My File.txt");
+        File::create(&file).unwrap();
+
+        let engine = mk_engine(DefaultArgs {
+            files: vec![file],
+            verbose: true,
+            no_color: true,
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        engine.run().expect("The run should have succeeded.");
+
+        let ansi_escapes = Regex::new(r"\x1b\[[0-9;]*m").unwrap();
+        let out = String::from_utf8(engine.out).unwrap();
+        assert!(ansi_escapes
+            .replace_all(&out, "")
+            .contains("\n    [-M-]{+m+}y[- F-]{+_f+}ile.txt\n"));
+    }
+
+    #[serial]
+    #[test]
+    fn interrupted_runs_can_be_resumed() {
+        let dir = mk_tmp_dir("resume");
+        for f in ["A File.txt", "B File.txt", "C File.txt", "D File.txt"] {
+            File::create(dir.join(f)).unwrap();
+        }
+        let mut history_writer = vec![];
+
+        // Interrupt after the directory and two of its files.
+        let mut engine = mk_engine(DefaultArgs {
+            files: vec![dir.clone()],
+            recursive: true,
+            ..Default::default()
+        });
+        for _ in 0..3 {
+            let f = engine.next_file().unwrap();
+            engine.process_file(f, &mut history_writer).unwrap();
+        }
+        let resume_path = dir.join("resume.txt");
+        engine.write_resume_file(&resume_path).unwrap();
+        let renamed = ["a_file.txt", "b_file.txt", "c_file.txt", "d_file.txt"]
+            .iter()
+            .filter(|f| dir.join(f).exists())
+            .count();
+        assert_eq!(renamed, 2);
+
+        let mut engine = mk_engine(DefaultArgs {
+            resume: Some(resume_path),
+            ..Default::default()
+        });
+        assert_eq!(engine.data.files.len(), 2);
+        engine
+            .run()
+            .expect("The resumed run should have succeeded.");
+        for f in ["a_file.txt", "b_file.txt", "c_file.txt", "d_file.txt"] {
+            assert!(dir.join(f).exists(), "{} should exist.", f);
+        }
+    }
+
+    #[test]
+    fn interruptions_are_not_handled_past_the_run() {
+        let interruptions = Interruptions::handle().unwrap();
+        let sig_ids = interruptions.sig_ids.clone();
+        drop(interruptions);
+        for sig_id in sig_ids {
+            assert!(!signal_hook::low_level::unregister(sig_id));
+        }
+    }
+
+    #[serial]
+    #[test]
+    fn no_more_files_than_the_limit_are_renamed() {
+        let dir = mk_tmp_dir("limit");
+        let filenames = ["A File.txt", "B File.txt", "C File.txt", "D File.txt"];
+        for f in filenames {
+            File::create(dir.join(f)).unwrap();
+        }
+
+        let engine = mk_engine(DefaultArgs {
+            files: filenames.iter().map(|f| dir.join(f)).collect(),
+            limit: Some(2),
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        engine.run().expect("The run should have succeeded.");
+
+        assert_eq!(engine.summary.renamed, 2);
+        let left = filenames.iter().filter(|f| dir.join(f).exists()).count();
+        assert_eq!(left, 2);
+        assert_eq!(engine.data.files.len(), 2);
+        let out = String::from_utf8(engine.out).unwrap();
+        assert!(out.contains("Limit of 2 renamings reached, 2 files left."));
+    }
+
+    #[serial]
+    #[test]
+    fn results_go_to_stdout_and_errors_to_stderr() {
+        let dir = mk_tmp_dir("streams");
+        let files = ["A.txt", "My File.txt"].map(|f| dir.join(f));
+        for f in &files {
+            File::create(f).unwrap();
+        }
+
+        let engine = mk_engine(DefaultArgs {
+            files: files.to_vec(),
+            min_length: Some(2),
+            yes_to_errors: true,
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        engine.run().expect("The run should have succeeded.");
+
+        let out = String::from_utf8(engine.out).unwrap();
+        let err = String::from_utf8(engine.err).unwrap();
+        assert!(out.contains("(d) "));
+        assert!(!out.contains("(e) "));
+        assert!(err.contains("(e) "));
+        assert!(!err.contains("(d) "));
+    }
+
+    #[serial]
+    #[test]
+    fn printed_lines_follow_the_line_format() {
+        let dir = mk_tmp_dir("line_format");
+        let file = dir.join("My File.txt");
+        File::create(&file).unwrap();
+
+        let engine = mk_engine(DefaultArgs {
+            files: vec![file.clone()],
+            line_format: Some(String::from("{op};{from};{to}")),
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        engine.run().expect("The run should have succeeded.");
+
+        let ansi_escapes = Regex::new(r"\x1b\[[0-9;]*m").unwrap();
+        let out = String::from_utf8(engine.out).unwrap();
+        let new_file = dir.join("my_file.txt");
+        assert_eq!(
+            ansi_escapes.replace_all(&out, ""),
+            format!(
+                "d;{};{}\n",
+                file.to_string_lossy(),
+                new_file.to_string_lossy()
+            )
+        );
+
+        // The history file keeps the usual format.
+        let history_file = fs::read_dir(&engine.data.history_dir_path)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        assert_eq!(
+            fs::read_to_string(history_file).unwrap(),
+            format!(
+                "(d) {} -> {}\n",
+                file.to_string_lossy(),
+                new_file.to_string_lossy()
+            )
+        );
+    }
+
+    #[serial]
+    #[test]
+    fn fatal_errors_roll_back_the_run_if_transactional() {
+        let dir = mk_tmp_dir("transactional");
+        File::create(dir.join("A File.txt")).unwrap();
+        File::create(dir.join("B File.txt")).unwrap();
+        // Overwriting a non-empty directory with a file fails.
+        fs::create_dir_all(dir.join("b_file.txt").join("child")).unwrap();
+
+        let mut engine = mk_engine(DefaultArgs {
+            // Processed from the last one.
+            files: vec![dir.join("B File.txt"), dir.join("A File.txt")],
+            transactional: true,
+            ..Default::default()
+        });
+        engine.action = Some(Action::Overwrite);
+        assert!(engine.run().is_err());
+
+        assert!(dir.join("A File.txt").exists());
+        assert!(!dir.join("a_file.txt").exists());
+        assert!(dir.join("B File.txt").exists());
+    }
+
+    #[serial]
+    #[test]
+    fn summary_json_is_the_only_output() {
+        let dir = mk_tmp_dir("summary_json");
+        for f in ["A File.txt", "B File.txt", "b_file.txt", "c.txt", "D.txt"] {
+            File::create(dir.join(f)).unwrap();
+        }
+
+        let engine = mk_engine(DefaultArgs {
+            files: vec![dir.clone()],
+            recursive: true,
+            min_length: Some(2),
+            yes_to_errors: true,
+            summary_json: true,
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        engine.action = Some(Action::Skip);
+        engine.run().expect("The run should have succeeded.");
+
+        let out = String::from_utf8(engine.out).unwrap();
+        assert_eq!(out.lines().count(), 1);
+        let summary: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(summary["exit_status"], 0);
+        assert_eq!(summary["visited"], 6);
+        assert_eq!(summary["renamed"], 1);
+        assert_eq!(summary["skipped"], 1);
+        assert_eq!(summary["backed_up"], 0);
+        assert_eq!(summary["overwritten"], 0);
+        assert_eq!(summary["errors"].as_array().unwrap().len(), 2);
+        let history_file = fs::read_dir(&engine.data.history_dir_path)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        assert_eq!(
+            summary["history_file"],
+            history_file.to_string_lossy().as_ref()
+        );
+    }
+
+    #[serial]
+    #[test]
+    fn observers_are_invoked_for_each_processed_file() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let dir = mk_tmp_dir("observer");
+        let files = ["A.txt", "My File.txt"].map(|f| dir.join(f));
+        for f in &files {
+            File::create(f).unwrap();
+        }
+
+        let events = Rc::new(RefCell::new(vec![]));
+        let events_clone = Rc::clone(&events);
+        let mut engine = mk_engine(DefaultArgs {
+            files: files.to_vec(),
+            min_length: Some(2),
+            yes_to_errors: true,
+            ..Default::default()
+        })
+        .with_observer(Box::new(move |event: &RenameEvent| {
+            events_clone.borrow_mut().push(event.clone())
+        }));
+        engine.run().expect("The run should have succeeded.");
+
+        let mut events = events.take();
+        events.sort_by_key(|event| event.op);
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[0],
+            RenameEvent {
+                op: 'd',
+                path: dir.join("My File.txt"),
+                new_path: Some(dir.join("my_file.txt")),
+                message: None,
+            }
+        );
+        assert_eq!(events[1].op, 'e');
+        assert_eq!(events[1].path, dir.join("A.txt"));
+        assert!(events[1].message.is_some());
+    }
+
+    /// Runs with `on_conflict` on a conflict between "My File.txt" and
+    /// "my_file.txt", created by `mk_files`, then returns the content of
+    /// "my_file.txt" and of the backed up file.
+    fn resolve_conflict_with(
+        on_conflict: OnConflict,
+        mk_files: impl FnOnce(&Path, &Path),
+    ) -> (String, String) {
+        let dir = mk_tmp_dir("on_conflict");
+        let (file, existing) = (dir.join("My File.txt"), dir.join("my_file.txt"));
+        mk_files(&file, &existing);
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: vec![file.clone()],
+            on_conflict,
+            ..Default::default()
+        });
+        engine.run().expect("The run should have succeeded.");
+
+        assert!(!file.exists());
+        let backups: Vec<_> = fs::read_dir(&engine.data.backup_dir_path)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect();
+        assert_eq!(backups.len(), 1);
+        (
+            fs::read_to_string(existing).unwrap(),
+            fs::read_to_string(&backups[0]).unwrap(),
+        )
+    }
+
+    #[serial]
+    #[test]
+    fn conflicts_keep_the_newest_file_if_asked() {
+        let now = std::time::SystemTime::now();
+        let an_hour_ago = now - std::time::Duration::from_secs(3600);
+        let mk_files = |older: &Path, newer: &Path| {
+            fs::write(older, "older").unwrap();
+            fs::write(newer, "newer").unwrap();
+            File::options()
+                .write(true)
+                .open(older)
+                .unwrap()
+                .set_modified(an_hour_ago)
+                .unwrap();
+            File::options()
+                .write(true)
+                .open(newer)
+                .unwrap()
+                .set_modified(now)
+                .unwrap();
+        };
+
+        let (kept, backed_up) = resolve_conflict_with(OnConflict::Newest, |file, existing| {
+            mk_files(existing, file)
+        });
+        assert_eq!((kept.as_str(), backed_up.as_str()), ("newer", "older"));
+
+        let (kept, backed_up) = resolve_conflict_with(OnConflict::Newest, |file, existing| {
+            mk_files(file, existing)
+        });
+        assert_eq!((kept.as_str(), backed_up.as_str()), ("newer", "older"));
+    }
+
+    #[serial]
+    #[test]
+    fn conflicts_keep_the_largest_file_if_asked() {
+        let mk_files = |smaller: &Path, larger: &Path| {
+            fs::write(smaller, "small").unwrap();
+            fs::write(larger, "larger").unwrap();
+        };
+
+        let (kept, backed_up) = resolve_conflict_with(OnConflict::Largest, |file, existing| {
+            mk_files(existing, file)
+        });
+        assert_eq!((kept.as_str(), backed_up.as_str()), ("larger", "small"));
+
+        let (kept, backed_up) = resolve_conflict_with(OnConflict::Largest, |file, existing| {
+            mk_files(file, existing)
+        });
+        assert_eq!((kept.as_str(), backed_up.as_str()), ("larger", "small"));
+    }
+
+    #[serial]
+    #[test]
+    fn formatted_copies_are_collected_in_the_output_dir() {
+        let dir = mk_tmp_dir("output_dir");
+        let (src, out) = (dir.join("src"), dir.join("out"));
+        fs::create_dir_all(src.join("My Dir")).unwrap();
+        fs::write(src.join("My Dir").join("Some File.txt"), "content").unwrap();
+        File::create(src.join("conforming.txt")).unwrap();
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: vec![src.join("My Dir"), src.join("conforming.txt")],
+            recursive: true,
+            output_dir: Some(out.clone()),
+            ..Default::default()
+        });
+        engine.run().expect("The run should have succeeded.");
+
+        assert!(src.join("My Dir").join("Some File.txt").exists());
+        assert!(src.join("conforming.txt").exists());
+        assert_eq!(
+            fs::read_to_string(out.join("my_dir").join("some_file.txt")).unwrap(),
+            "content"
+        );
+        assert!(out.join("conforming.txt").exists());
+        assert_eq!(engine.summary.copied, 3);
+
+        // Reverting removes the copies only.
+        let history_file = fs::read_dir(&engine.data.history_dir_path)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let revert_cli = revert::RevertCli {
+            history_file: Some(history_file),
+            last: false,
+            history_dir: Some(engine.data.history_dir_path.clone()),
+            local: false,
+            ops: vec![],
+            lenient: false,
+            chronological: false,
+        };
+        revert::get_engine(revert_cli, Cfg::default())
+            .and_then(|mut engine| engine.run())
+            .expect("The revert should have succeeded.");
+        assert_eq!(fs::read_dir(&out).unwrap().count(), 0);
+        assert!(src.join("My Dir").join("Some File.txt").exists());
+    }
+
+    #[serial]
+    #[test]
+    fn timings_are_printed_to_stderr_if_asked() {
+        let dir = mk_tmp_dir("timings");
+        fs::create_dir(dir.join("My Dir")).unwrap();
+        File::create(dir.join("My Dir").join("My File.txt")).unwrap();
+
+        let engine = mk_engine(DefaultArgs {
+            files: vec![dir.join("My Dir")],
+            recursive: true,
+            timings: true,
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        engine.run().expect("The run should have succeeded.");
+
+        let out = String::from_utf8(engine.out).unwrap();
+        let err = String::from_utf8(engine.err).unwrap();
+        assert!(!out.contains("Timings:"));
+        let mut lines = err.lines().skip_while(|l| *l != "Timings:").skip(1);
+        for step in [
+            "traversal",
+            "name computation",
+            "filesystem operations",
+            "total",
+        ] {
+            let line = lines.next().expect("A timing line is missing.");
+            let value = line
+                .strip_prefix(&format!("  {}: ", step))
+                .expect("Unexpected timing line.");
+            let number: f64 = value
+                .trim_end_matches(char::is_alphabetic)
+                .parse()
+                .expect("The timing should be a number.");
+            assert!(number >= 0.0);
+        }
+    }
+
+    #[serial]
+    #[test]
+    fn marker_files_set_the_options_of_the_files_below_them() {
+        let dir = mk_tmp_dir("markers");
+        for (project, marker) in [
+            ("kebab", Some("naming_convention = \"kebab-case\"\n")),
+            ("pascal", Some("naming_convention = \"PascalCase\"\n")),
+            ("none", None),
+        ] {
+            let project = dir.join(project);
+            fs::create_dir_all(project.join("Sub Dir")).unwrap();
+            File::create(project.join("Sub Dir").join("My File.txt")).unwrap();
+            if let Some(marker) = marker {
+                fs::write(project.join(MARKER_FILE_NAME), marker).unwrap();
+            }
+        }
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: ["kebab", "pascal", "none"]
+                .map(|project| dir.join(project).join("Sub Dir"))
+                .to_vec(),
+            recursive: true,
+            ..Default::default()
+        });
+        engine.run().expect("The run should have succeeded.");
+
+        assert!(dir.join("kebab/sub-dir/my-file.txt").exists());
+        assert!(dir.join("pascal/SubDir/MyFile.txt").exists());
+        assert!(dir.join("none/sub_dir/my_file.txt").exists());
+    }
+
+    #[serial]
+    #[test]
+    fn renamings_that_couldnt_be_reverted_are_flagged() {
+        let dir = mk_tmp_dir("verify_revert");
+        let files = ["A -> B.txt", "My File.txt"].map(|f| dir.join(f));
+        for f in &files {
+            File::create(f).unwrap();
+        }
+
+        let engine = mk_engine(DefaultArgs {
+            files: files.to_vec(),
+            convention: ConventionArgs {
+                case_only: true,
+                ..Default::default()
+            },
+            dry_run: true,
+            verify_revert: true,
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        assert!(engine.run().is_err());
+
+        let err = String::from_utf8(engine.err).unwrap();
+        let flagged: Vec<_> = err.lines().filter(|l| l.contains("(w) ")).collect();
+        assert_eq!(flagged.len(), 1);
+        assert!(flagged[0].contains("A -> B.txt"));
+    }
+
+    #[serial]
+    #[test]
+    fn extensions_are_put_in_the_chosen_case() {
+        for (extension_case, expected) in [
+            (ExtensionCase::Keep, "file.JPG"),
+            (ExtensionCase::Lower, "file.jpg"),
+            (ExtensionCase::Upper, "file.JPG"),
+        ] {
+            let dir = mk_tmp_dir("extension_case");
+            let file = dir.join("File.JPG");
+            File::create(&file).unwrap();
+
+            let mut engine = mk_engine(DefaultArgs {
+                files: vec![file],
+                extension_case,
+                ..Default::default()
+            });
+            engine.run().expect("The run should have succeeded.");
+
+            let filenames: Vec<_> = fs::read_dir(&dir)
+                .unwrap()
+                .map(|e| e.unwrap().file_name())
+                .collect();
+            assert_eq!(filenames, [expected], "with {:?}", extension_case);
+        }
+    }
+
+    #[serial]
+    #[test]
+    fn the_legend_explains_every_operation_code() {
+        let dir = mk_tmp_dir("legend");
+        let files = ["A.txt", "My File.txt"].map(|f| dir.join(f));
+        for f in &files {
+            File::create(f).unwrap();
+        }
+
+        let engine = mk_engine(DefaultArgs {
+            files: files.to_vec(),
+            min_length: Some(2),
+            yes_to_errors: true,
+            legend: true,
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        engine.run().expect("The run should have succeeded.");
+
+        let out = String::from_utf8(engine.out).unwrap();
+        let legend: Vec<_> = out
+            .lines()
+            .skip_while(|l| *l != "Legend:")
+            .skip(1)
+            .take_while(|l| l.starts_with("  "))
+            .collect();
+        for op in ["(d)", "(s)", "(b)", "(o)", "(e)"] {
+            assert!(legend.iter().any(|l| l.contains(op)), "{} is missing", op);
+        }
+        let summary = out.lines().find(|l| l.starts_with("Summary: ")).unwrap();
+        assert!(summary.contains("1 (d)"));
+        assert!(summary.contains("1 (e)"));
+    }
+
+    #[serial]
+    #[test]
+    fn only_files_following_the_from_convention_are_formatted() {
+        let dir = mk_tmp_dir("from_convention");
+        for f in [
+            "my-notes.txt",
+            "old-report.md",
+            "MyPhotos.jpg",
+            "Some File.txt",
+        ] {
+            File::create(dir.join(f)).unwrap();
+        }
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: vec![dir.clone()],
+            recursive: true,
+            convention: ConventionArgs {
+                naming_convention: Some(NamingConvention::SnakeCase),
+                ..Default::default()
+            },
+            from: Some(NamingConvention::KebabCase),
+            ..Default::default()
+        });
+        engine.run().expect("The run should have succeeded.");
+
+        let mut filenames: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        filenames.sort();
+        assert_eq!(
+            filenames,
+            [
+                "MyPhotos.jpg",
+                "Some File.txt",
+                "my_notes.txt",
+                "old_report.md"
+            ]
+        );
+    }
+
+    #[serial]
+    #[test]
+    fn extensionless_files_are_filtered_by_their_content_type() {
+        let dir = mk_tmp_dir("by_mime");
+        fs::write(
+            dir.join("Holiday Photo"),
+            b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01\0\0\0\x01",
+        )
+        .unwrap();
+        fs::write(dir.join("Read Me"), "Some notes.\n").unwrap();
+        fs::write(dir.join("My Notes.txt"), "Some notes.\n").unwrap();
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: vec![dir.clone()],
+            recursive: true,
+            by_mime: Some(String::from("text")),
+            ..Default::default()
+        });
+        engine.run().expect("The run should have succeeded.");
+
+        let mut filenames: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        filenames.sort();
+        assert_eq!(filenames, ["Holiday Photo", "my_notes.txt", "read_me"]);
+    }
+
+    #[serial]
+    #[test]
+    fn listings_are_planned_without_touching_the_filesystem() {
+        let dir = mk_tmp_dir("from_listing");
+        let listing = dir.join("listing.txt");
+        fs::write(
+            &listing,
+            "My Archive/\nMy Archive/Some Notes.txt\nMy Archive/some_notes.txt\n\nMy Archive/done.txt\n",
+        )
+        .unwrap();
+
+        let engine = mk_engine(DefaultArgs {
+            from_listing: Some(listing),
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        engine.run().expect("The run should have succeeded.");
+
+        let plan: Vec<serde_json::Value> = String::from_utf8(engine.out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            plan,
+            [
+                serde_json::json!({"from": "My Archive/", "to": "my_archive", "decision": "rename"}),
+                serde_json::json!({
+                    "from": "My Archive/Some Notes.txt",
+                    "to": "My Archive/some_notes.txt",
+                    "decision": "conflict",
+                    "reason": "My Archive/some_notes.txt is listed too.",
+                }),
+                serde_json::json!({
+                    "from": "My Archive/some_notes.txt",
+                    "to": null,
+                    "decision": "keep",
+                    "reason": ALREADY_FORMATTED,
+                }),
+                serde_json::json!({
+                    "from": "My Archive/done.txt",
+                    "to": null,
+                    "decision": "keep",
+                    "reason": ALREADY_FORMATTED,
+                }),
+            ]
+        );
+        assert!(!dir.join("My Archive").exists());
+    }
+
+    #[serial]
+    #[test]
+    fn files_can_be_excluded_by_the_name_of_their_parent_directory() {
+        let dir = mk_tmp_dir("parent_exclude");
+        fs::create_dir_all(dir.join("vendor")).unwrap();
+        fs::create_dir_all(dir.join("src").join("vendor")).unwrap();
+        for f in [
+            "vendor/Some Lib.js",
+            "src/vendor/Other Lib.js",
+            "src/My App.js",
+        ] {
+            File::create(dir.join(f)).unwrap();
+        }
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: vec![dir.clone()],
+            recursive: true,
+            ..Default::default()
+        });
+        engine.data.exclude_patterns = vec![ExcludePattern::new("parent:^vendor$").unwrap()];
+        engine.run().expect("The run should have succeeded.");
+
+        assert!(dir.join("vendor").join("Some Lib.js").exists());
+        assert!(dir.join("src").join("vendor").join("Other Lib.js").exists());
+        assert!(dir.join("src").join("my_app.js").exists());
+    }
+
+    #[serial]
+    #[test]
+    fn excluded_files_are_listed_with_their_pattern() {
+        let dir = mk_tmp_dir("list_excluded");
+        fs::create_dir_all(dir.join("node_modules")).unwrap();
+        fs::create_dir_all(dir.join("vendor")).unwrap();
+        for f in [
+            "node_modules/Some Package.js",
+            "vendor/Some Lib.js",
+            "Debug Output.log",
+            "My App.js",
+        ] {
+            File::create(dir.join(f)).unwrap();
+        }
+
+        let engine = mk_engine(DefaultArgs {
+            files: vec![dir.clone()],
+            recursive: true,
+            list_excluded: true,
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        engine.data.exclude_patterns = vec![
+            ExcludePattern::new("^node_modules$").unwrap(),
+            ExcludePattern::new(r".*\.log").unwrap(),
+            ExcludePattern::new("parent:^vendor$").unwrap(),
+        ];
+        engine.run().expect("The run should have succeeded.");
+
+        let mut excluded: Vec<(String, String)> = String::from_utf8(engine.out)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let record: serde_json::Value = serde_json::from_str(line).unwrap();
+                (
+                    record["path"].as_str().unwrap().to_string(),
+                    record["pattern"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect();
+        excluded.sort();
+        let path = |f: &str| dir.join(f).to_string_lossy().into_owned();
+        assert_eq!(
+            excluded,
+            [
+                (path("Debug Output.log"), String::from(r".*\.log")),
+                (path("node_modules"), String::from("^node_modules$")),
+                (path("vendor/Some Lib.js"), String::from("parent:^vendor$")),
+            ]
+        );
+        // Nothing is renamed.
+        assert!(dir.join("My App.js").exists());
+    }
+
+    #[cfg(unix)]
+    #[serial]
+    #[test]
+    fn only_the_renamings_left_in_the_edited_plan_are_done() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = mk_tmp_dir("edit_plan");
+        for f in ["A File.txt", "B File.txt", "C File.txt"] {
+            File::create(dir.join(f)).unwrap();
+        }
+        // An "editor" deleting the line of B File.txt.
+        let editor = mk_tmp_dir("edit_plan_editor").join("editor.sh");
+        fs::write(&editor, "#!/bin/sh\nsed -i '/B File/d' \"$1\"\n").unwrap();
+        fs::set_permissions(&editor, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: vec![dir.clone()],
+            recursive: true,
+            edit_plan: true,
+            ..Default::default()
+        });
+        engine.data.editor = editor.to_string_lossy().into_owned();
+        engine.run().expect("The run should have succeeded.");
+
+        let mut filenames: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        filenames.sort();
+        assert_eq!(filenames, ["B File.txt", "a_file.txt", "c_file.txt"]);
+        assert_eq!(engine.summary.renamed, 2);
+    }
+
+    #[serial]
+    #[test]
+    fn read_only_filesystems_stop_the_run_unless_ignored() {
+        // Stands for a filesystem refusing the renaming.
+        let read_only =
+            || ChangeStemResult::ReadOnly(io::Error::from(io::ErrorKind::ReadOnlyFilesystem));
+        let f = PathBuf::from("/mnt/cdrom/Some File.txt");
+
+        let mut engine = mk_engine(DefaultArgs::default());
+        let mut history: Vec<u8> = vec![];
+        let err = engine
+            .process_result(f.clone(), read_only(), &mut history)
+            .expect_err("The run should have stopped.");
+        assert!(err.to_string().contains("read-only filesystem"));
+        assert!(engine.summary.errors.is_empty());
+
+        let mut engine = mk_engine(DefaultArgs {
+            ignore_readonly: true,
+            ..Default::default()
+        });
+        let mut history: Vec<u8> = vec![];
+        engine
+            .process_result(f.clone(), read_only(), &mut history)
+            .expect("The file should have been skipped.");
+        assert_eq!(engine.summary.read_only, 1);
+        assert_eq!(
+            String::from_utf8(history).unwrap(),
+            "(r) /mnt/cdrom/Some File.txt: Skipped, the file is on a read-only filesystem.\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[serial]
+    #[test]
+    fn scripts_are_emitted_instead_of_renaming() {
+        let dir = mk_tmp_dir("emit_script");
+        fs::create_dir_all(dir.join("My Dir")).unwrap();
+        File::create(dir.join("My Dir").join("It's Here.txt")).unwrap();
+
+        let engine = mk_engine(DefaultArgs {
+            files: vec![dir.join("My Dir")],
+            recursive: true,
+            emit_script: Some(ScriptShell::Sh),
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        engine.run().expect("The run should have succeeded.");
+
+        let dir_str = dir.to_string_lossy();
+        assert_eq!(
+            String::from_utf8(engine.out).unwrap(),
+            format!(
+                "#!/bin/sh\nset -e\n\
+                 mv -- '{0}/My Dir/It'\\''s Here.txt' '{0}/My Dir/its_here.txt'\n\
+                 mv -- '{0}/My Dir' '{0}/my_dir'\n",
+                dir_str
+            )
+        );
+        assert!(dir.join("My Dir").join("It's Here.txt").exists());
+    }
+
+    #[serial]
+    #[test]
+    fn runs_fail_while_another_one_holds_the_lock() {
+        let dir = mk_tmp_dir("locked_run");
+        let file = dir.join("Some File.txt");
+        File::create(&file).unwrap();
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: vec![file.clone()],
+            ..Default::default()
+        });
+        // As if held by a run in progress, this one.
+        let _lock = RunLock::acquire(&engine.data.lock_file_path, &mut io::sink()).unwrap();
+        let err = engine.run().expect_err("The run should have failed.");
+        assert!(err.to_string().contains("in progress"));
+        assert!(file.exists());
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: vec![file.clone()],
+            no_lock: true,
+            ..Default::default()
+        });
+        engine.run().expect("The run should have succeeded.");
+        assert!(dir.join("some_file.txt").exists());
+    }
+
+    #[serial]
+    #[test]
+    fn roots_keep_their_name_unless_renaming_them() {
+        let dir = mk_tmp_dir("no_rename_roots");
+        let root = dir.join("My Project");
+        fs::create_dir_all(root.join("Sub Dir")).unwrap();
+        File::create(root.join("Sub Dir").join("Some File.txt")).unwrap();
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: vec![root.clone()],
+            recursive: true,
+            no_rename_roots: true,
+            ..Default::default()
+        });
+        engine.run().expect("The run should have succeeded.");
+
+        assert!(root.join("sub_dir").join("some_file.txt").exists());
+        assert!(!dir.join("my_project").exists());
+    }
+
+    #[cfg(unix)]
+    #[serial]
+    #[test]
+    fn dangling_symlinks_are_renamed() {
+        let dir = mk_tmp_dir("dangling_symlink");
+        let target = dir.join("Missing Target.txt");
+        let link = dir.join("My Link");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: vec![link.clone()],
+            ..Default::default()
+        });
+        engine.run().expect("The run should have succeeded.");
+
+        let new_link = dir.join("my_link");
+        assert!(new_link.is_symlink());
+        assert_eq!(fs::read_link(&new_link).unwrap(), target);
+        assert!(!link.is_symlink());
+    }
+
+    #[serial]
+    #[test]
+    fn echoed_config_reflects_the_cli_overrides() {
+        let dir = mk_tmp_dir("echo_config");
+        let engine = mk_engine(DefaultArgs {
+            files: vec![dir.clone()],
+            recursive: true,
+            convention: ConventionArgs {
+                naming_convention: Some(NamingConvention::KebabCase),
+                keep_dots: true,
+                ..Default::default()
+            },
+            echo_config: true,
+            dry_run: true,
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        engine.run().expect("The run should have succeeded.");
+
+        assert_eq!(
+            String::from_utf8(engine.err).unwrap(),
+            "Options: naming_convention=kebab-case keep_dots=true keep_special_chars=false \
+             keep_unicode=false recursive=true exclude_file=none\n"
+        );
+    }
+
+    #[serial]
+    #[test]
+    fn runs_abort_cleanly_past_the_maximum_number_of_conflicts() {
+        let dir = mk_tmp_dir("max_conflicts");
+        for f in [
+            "A File.txt",
+            "a_file.txt",
+            "B File.txt",
+            "b_file.txt",
+            "C File.txt",
+            "c_file.txt",
+        ] {
+            File::create(dir.join(f)).unwrap();
+        }
+
+        let engine = mk_engine(DefaultArgs {
+            files: vec![
+                dir.join("A File.txt"),
+                dir.join("B File.txt"),
+                dir.join("C File.txt"),
+            ],
+            max_conflicts: Some(1),
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        engine.action = Some(Action::Skip);
+        let err = engine.run().expect_err("The run should have been aborted.");
+
+        assert!(err
+            .to_string()
+            .starts_with("Aborted after more than 1 conflicts"));
+        assert_eq!(engine.summary.conflicts, 2);
+        assert_eq!(engine.summary.skipped, 1);
+        // The skipped conflict is in the history, the unresolved one and the
+        // file not processed yet are in the resume file.
+        let history_file = fs::read_dir(&engine.data.history_dir_path)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        assert_eq!(fs::read_to_string(history_file).unwrap().lines().count(), 1);
+        let resume_file = fs::read_dir(&engine.data.resume_dir_path)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        assert_eq!(fs::read_to_string(resume_file).unwrap().lines().count(), 2);
+    }
+
+    #[serial]
+    #[test]
+    fn the_diff_has_a_rename_from_and_to_pair_per_renaming() {
+        let dir = mk_tmp_dir("emit_diff");
+        fs::create_dir(dir.join("My Dir")).unwrap();
+        for f in ["My Dir/Some File.txt", "Other File.txt", "done.txt"] {
+            File::create(dir.join(f)).unwrap();
+        }
+
+        let engine = mk_engine(DefaultArgs {
+            files: vec![dir.clone()],
+            recursive: true,
+            base: Some(dir.clone()),
+            emit_diff: true,
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        engine.run().expect("The run should have succeeded.");
+
+        let out = String::from_utf8(engine.out).unwrap();
+        let path = |p: &str| p.replace('/', std::path::MAIN_SEPARATOR_STR);
+        let pairs: Vec<(&str, &str)> = out
+            .lines()
+            .filter_map(|line| line.strip_prefix("rename from "))
+            .zip(
+                out.lines()
+                    .filter_map(|line| line.strip_prefix("rename to ")),
+            )
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                ("My Dir", "my_dir"),
+                (
+                    path("My Dir/Some File.txt").as_str(),
+                    path("my_dir/some_file.txt").as_str()
+                ),
+                ("Other File.txt", "other_file.txt"),
+            ]
+        );
+        assert!(out.starts_with("diff --git a/My Dir b/my_dir\nsimilarity index 100%\n"));
+        // Nothing is renamed.
+        assert!(dir.join("My Dir").join("Some File.txt").exists());
+    }
+
+    #[serial]
+    #[test]
+    fn errors_are_counted_per_category() {
+        let dir = mk_tmp_dir("error_categories");
+        for f in ["c.txt", "D.txt", "Some File.txt"] {
+            File::create(dir.join(f)).unwrap();
+        }
+
+        let engine = mk_engine(DefaultArgs {
+            files: vec![
+                dir.join("c.txt"),
+                dir.join("D.txt"),
+                dir.join("Some File.txt"),
+                dir.join("Missing File.txt"),
+            ],
+            min_length: Some(2),
+            yes_to_errors: true,
+            summary_json: true,
+            ..Default::default()
+        });
+        let mut engine = mk_captured_engine(engine.data);
+        engine.run().expect("The run should have succeeded.");
+
+        let summary: serde_json::Value =
+            serde_json::from_slice(&engine.out).expect("The summary should be JSON.");
+        assert_eq!(
+            summary["error_counts"],
+            serde_json::json!({"missing_file": 1, "too_short": 2})
+        );
+        let mut categories: Vec<_> = summary["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|error| error["category"].as_str().unwrap())
+            .collect();
+        categories.sort();
+        assert_eq!(categories, ["missing_file", "too_short", "too_short"]);
+        assert_eq!(summary["renamed"], 1);
+    }
+
+    #[serial]
+    #[test]
+    fn capture_groups_are_reordered_by_the_rename_template() {
+        let dir = mk_tmp_dir("rename_pattern");
+        let files = ["01 - Intro.mp3", "02 - Main Theme.mp3", "Cover.jpg"].map(|f| dir.join(f));
+        for f in &files {
+            File::create(f).unwrap();
+        }
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: files[..2].to_vec(),
+            ..Default::default()
+        })
+        .with_rename_pattern(RenamePattern {
+            regex: Regex::new(r"^(?<track>\d+) - (.+)\.mp3$").unwrap(),
+            template: String::from("$2 (${track}).mp3"),
+            apply_convention: false,
+        });
+        engine.run().expect("The run should have succeeded.");
+
+        assert!(dir.join("Intro (01).mp3").exists());
+        assert!(dir.join("Main Theme (02).mp3").exists());
+        assert!(!files[0].exists());
+        assert!(!files[1].exists());
+
+        // Names not matching are left alone, even if not formatted.
+        let mut engine = mk_engine(DefaultArgs {
+            files: vec![dir.join("Main Theme (02).mp3"), files[2].clone()],
+            ..Default::default()
+        })
+        .with_rename_pattern(RenamePattern {
+            regex: Regex::new(r"^(.+) \((\d+)\)\.mp3$").unwrap(),
+            template: String::from("$2 - $1.mp3"),
+            apply_convention: true,
+        });
+        engine.run().expect("The run should have succeeded.");
+
+        assert!(dir.join("02_main_theme.mp3").exists());
+        assert!(files[2].exists());
+    }
+
+    #[serial]
+    #[test]
+    fn plans_written_out_can_be_applied_later() {
+        let dir = mk_tmp_dir("plan_out");
+        let sub_dir = dir.join("Sub Dir");
+        fs::create_dir_all(&sub_dir).unwrap();
+        File::create(sub_dir.join("Some File.txt")).unwrap();
+        let plan_path = mk_tmp_dir("plan_out_plans").join("plan.txt");
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: vec![sub_dir.clone()],
+            recursive: true,
+            plan_out: Some(plan_path.clone()),
+            ..Default::default()
+        });
+        engine.run().expect("The run should have succeeded.");
+        // Nothing is renamed until the plan is applied.
+        assert!(sub_dir.join("Some File.txt").exists());
+        assert_eq!(
+            fs::read_dir(&engine.data.history_dir_path).unwrap().count(),
+            0
+        );
+
+        let mut engine = mk_engine(DefaultArgs::default()).with_plan_to_apply(plan_path);
+        engine.run().expect("The run should have succeeded.");
+
+        assert!(dir.join("sub_dir").join("some_file.txt").exists());
+        assert!(!sub_dir.exists());
+        let history_files: Vec<_> = fs::read_dir(&engine.data.history_dir_path)
+            .unwrap()
+            .collect();
+        assert_eq!(history_files.len(), 1);
+    }
+
+    #[serial]
+    #[test]
+    fn local_history_is_written_in_the_current_directory() {
+        let dir = mk_tmp_dir("local_history");
+        let file = dir.join("Some File.txt");
+        File::create(&file).unwrap();
+
+        let cwd = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&dir).unwrap();
+        let res = DefaultEngine::new(
+            DefaultArgs {
+                files: vec![file],
+                local_history: true,
+                ..Default::default()
+            },
+            Cfg::default(),
+        )
+        .and_then(|mut engine| {
+            engine.data.exclude_patterns = vec![];
+            engine.run()
+        });
+        std::env::set_current_dir(cwd).unwrap();
+        res.expect("The run should have succeeded.");
+
+        let history_files = fs::read_dir(dir.join(".fmtna-history"))
+            .expect("The local history directory should have been created.")
+            .collect::<Vec<_>>();
+        assert_eq!(history_files.len(), 1);
+    }
+
+    #[serial]
+    #[test]
+    fn dotfiles_keep_their_leading_dot() {
+        let dir = mk_tmp_dir("dotfiles");
+        let filenames = [".bashrc", ".config.yaml", ".My Config.yaml"];
+        for filename in filenames {
+            File::create(dir.join(filename)).unwrap();
+        }
+
+        let test_cases = [
+            (
+                NamingConvention::SnakeCase,
+                [None, None, Some(".my_config.yaml")],
+            ),
+            (
+                NamingConvention::KebabCase,
+                [None, None, Some(".my-config.yaml")],
+            ),
+            (
+                NamingConvention::CamelCase,
+                [None, None, Some(".myConfig.yaml")],
+            ),
+            // The first letter of a dotfile is never capitalized.
+            (
+                NamingConvention::PascalCase,
+                [None, None, Some(".myConfig.yaml")],
+            ),
+            (
+                NamingConvention::Lower,
+                [None, None, Some(".my config.yaml")],
+            ),
+            (
+                NamingConvention::Upper,
+                [
+                    Some(".BASHRC"),
+                    Some(".CONFIG.yaml"),
+                    Some(".MY CONFIG.yaml"),
+                ],
+            ),
+        ];
+        for (nc, expected_filenames) in test_cases {
+            let engine = mk_engine(DefaultArgs {
+                convention: ConventionArgs {
+                    naming_convention: Some(nc.clone()),
+                    ..Default::default()
+                },
+                dry_run: true,
+                ..Default::default()
+            });
+            for (filename, expected_filename) in filenames.iter().zip(expected_filenames) {
+                let res = engine.change_stem_of_file(&dir.join(filename));
+                match expected_filename {
+                    None => assert!(
+                        matches!(res, ChangeStemResult::NoNeedToRename),
+                        "Expected {} to be left as is in {:?}, but got {:?}.",
+                        filename,
+                        nc,
+                        res
+                    ),
+                    Some(expected_filename) => assert!(
+                        matches!(res, ChangeStemResult::Ok(ref new_file) if *new_file == dir.join(expected_filename)),
+                        "Expected {} to be renamed into {} in {:?}, but got {:?}.",
+                        filename,
+                        expected_filename,
+                        nc,
+                        res
+                    ),
+                }
+            }
+        }
+    }
+
+    #[serial]
+    #[test]
+    fn report_contains_the_counts_of_the_run() {
+        let dir = mk_tmp_dir("report_contains_the_counts");
+        let files = ["A File.txt", "already_formatted.txt", "Another File.txt"]
+            .iter()
+            .map(|name| dir.join(name))
+            .collect::<Vec<_>>();
+        for f in &files {
+            File::create(f).unwrap();
+        }
+        let report_path = dir.join("report.json");
+
+        let mut engine = mk_engine(DefaultArgs {
+            files: files.clone(),
+            report: Some(report_path.clone()),
+            ..Default::default()
+        });
+        engine.run().expect("The run should have succeeded.");
+
+        let report: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&report_path).unwrap()).unwrap();
+        assert_eq!(report["renamed"], 2);
+        assert_eq!(report["skipped"], 0);
+        assert_eq!(report["backed_up"], 0);
+        assert_eq!(report["overwritten"], 0);
+        assert_eq!(report["errors"], serde_json::json!([]));
+        assert_eq!(report["options"]["naming_convention"], "snake_case");
+        let history_file = report["history_file"].as_str().unwrap();
+        assert!(Path::new(history_file).exists());
+    }
 }
@@ -0,0 +1,41 @@
+use std::path::{Path, PathBuf};
+
+/// What happened to a file during a run, as passed to the observer given to
+/// [get_engine_with_observer](crate::default::get_engine_with_observer).
+///
+/// There is one event per line of the history file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenameEvent {
+    /// The code of the operation, as in the history file, e.g. `d` for a
+    /// renaming, `b` for a backup or `e` for an error.
+    pub op: char,
+
+    /// The path of the file, before the operation.
+    pub path: PathBuf,
+
+    /// The path of the file after the operation, for changes (e.g. renamings).
+    pub new_path: Option<PathBuf>,
+
+    /// What happened, for anything other than a change (e.g. errors).
+    pub message: Option<String>,
+}
+
+impl RenameEvent {
+    pub(super) fn change(op: char, path: &Path, new_path: &Path) -> Self {
+        Self {
+            op,
+            path: path.to_path_buf(),
+            new_path: Some(new_path.to_path_buf()),
+            message: None,
+        }
+    }
+
+    pub(super) fn note(op: char, path: &Path, message: &str) -> Self {
+        Self {
+            op,
+            path: path.to_path_buf(),
+            new_path: None,
+            message: Some(message.to_string()),
+        }
+    }
+}
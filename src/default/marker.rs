@@ -0,0 +1,53 @@
+use crate::naming_conventions::{FormatOptions, NamingConvention};
+use anyhow::Context;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// The name of the marker files setting formatting options for the files
+/// below them, see [DefaultArgs::no_markers](crate::default::DefaultArgs::no_markers).
+pub const MARKER_FILE_NAME: &str = ".fmtna.toml";
+
+/// The formatting options of a marker file. Options left out are taken from
+/// the CLI arguments or the configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Marker {
+    naming_convention: Option<NamingConvention>,
+    keep_dots: Option<bool>,
+    keep_special_chars: Option<bool>,
+    keep_unicode: Option<bool>,
+}
+
+impl Marker {
+    /// Reads the marker file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file can't be read, or is not valid TOML with only the
+    /// supported options.
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}.", path.to_string_lossy()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Invalid marker file {}.", path.to_string_lossy()))
+    }
+
+    /// Returns `format_options` with the options of the marker applied.
+    pub fn apply(&self, format_options: &FormatOptions) -> FormatOptions {
+        let mut format_options = format_options.clone();
+        if let Some(naming_convention) = &self.naming_convention {
+            format_options.naming_convention = naming_convention.clone();
+        }
+        if let Some(keep_dots) = self.keep_dots {
+            format_options.keep_dots = keep_dots;
+        }
+        if let Some(keep_special_chars) = self.keep_special_chars {
+            format_options.keep_special_chars = keep_special_chars;
+        }
+        if let Some(keep_unicode) = self.keep_unicode {
+            format_options.keep_unicode = keep_unicode;
+        }
+        format_options
+    }
+}
@@ -0,0 +1,241 @@
+use crate::utils::is_same_file;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// What a run would do with a file, see [DefaultArgs::dry_run](crate::default::DefaultArgs::dry_run).
+#[derive(Debug, Serialize)]
+pub struct PlanRecord {
+    /// The current path of the file.
+    pub from: PathBuf,
+
+    /// The path the file would be renamed into, if it has one.
+    pub to: Option<PathBuf>,
+
+    pub decision: Decision,
+
+    /// Why the decision was taken, for anything other than a plain rename.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// The decision taken for a file.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    /// The file would be renamed.
+    Rename,
+    /// The file would be left as is, as it needs no renaming.
+    Keep,
+    /// The file would be left as is, as it is excluded.
+    Exclude,
+    /// The file would be renamed into an already existing file,
+    /// which would require the user to choose what to do.
+    Conflict,
+    /// The file would be left as is because of an error.
+    Error,
+}
+
+/// A file excluded by a pattern, see
+/// [DefaultArgs::list_excluded](crate::default::DefaultArgs::list_excluded).
+#[derive(Debug, Serialize)]
+pub struct ExclusionRecord {
+    /// The path of the file.
+    pub path: PathBuf,
+
+    /// The exclude pattern matching the file.
+    pub pattern: String,
+}
+
+/// A renaming of the plan of [DefaultArgs::edit_plan](crate::default::DefaultArgs::edit_plan),
+/// as `(path, new path)`.
+pub type Renaming = (PathBuf, PathBuf);
+
+/// A problem of an edited plan, as `(line_no, problem)` (line numbers
+/// starting at 1).
+pub type PlanProblem = (usize, String);
+
+/// Returns the content of the plan file listing `renamings`, for the user
+/// to edit.
+pub fn plan_file_content(renamings: &[Renaming]) -> String {
+    let mut content = String::from(
+        "# Delete a line to skip its renaming, or change the path after \" -> \".\n\
+         # Lines starting with # are ignored.\n",
+    );
+    for (path, new_path) in renamings {
+        content.push_str(&format!(
+            "{} -> {}\n",
+            path.to_string_lossy(),
+            new_path.to_string_lossy()
+        ));
+    }
+
+    content
+}
+
+/// Parses the plan file `content`, as edited by the user, checking it
+/// against the `planned` renamings.
+///
+/// Blank lines and lines starting with `#` are ignored, as well as lines
+/// whose new path is the path itself.
+///
+/// # Errors
+///
+/// Returns all the problems found, e.g. paths that were not planned to be
+/// renamed or new paths that already exist.
+pub fn parse_edited_plan(
+    content: &str,
+    planned: &[Renaming],
+) -> Result<Vec<Renaming>, Vec<PlanProblem>> {
+    let planned: HashSet<&Path> = planned.iter().map(|(path, _)| path.as_path()).collect();
+    parse_plan(content, |path| {
+        (!planned.contains(path))
+            .then(|| format!("{} was not planned to be renamed.", path.to_string_lossy()))
+    })
+}
+
+/// Parses the plan file `content` written by
+/// [DefaultArgs::plan_out](crate::default::DefaultArgs::plan_out), to apply
+/// it.
+///
+/// Like [parse_edited_plan], except that any existing path can be renamed.
+///
+/// # Errors
+///
+/// Returns all the problems found, e.g. paths that don't exist (anymore) or
+/// new paths that already exist.
+pub fn parse_saved_plan(content: &str) -> Result<Vec<Renaming>, Vec<PlanProblem>> {
+    parse_plan(content, |path| {
+        (!path.exists() && !path.is_symlink())
+            .then(|| format!("{} doesn't exist.", path.to_string_lossy()))
+    })
+}
+
+/// Parses the plan file `content`, `unrenamable` returning why a path can't
+/// be renamed, if it can't.
+fn parse_plan(
+    content: &str,
+    unrenamable: impl Fn(&Path) -> Option<String>,
+) -> Result<Vec<Renaming>, Vec<PlanProblem>> {
+    let mut renamings: Vec<Renaming> = vec![];
+    let mut problems = vec![];
+    let mut path_lines = HashMap::new();
+    let mut new_path_lines = HashMap::new();
+    for (i, line) in content.lines().enumerate() {
+        let line_no = i + 1;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((path, new_path)) = line.split_once(" -> ") else {
+            problems.push((line_no, String::from("Expected `<path> -> <new path>`.")));
+            continue;
+        };
+        let (path, new_path) = (PathBuf::from(path), PathBuf::from(new_path.trim_end()));
+        if path == new_path {
+            continue;
+        }
+
+        let problem = if let Some(problem) = unrenamable(&path) {
+            Some(problem)
+        } else if let Some(other) = path_lines.insert(path.clone(), line_no) {
+            Some(format!(
+                "{} is already renamed on line {}.",
+                path.to_string_lossy(),
+                other
+            ))
+        } else if let Some(other) = new_path_lines.insert(new_path.clone(), line_no) {
+            Some(format!(
+                "{} is already the new path on line {}.",
+                new_path.to_string_lossy(),
+                other
+            ))
+        } else if new_path.parent() != path.parent() || new_path.file_name().is_none() {
+            Some(format!(
+                "{} is not in the same directory as {}.",
+                new_path.to_string_lossy(),
+                path.to_string_lossy()
+            ))
+        } else if (new_path.exists() || new_path.is_symlink()) && !is_same_file(&path, &new_path) {
+            Some(format!("{} already exists.", new_path.to_string_lossy()))
+        } else {
+            None
+        };
+        match problem {
+            Some(problem) => problems.push((line_no, problem)),
+            None => renamings.push((path, new_path)),
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err(problems);
+    }
+
+    Ok(renamings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paths::tests::mk_tmp_dir;
+    use crate::utils::symlink;
+
+    #[test]
+    fn edited_plans_are_checked() {
+        let planned = vec![
+            (PathBuf::from("/dir/A File"), PathBuf::from("/dir/a_file")),
+            (PathBuf::from("/dir/B File"), PathBuf::from("/dir/b_file")),
+        ];
+        let content = plan_file_content(&planned);
+        assert_eq!(parse_edited_plan(&content, &planned), Ok(planned.clone()));
+
+        let content = "# comment\n\n/dir/A File -> /dir/a-file\n/dir/A File -> /dir/a_file\n\
+                       /dir/B File -> /other/b_file\n/dir/C File -> /dir/c_file\nnonsense\n";
+        assert_eq!(
+            parse_edited_plan(content, &planned),
+            Err(vec![
+                (4, String::from("/dir/A File is already renamed on line 3.")),
+                (
+                    5,
+                    String::from("/other/b_file is not in the same directory as /dir/B File.")
+                ),
+                (
+                    6,
+                    String::from("/dir/C File was not planned to be renamed.")
+                ),
+                (7, String::from("Expected `<path> -> <new path>`.")),
+            ])
+        );
+    }
+
+    #[test]
+    fn dangling_symlinks_are_taken_new_paths() {
+        let dir = mk_tmp_dir("plan_dangling_symlink");
+        let path = dir.join("A File");
+        let new_path = dir.join("a_file");
+        std::fs::write(&path, "").unwrap();
+        symlink(&dir.join("missing"), &new_path).unwrap();
+
+        let planned = vec![(path, new_path.clone())];
+        let content = plan_file_content(&planned);
+        assert_eq!(
+            parse_edited_plan(&content, &planned),
+            Err(vec![(
+                3,
+                format!("{} already exists.", new_path.to_string_lossy())
+            )])
+        );
+    }
+
+    #[test]
+    fn saved_plans_only_rename_existing_paths() {
+        let content = "/fmtna/Missing File -> /fmtna/missing_file\n";
+        assert_eq!(
+            parse_saved_plan(content),
+            Err(vec![(
+                1,
+                String::from("/fmtna/Missing File doesn't exist.")
+            )])
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use super::cli::ScriptShell;
+use super::plan::Renaming;
+
+/// Quotes `s` as a single word for `shell`, so that it is taken literally.
+///
+/// # Parameters
+///
+/// - `shell`
+/// - `s`
+pub fn quote(shell: ScriptShell, s: &str) -> String {
+    match shell {
+        // Nothing is special within single quotes, except single quotes
+        // themselves, which are closed, escaped and reopened.
+        ScriptShell::Sh => format!("'{}'", s.replace('\'', r"'\''")),
+        // Single quotes (typographic ones included) are escaped by doubling
+        // them.
+        ScriptShell::Ps1 => {
+            let mut quoted = String::from("'");
+            for c in s.chars() {
+                if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}') {
+                    quoted.push(c);
+                }
+                quoted.push(c);
+            }
+            quoted.push('\'');
+            quoted
+        }
+    }
+}
+
+/// Returns the script doing `renamings` in order, for `shell`.
+///
+/// The script stops at the first renaming that fails.
+///
+/// # Parameters
+///
+/// - `shell`
+/// - `renamings`
+pub fn rename_script(shell: ScriptShell, renamings: &[Renaming]) -> String {
+    let mut script = String::from(match shell {
+        ScriptShell::Sh => "#!/bin/sh\nset -e\n",
+        ScriptShell::Ps1 => "$ErrorActionPreference = 'Stop'\n",
+    });
+    for (path, new_path) in renamings {
+        let (path, new_path) = (
+            quote(shell, &path.to_string_lossy()),
+            quote(shell, &new_path.to_string_lossy()),
+        );
+        script.push_str(&match shell {
+            ScriptShell::Sh => format!("mv -- {} {}\n", path, new_path),
+            ScriptShell::Ps1 => {
+                format!(
+                    "Move-Item -LiteralPath {} -Destination {}\n",
+                    path, new_path
+                )
+            }
+        });
+    }
+
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn paths_with_spaces_and_quotes_are_escaped() {
+        let renamings = vec![(
+            PathBuf::from("/dir/Bob's \"Best\" $File.txt"),
+            PathBuf::from("/dir/bobs_best_file.txt"),
+        )];
+
+        assert_eq!(
+            rename_script(ScriptShell::Sh, &renamings),
+            "#!/bin/sh\nset -e\n\
+             mv -- '/dir/Bob'\\''s \"Best\" $File.txt' '/dir/bobs_best_file.txt'\n"
+        );
+        assert_eq!(
+            rename_script(ScriptShell::Ps1, &renamings),
+            "$ErrorActionPreference = 'Stop'\n\
+             Move-Item -LiteralPath '/dir/Bob''s \"Best\" $File.txt' -Destination '/dir/bobs_best_file.txt'\n"
+        );
+        assert_eq!(
+            quote(ScriptShell::Ps1, "it\u{2019}s"),
+            "'it\u{2019}\u{2019}s'"
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// What happened during a run, file by file, aggregated.
+#[derive(Debug, Default, Serialize)]
+pub struct Summary {
+    /// Number of files visited, excluded ones included.
+    pub visited: usize,
+
+    /// Number of files renamed without conflict, i.e. `(d)` recap lines.
+    pub renamed: usize,
+
+    /// Number of files copied into the output directory, conflicts included,
+    /// i.e. `(c)` recap lines.
+    pub copied: usize,
+
+    /// Number of conflicts skipped, i.e. `(s)` recap lines.
+    pub skipped: usize,
+
+    /// Number of conflicts resolved by backing up the existing file,
+    /// i.e. `(b)` recap lines.
+    pub backed_up: usize,
+
+    /// Number of conflicts resolved by overwriting the existing file,
+    /// i.e. `(o)` recap lines.
+    pub overwritten: usize,
+
+    /// Number of files skipped because locked by another process,
+    /// i.e. `(k)` recap lines.
+    pub locked: usize,
+
+    /// Number of files skipped because on a read-only filesystem,
+    /// i.e. `(r)` recap lines.
+    pub read_only: usize,
+
+    /// Number of conflicts, however resolved (or not, see
+    /// [DefaultArgs::max_conflicts](crate::default::DefaultArgs::max_conflicts)).
+    pub conflicts: usize,
+
+    /// The errors, i.e. `(e)` recap lines.
+    pub errors: Vec<SummaryError>,
+
+    /// Number of errors per category.
+    pub error_counts: BTreeMap<ErrorCategory, usize>,
+}
+
+/// An error that happened for a specific file.
+#[derive(Debug, Serialize)]
+pub struct SummaryError {
+    pub path: String,
+    pub category: ErrorCategory,
+    pub message: String,
+}
+
+/// What kind of error happened for a file, to tell systemic issues apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// The file doesn't exist (anymore).
+    MissingFile,
+    /// The stem or the parent directory of the file couldn't be found.
+    InvalidPath,
+    /// The filename contains invalid Unicode.
+    InvalidUnicode,
+    /// The formatted stem is shorter than the minimum length.
+    TooShort,
+    /// The filename contains characters that can't be transliterated.
+    Untransliterable,
+    /// A marker file applying to the file is invalid.
+    InvalidMarker,
+    /// The file is locked by another process.
+    Locked,
+    /// Renaming (or backing up) the file failed.
+    RenameFailed,
+    /// Copying the file into the output directory failed.
+    CopyFailed,
+    /// Anything else, e.g. failing to leave a symbolic link.
+    Other,
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ErrorCategory::MissingFile => "missing file",
+            ErrorCategory::InvalidPath => "invalid path",
+            ErrorCategory::InvalidUnicode => "invalid unicode",
+            ErrorCategory::TooShort => "too short",
+            ErrorCategory::Untransliterable => "untransliterable",
+            ErrorCategory::InvalidMarker => "invalid marker",
+            ErrorCategory::Locked => "locked",
+            ErrorCategory::RenameFailed => "rename failed",
+            ErrorCategory::CopyFailed => "copy failed",
+            ErrorCategory::Other => "other",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Summary {
+    /// Returns the number of files renamed (or copied), with or without conflict.
+    pub fn changed(&self) -> usize {
+        self.renamed + self.copied + self.backed_up + self.overwritten
+    }
+
+    pub fn add_error(&mut self, path: &Path, category: ErrorCategory, message: &str) {
+        self.errors.push(SummaryError {
+            path: path.to_string_lossy().into_owned(),
+            category,
+            message: message.to_string(),
+        });
+        *self.error_counts.entry(category).or_default() += 1;
+    }
+}
@@ -0,0 +1,39 @@
+use std::cell::Cell;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// The time spent in each step of a run, see
+/// [DefaultArgs::timings](crate::default::DefaultArgs::timings).
+///
+/// Durations are cells so that they can be accumulated from methods only
+/// borrowing the engine.
+#[derive(Debug, Default)]
+pub struct Timings {
+    /// Time spent listing the content of directories.
+    pub traversal: Cell<Duration>,
+
+    /// Time spent computing formatted names.
+    pub naming: Cell<Duration>,
+
+    /// Time spent renaming (or copying) files.
+    pub fs_ops: Cell<Duration>,
+}
+
+impl Timings {
+    /// Runs `f`, adding the time it took to `step`.
+    pub fn time<T>(step: &Cell<Duration>, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let res = f();
+        step.set(step.get() + start.elapsed());
+        res
+    }
+
+    /// Writes the timings into `writer`, given that the whole run took `total`.
+    pub fn write<W: Write>(&self, total: Duration, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "Timings:")?;
+        writeln!(writer, "  traversal: {:?}", self.traversal.get())?;
+        writeln!(writer, "  name computation: {:?}", self.naming.get())?;
+        writeln!(writer, "  filesystem operations: {:?}", self.fs_ops.get())?;
+        writeln!(writer, "  total: {:?}", total)
+    }
+}
@@ -0,0 +1,7 @@
+//! Module for the detect subcommand.
+
+mod cli;
+mod data;
+mod engine;
+pub use cli::DetectCli;
+pub use engine::get_engine;
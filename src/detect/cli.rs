@@ -0,0 +1,31 @@
+use clap::Args;
+
+#[derive(Args, Clone, Debug, PartialEq, Eq)]
+#[clap(verbatim_doc_comment)]
+/// Print the naming conventions names already follow.
+///
+/// A name follows a naming convention when formatting it according to that
+/// convention leaves it unchanged. It may follow several (e.g. "abc" follows
+/// both lower and kebab-case), or none.
+/// This is useful to understand why a file is considered already formatted.
+pub struct DetectCli {
+    #[clap(verbatim_doc_comment)]
+    /// The names to check.
+    #[arg(required = true)]
+    pub names: Vec<String>,
+
+    #[clap(verbatim_doc_comment)]
+    /// Check as if formatting with --keep-dots.
+    #[arg(long)]
+    pub keep_dots: bool,
+
+    #[clap(verbatim_doc_comment)]
+    /// Check as if formatting with --keep-special-chars.
+    #[arg(long)]
+    pub keep_special_chars: bool,
+
+    #[clap(verbatim_doc_comment)]
+    /// Check as if formatting with --keep-unicode.
+    #[arg(long)]
+    pub keep_unicode: bool,
+}
@@ -0,0 +1,28 @@
+use super::cli::DetectCli;
+use crate::cfg::Cfg;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Data {
+    /// Same as [DetectCli::names](crate::detect::DetectCli::names)
+    pub names: Vec<String>,
+
+    /// Same as [DetectCli::keep_dots](crate::detect::DetectCli::keep_dots)
+    pub keep_dots: bool,
+
+    /// Same as [DetectCli::keep_special_chars](crate::detect::DetectCli::keep_special_chars)
+    pub keep_special_chars: bool,
+
+    /// Same as [DetectCli::keep_unicode](crate::detect::DetectCli::keep_unicode)
+    pub keep_unicode: bool,
+}
+
+impl Data {
+    pub fn new(cli: DetectCli, cfg: Cfg) -> anyhow::Result<Self> {
+        Ok(Data {
+            names: cli.names,
+            keep_dots: cfg.resolve_keep_dots(cli.keep_dots),
+            keep_special_chars: cfg.resolve_keep_special_chars(cli.keep_special_chars),
+            keep_unicode: cfg.resolve_keep_unicode(cli.keep_unicode),
+        })
+    }
+}
@@ -0,0 +1,98 @@
+use super::cli::DetectCli;
+use super::data::Data;
+use crate::cfg::Cfg;
+use crate::engine::Engine;
+use crate::naming_conventions::detect_ncs;
+use anyhow::Context;
+use clap::ValueEnum;
+use std::io;
+use std::io::Write;
+
+/// Returns the engine for the detect subcommand, parameterized by `cli` and `cfg`.
+///
+/// # Parameters
+///
+/// - `cli`: The CLI arguments.
+/// - `cfg`: The configuration values.
+///
+/// # Returns
+///
+/// The parametrized engine for running the detect subcommand's logic, or an
+/// error if engine creation failed.
+pub fn get_engine(cli: DetectCli, cfg: Cfg) -> anyhow::Result<Box<dyn Engine>> {
+    Ok(Box::new(DetectEngine::new(cli, cfg)?))
+}
+
+struct DetectEngine {
+    data: Data,
+}
+
+impl DetectEngine {
+    pub fn new(cli: DetectCli, cfg: Cfg) -> anyhow::Result<Self> {
+        let data = Data::new(cli, cfg)?;
+        Ok(Self { data })
+    }
+
+    fn write_detected<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
+        for name in &self.data.names {
+            let ncs = detect_ncs(
+                name,
+                self.data.keep_dots,
+                self.data.keep_special_chars,
+                self.data.keep_unicode,
+            );
+            let ncs_str = if ncs.is_empty() {
+                String::from("none")
+            } else {
+                ncs.iter()
+                    .filter_map(|nc| nc.to_possible_value())
+                    .map(|value| value.get_name().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            writeln!(writer, "{}: {}", name, ncs_str)
+                .with_context(|| "Failed to write detected naming conventions.")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Engine for DetectEngine {
+    fn run(&mut self) -> anyhow::Result<()> {
+        self.write_detected(&mut io::stdout().lock())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Command};
+    use clap::Parser;
+
+    fn output_of(args: &[&str]) -> String {
+        let cli = Cli::try_parse_from(args).expect("Arguments should be valid.");
+        let engine = match cli.command {
+            Some(Command::Detect(cli)) => DetectEngine::new(cli, Cfg::default())
+                .expect("DetectEngine::new should have succeeded."),
+            _ => panic!("Expected the detect subcommand."),
+        };
+        let mut out: Vec<u8> = vec![];
+        engine
+            .write_detected(&mut out)
+            .expect("Writing to a Vec shouldn't fail.");
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn prints_the_naming_conventions_followed() {
+        assert_eq!(
+            output_of(&["fmtna", "detect", "my_file"]),
+            "my_file: snake_case, lower\n"
+        );
+        assert_eq!(
+            output_of(&["fmtna", "detect", "MyFile", "My File!"]),
+            "MyFile: PascalCase\nMy File!: none\n"
+        );
+    }
+}
@@ -0,0 +1,7 @@
+//! Module for the doctor subcommand.
+
+mod cli;
+mod data;
+mod engine;
+pub use cli::DoctorCli;
+pub use engine::get_engine;
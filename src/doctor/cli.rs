@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+use clap::Args;
+
+#[derive(Args, Clone, Debug, PartialEq, Eq)]
+#[clap(verbatim_doc_comment)]
+/// Check the history and backup directories for leftovers.
+///
+/// A run that crashed or was killed may leave an inconsistent state behind.
+/// The following anomalies are reported:
+///   - Empty history files, which can't be reverted.
+///   - Orphaned backups, i.e. backups no history file of the history
+///     directory refers to, so that reverting won't restore them.
+///
+/// The program exits with a non-zero status if there is any anomaly left.
+pub struct DoctorCli {
+    #[clap(verbatim_doc_comment)]
+    /// Remove the empty history files and the orphaned backups.
+    ///
+    /// WARNING! Orphaned backups are removed for good. Check that you don't
+    /// need them anymore first, e.g. if a history file referring to them was
+    /// moved elsewhere (like the .fmtna-history directory of a project).
+    #[arg(long)]
+    pub fix: bool,
+
+    #[clap(verbatim_doc_comment)]
+    /// The history directory to check.
+    ///
+    /// Defaults to the history directory in your config directory.
+    #[arg(long, value_name = "DIR")]
+    pub history_dir: Option<PathBuf>,
+
+    #[clap(verbatim_doc_comment)]
+    /// The backup directory to check.
+    ///
+    /// Defaults to the backup directory in your config directory.
+    #[arg(long, value_name = "DIR")]
+    pub backup_dir: Option<PathBuf>,
+}
@@ -0,0 +1,30 @@
+use super::cli::DoctorCli;
+use crate::cfg::Cfg;
+use crate::paths::{BACKUP_DIR_PATH, HISTORY_DIR_PATH};
+use std::path::PathBuf;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Data {
+    /// Same as [DoctorCli::fix](crate::doctor::DoctorCli::fix)
+    pub fix: bool,
+
+    /// Same as [DoctorCli::history_dir](crate::doctor::DoctorCli::history_dir),
+    /// with its default
+    pub history_dir_path: PathBuf,
+
+    /// Same as [DoctorCli::backup_dir](crate::doctor::DoctorCli::backup_dir),
+    /// with its default
+    pub backup_dir_path: PathBuf,
+}
+
+impl Data {
+    pub fn new(cli: DoctorCli, cfg: Cfg) -> anyhow::Result<Self> {
+        let _ = cfg;
+
+        Ok(Data {
+            fix: cli.fix,
+            history_dir_path: cli.history_dir.unwrap_or(HISTORY_DIR_PATH.clone()),
+            backup_dir_path: cli.backup_dir.unwrap_or(BACKUP_DIR_PATH.clone()),
+        })
+    }
+}
@@ -0,0 +1,253 @@
+use super::cli::DoctorCli;
+use super::data::Data;
+use crate::cfg::Cfg;
+use crate::engine::Engine;
+use crate::revert::parse_history_line;
+use crate::utils::file_is_empty;
+use anyhow::{anyhow, Context};
+use crossterm::style::Stylize;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+lazy_static! {
+    /// Matches the names of backups, as named by
+    /// [plan_backup](crate::utils::plan_backup), capturing the stem and the
+    /// extension (with its dot) of the backed up file.
+    static ref BACKUP_NAME_RE: Regex =
+        Regex::new(r"^(?<stem>.*)_backup_\d{8}_\d{6}\.\d{9}(?<ext>\..*)?$").unwrap();
+}
+
+/// Returns the engine for the doctor subcommand, parameterized by `cli` and `cfg`.
+///
+/// # Parameters
+///
+/// - `cli`: The CLI arguments.
+/// - `cfg`: The configuration values.
+///
+/// # Returns
+///
+/// The parametrized engine for running the doctor subcommand's logic, or an
+/// error if engine creation failed.
+pub fn get_engine(cli: DoctorCli, cfg: Cfg) -> anyhow::Result<Box<dyn Engine>> {
+    Ok(Box::new(DoctorEngine::new(cli, cfg)?))
+}
+
+struct DoctorEngine {
+    data: Data,
+}
+
+/// An anomaly found in the history or backup directory.
+#[derive(Debug, PartialEq, Eq)]
+enum Anomaly {
+    EmptyHistoryFile(PathBuf),
+    OrphanedBackup(PathBuf),
+}
+
+impl Anomaly {
+    fn path(&self) -> &Path {
+        match self {
+            Anomaly::EmptyHistoryFile(path) | Anomaly::OrphanedBackup(path) => path,
+        }
+    }
+
+    fn message(&self) -> &'static str {
+        match self {
+            Anomaly::EmptyHistoryFile(_) => "Empty history file.",
+            Anomaly::OrphanedBackup(_) => "Orphaned backup, no history file refers to it.",
+        }
+    }
+}
+
+impl DoctorEngine {
+    pub fn new(cli: DoctorCli, cfg: Cfg) -> anyhow::Result<Self> {
+        let data = Data::new(cli, cfg)?;
+        Ok(Self { data })
+    }
+
+    /// Returns the anomalies of the history and backup directories.
+    fn find_anomalies(&self) -> anyhow::Result<Vec<Anomaly>> {
+        let mut anomalies = vec![];
+
+        // Paths backups were moved to (e.g. with --on-conflict), and names
+        // of the files backed up before renaming into them.
+        let mut backup_paths = HashSet::new();
+        let mut backed_up_names = HashSet::new();
+        for history_file in list_dir(&self.data.history_dir_path)? {
+            if !history_file.is_file() {
+                continue;
+            }
+            if file_is_empty(&history_file)? {
+                anomalies.push(Anomaly::EmptyHistoryFile(history_file));
+                continue;
+            }
+
+            let content = fs::read_to_string(&history_file).with_context(|| {
+                format!(
+                    "Failed to read history file ({}).",
+                    history_file.to_string_lossy()
+                )
+            })?;
+            for (op, _, to) in content.lines().filter_map(parse_history_line) {
+                if op == "b" {
+                    if let Some(name) = to.file_name() {
+                        backed_up_names.insert(name.to_string_lossy().into_owned());
+                    }
+                }
+                backup_paths.insert(to);
+            }
+        }
+
+        for backup in list_dir(&self.data.backup_dir_path)? {
+            let backed_up_name = backup.file_name().and_then(|name| {
+                let caps = BACKUP_NAME_RE.captures(name.to_str()?)?;
+                let ext = caps.name("ext").map_or("", |ext| ext.as_str());
+                Some(format!("{}{}", &caps["stem"], ext))
+            });
+            let referred_to = backup_paths.contains(&backup)
+                || backed_up_name.is_some_and(|name| backed_up_names.contains(&name));
+            if !referred_to {
+                anomalies.push(Anomaly::OrphanedBackup(backup));
+            }
+        }
+
+        Ok(anomalies)
+    }
+
+    /// Reports `anomalies` into `writer`, removing them if asked to.
+    ///
+    /// Returns the number of anomalies left.
+    fn report_anomalies<W: Write>(
+        &self,
+        anomalies: &[Anomaly],
+        writer: &mut W,
+    ) -> anyhow::Result<usize> {
+        if anomalies.is_empty() {
+            writeln!(writer, "No anomaly found.")?;
+            return Ok(0);
+        }
+
+        let mut left = 0;
+        for anomaly in anomalies {
+            let path = anomaly.path();
+            writeln!(
+                writer,
+                "{}",
+                format!("(w) {}: {}", path.to_string_lossy(), anomaly.message()).dark_yellow()
+            )?;
+            if !self.data.fix {
+                left += 1;
+                continue;
+            }
+
+            let res = if path.is_dir() {
+                fs::remove_dir_all(path)
+            } else {
+                fs::remove_file(path)
+            };
+            match res {
+                Ok(()) => writeln!(
+                    writer,
+                    "{}",
+                    format!("Removed {}", path.to_string_lossy()).dark_grey()
+                )?,
+                Err(err) => {
+                    left += 1;
+                    writeln!(
+                        writer,
+                        "{}",
+                        format!("(e) {}: Failed to remove. {}", path.to_string_lossy(), err)
+                            .dark_red()
+                    )?;
+                }
+            }
+        }
+
+        Ok(left)
+    }
+}
+
+impl Engine for DoctorEngine {
+    fn run(&mut self) -> anyhow::Result<()> {
+        let anomalies = self.find_anomalies()?;
+        let left = self.report_anomalies(&anomalies, &mut io::stdout().lock())?;
+        if left > 0 {
+            return Err(anyhow!("{} anomalies found.", left));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the paths of the entries of `dir`, in filename order, or nothing
+/// if `dir` doesn't exist.
+fn list_dir(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let entries = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read directory ({}).", dir.to_string_lossy()))?;
+    let mut paths = entries
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()
+        .with_context(|| format!("Failed to read directory ({}).", dir.to_string_lossy()))?;
+    paths.sort();
+
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paths::tests::mk_tmp_dir;
+    use serial_test::serial;
+    use std::fs::File;
+
+    #[serial]
+    #[test]
+    fn empty_history_files_and_orphaned_backups_are_reported() {
+        let history_dir = mk_tmp_dir("doctor_history");
+        let backup_dir = mk_tmp_dir("doctor_backups");
+        File::create(history_dir.join("20240101_120000.000000000")).unwrap();
+        fs::write(
+            history_dir.join("20240102_120000.000000000"),
+            "(b) /dir/My Notes.txt -> /dir/my_notes.txt\n",
+        )
+        .unwrap();
+        File::create(backup_dir.join("my_notes_backup_20240102_120000.123456789.txt")).unwrap();
+        File::create(backup_dir.join("stray_backup_20240103_120000.123456789.txt")).unwrap();
+
+        let engine = DoctorEngine::new(
+            DoctorCli {
+                fix: false,
+                history_dir: Some(history_dir.clone()),
+                backup_dir: Some(backup_dir.clone()),
+            },
+            Cfg::default(),
+        )
+        .expect("DoctorEngine::new should have succeeded.");
+        let anomalies = engine
+            .find_anomalies()
+            .expect("Finding anomalies should have succeeded.");
+        assert_eq!(
+            anomalies,
+            [
+                Anomaly::EmptyHistoryFile(history_dir.join("20240101_120000.000000000")),
+                Anomaly::OrphanedBackup(
+                    backup_dir.join("stray_backup_20240103_120000.123456789.txt")
+                ),
+            ]
+        );
+
+        let mut out: Vec<u8> = vec![];
+        let left = engine
+            .report_anomalies(&anomalies, &mut out)
+            .expect("Reporting anomalies should have succeeded.");
+        assert_eq!(left, 2);
+        assert!(history_dir.join("20240101_120000.000000000").exists());
+    }
+}
@@ -1,10 +1,16 @@
 //! Everything related to the app's CLI.
 
+use crate::apply_plan;
 use crate::cfg::Cfg;
 use crate::cli::Cli;
 use crate::cli::Command;
+use crate::compare;
 use crate::default;
+use crate::detect;
+use crate::doctor;
 use crate::exclude;
+use crate::format;
+use crate::rename;
 use crate::revert;
 
 /// A subcommand engine, a structure that encapsulates the logic of a subcommand.
@@ -45,6 +51,12 @@ pub fn get_engine(cli: Cli, cfg: Cfg) -> anyhow::Result<Box<dyn Engine>> {
     match cli.command {
         Some(Command::Exclude(cli)) => exclude::get_engine(cli, cfg),
         Some(Command::Revert(cli)) => revert::get_engine(cli, cfg),
+        Some(Command::Format(cli)) => format::get_engine(cli, cfg),
+        Some(Command::Detect(cli)) => detect::get_engine(cli, cfg),
+        Some(Command::Compare(cli)) => compare::get_engine(cli, cfg),
+        Some(Command::Doctor(cli)) => doctor::get_engine(cli, cfg),
+        Some(Command::Rename(cli)) => rename::get_engine(cli, cfg),
+        Some(Command::ApplyPlan(cli)) => apply_plan::get_engine(cli, cfg),
         None => default::get_engine(cli.args, cfg),
     }
 }
@@ -5,5 +5,418 @@ mod cli;
 mod del;
 mod edit;
 mod engine;
+mod import;
 pub use cli::ExcludeCli;
 pub use engine::get_engine;
+
+use regex::Regex;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// The prefix of exclude patterns matched against the name of the parent
+/// directory rather than the filename, see [ExcludePattern::Parent].
+pub const PARENT_PREFIX: &str = "parent:";
+
+/// The prefix of the lines of an exclude file including another one, see
+/// [read_exclude_file].
+pub const INCLUDE_DIRECTIVE: &str = "include ";
+
+/// A compiled exclude pattern.
+#[derive(Clone, Debug)]
+pub enum ExcludePattern {
+    /// Matched against the filename.
+    Filename(Regex),
+    /// Matched against the name of the parent directory, written with
+    /// [PARENT_PREFIX] (e.g. `parent:^vendor$`).
+    Parent(Regex),
+}
+
+impl ExcludePattern {
+    /// Compiles `pattern`, as written in the exclude file.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `pattern` (without its prefix) is not a valid regex.
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        match pattern.strip_prefix(PARENT_PREFIX) {
+            Some(pattern) => Regex::new(pattern).map(ExcludePattern::Parent),
+            None => Regex::new(pattern).map(ExcludePattern::Filename),
+        }
+    }
+
+    /// Returns whether `file` is excluded by the pattern.
+    ///
+    /// A file without filename (or parent directory name, for
+    /// [ExcludePattern::Parent]) doesn't match.
+    pub fn is_match(&self, file: &Path) -> bool {
+        let (re, name) = match self {
+            ExcludePattern::Filename(re) => (re, file.file_name()),
+            ExcludePattern::Parent(re) => (re, file.parent().and_then(Path::file_name)),
+        };
+        name.is_some_and(|name| re.is_match(&name.to_string_lossy()))
+    }
+}
+
+impl fmt::Display for ExcludePattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExcludePattern::Filename(re) => write!(f, "{}", re.as_str()),
+            ExcludePattern::Parent(re) => write!(f, "{}{}", PARENT_PREFIX, re.as_str()),
+        }
+    }
+}
+
+/// Returns the exclude pattern on `line`, a line of the exclude file, or
+/// `None` if the line doesn't hold any.
+///
+/// Blank lines and lines starting with `#` or `//` are comments (a pattern
+/// matching a leading `#` can be written `\#`).
+/// A pattern can also be followed by an inline comment, starting at the first
+/// `//` preceded by whitespace, e.g.:
+///
+/// ```text
+/// .*\.log  // build logs
+/// ```
+///
+/// Hence, `//` within a pattern is kept as long as it isn't preceded by
+/// whitespace (e.g. `https?://`). To match whitespace followed by `//`, use
+/// a character class instead, e.g. `\s//` or `[ ]//`.
+///
+/// The pattern is returned as written, prefix included (see [ExcludePattern]).
+///
+/// # Parameters
+///
+/// - `line`: A line of the exclude file, without its trailing newline.
+pub fn parse_exclude_line(line: &str) -> Option<&str> {
+    if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+        return None;
+    }
+
+    let pattern = line
+        .char_indices()
+        .find(|(i, c)| c.is_whitespace() && line[i + c.len_utf8()..].starts_with("//"))
+        .map_or(line, |(i, _)| line[..i].trim_end());
+
+    if pattern.is_empty() {
+        None
+    } else {
+        Some(pattern)
+    }
+}
+
+/// An invalid pattern of an exclude file, as `(line_no, pattern)`
+/// (line numbers starting at 1).
+pub type InvalidPattern = (usize, String);
+
+/// Reads the exclude file at `path` and compiles its patterns.
+///
+/// A line of the form `include <path>` (see [INCLUDE_DIRECTIVE]) is replaced
+/// by the patterns of the exclude file at this path, relative to the
+/// directory of the including file.
+///
+/// # Parameters
+///
+/// - `path`
+///
+/// # Returns
+///
+/// The compiled patterns, along with the invalid ones as `(line_no, pattern)`
+/// (line numbers starting at 1). The invalid patterns of included files are
+/// reported at the line of the include directive, with the file and line
+/// they are at.
+///
+/// # Errors
+///
+/// Fails if the file or a file it includes can't be read, or if a file ends
+/// up including itself.
+pub fn read_exclude_file(path: &Path) -> io::Result<(Vec<ExcludePattern>, Vec<InvalidPattern>)> {
+    read_exclude_file_included(path, &mut vec![])
+}
+
+/// Like [read_exclude_file], `including` being the files including `path`,
+/// directly or not, to detect include cycles.
+fn read_exclude_file_included(
+    path: &Path,
+    including: &mut Vec<PathBuf>,
+) -> io::Result<(Vec<ExcludePattern>, Vec<InvalidPattern>)> {
+    let reader = BufReader::new(File::open(path)?);
+    let canonical_path = path.canonicalize()?;
+    if including.contains(&canonical_path) {
+        let cycle: Vec<_> = including
+            .iter()
+            .chain([&canonical_path])
+            .map(|p| p.to_string_lossy())
+            .collect();
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Include cycle: {}.", cycle.join(" -> ")),
+        ));
+    }
+    including.push(canonical_path);
+
+    let mut patterns = vec![];
+    let mut invalid_patterns = vec![];
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let Some(pattern) = parse_exclude_line(&line) else {
+            continue;
+        };
+
+        if let Some(included) = pattern.strip_prefix(INCLUDE_DIRECTIVE) {
+            let included = path.parent().unwrap_or(Path::new("")).join(included.trim());
+            let (included_patterns, included_invalid_patterns) =
+                read_exclude_file_included(&included, including).map_err(|err| {
+                    io::Error::new(
+                        err.kind(),
+                        format!(
+                            "Failed to include {} (line {} of {}). {}",
+                            included.to_string_lossy(),
+                            i + 1,
+                            path.to_string_lossy(),
+                            err
+                        ),
+                    )
+                })?;
+            patterns.extend(included_patterns);
+            invalid_patterns.extend(included_invalid_patterns.into_iter().map(
+                |(line_no, pattern)| {
+                    (
+                        i + 1,
+                        format!(
+                            "{} (line {} of {})",
+                            pattern,
+                            line_no,
+                            included.to_string_lossy()
+                        ),
+                    )
+                },
+            ));
+            continue;
+        }
+
+        match ExcludePattern::new(pattern) {
+            Ok(pattern) => patterns.push(pattern),
+            Err(_) => invalid_patterns.push((i + 1, pattern.to_string())),
+        }
+    }
+    including.pop();
+
+    Ok((patterns, invalid_patterns))
+}
+
+/// Validates the exclude file at `path`, i.e. checks that all its patterns
+/// are valid regexes.
+///
+/// A nonexistent file is valid, as it holds no pattern.
+///
+/// # Parameters
+///
+/// - `path`
+///
+/// # Errors
+///
+/// Returns all the invalid patterns as `(line_no, pattern)` (line numbers
+/// starting at 1).
+/// If the file can't be read, the reason is returned as the only "pattern",
+/// with line number 0.
+pub fn validate_exclude_file(path: &Path) -> Result<(), Vec<(usize, String)>> {
+    if !path.exists() {
+        return Ok(());
+    }
+
+    match read_exclude_file(path) {
+        Ok((_, invalid_patterns)) if invalid_patterns.is_empty() => Ok(()),
+        Ok((_, invalid_patterns)) => Err(invalid_patterns),
+        Err(err) => Err(vec![(0, err.to_string())]),
+    }
+}
+
+/// Returns a message listing `invalid_patterns`, found in the exclude file
+/// at `path`, for the user.
+///
+/// # Parameters
+///
+/// - `path`
+/// - `invalid_patterns`: As returned by [read_exclude_file].
+pub fn invalid_patterns_message(path: &Path, invalid_patterns: &[InvalidPattern]) -> String {
+    let mut mess = format!(
+        "Found {} invalid exclude pattern(s) in {}:",
+        invalid_patterns.len(),
+        path.to_string_lossy()
+    );
+    for (line_no, pattern) in invalid_patterns {
+        mess.push_str(&format!("\n    line {}: {}", line_no, pattern));
+    }
+
+    mess
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paths::tests::mk_tmp_dir;
+    use std::fs;
+
+    #[test]
+    fn parse_exclude_line_works() {
+        struct TestCase {
+            line: &'static str,
+            expected_output: Option<&'static str>,
+        }
+
+        let test_cases = [
+            TestCase {
+                line: "",
+                expected_output: None,
+            },
+            TestCase {
+                line: "// A full-line comment",
+                expected_output: None,
+            },
+            TestCase {
+                line: "# A full-line comment",
+                expected_output: None,
+            },
+            TestCase {
+                line: "#",
+                expected_output: None,
+            },
+            TestCase {
+                line: r"\#notes",
+                expected_output: Some(r"\#notes"),
+            },
+            TestCase {
+                line: "notes#1",
+                expected_output: Some("notes#1"),
+            },
+            TestCase {
+                line: r".*\.log",
+                expected_output: Some(r".*\.log"),
+            },
+            TestCase {
+                line: r".*\.log  // build logs",
+                expected_output: Some(r".*\.log"),
+            },
+            TestCase {
+                line: "^tmp\t// tab separated",
+                expected_output: Some("^tmp"),
+            },
+            TestCase {
+                line: "https?://",
+                expected_output: Some("https?://"),
+            },
+            TestCase {
+                line: "https?://example // some website",
+                expected_output: Some("https?://example"),
+            },
+            TestCase {
+                line: r"a\s//b",
+                expected_output: Some(r"a\s//b"),
+            },
+            TestCase {
+                line: "   // only an indented comment",
+                expected_output: None,
+            },
+        ];
+
+        for tc in test_cases {
+            assert_eq!(
+                parse_exclude_line(tc.line),
+                tc.expected_output,
+                "line: {:?}",
+                tc.line
+            );
+        }
+    }
+
+    #[test]
+    fn validate_exclude_file_reports_all_invalid_patterns() {
+        let dir = mk_tmp_dir("validate_exclude_file");
+        let path = dir.join("exclude.txt");
+        fs::write(
+            &path,
+            "# Comment\n\\.rs$\n(unclosed\n\n[a-\n^ok$  // fine\n*\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            validate_exclude_file(&path),
+            Err(vec![
+                (3, String::from("(unclosed")),
+                (5, String::from("[a-")),
+                (7, String::from("*")),
+            ])
+        );
+
+        fs::write(&path, "\\.rs$\n^ok$  // fine\n").unwrap();
+        assert_eq!(validate_exclude_file(&path), Ok(()));
+
+        assert_eq!(validate_exclude_file(&dir.join("nonexistent.txt")), Ok(()));
+    }
+
+    #[test]
+    fn included_exclude_files_add_their_patterns() {
+        let dir = mk_tmp_dir("exclude_includes");
+        fs::create_dir_all(dir.join("shared")).unwrap();
+        let path = dir.join("exclude.txt");
+        fs::write(
+            &path,
+            "^first$\ninclude shared/common.txt  // team patterns\n^last$\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("shared").join("common.txt"),
+            "# Shared\n\\.log$\ninclude nested.txt\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("shared").join("nested.txt"),
+            "parent:^vendor$\n(\n",
+        )
+        .unwrap();
+
+        let (patterns, invalid_patterns) = read_exclude_file(&path).unwrap();
+        let patterns: Vec<_> = patterns.iter().map(ExcludePattern::to_string).collect();
+        assert_eq!(
+            patterns,
+            ["^first$", r"\.log$", "parent:^vendor$", "^last$"]
+        );
+        assert_eq!(
+            invalid_patterns,
+            [(
+                2,
+                format!(
+                    "( (line 2 of {}) (line 3 of {})",
+                    dir.join("shared").join("nested.txt").to_string_lossy(),
+                    dir.join("shared").join("common.txt").to_string_lossy()
+                )
+            )]
+        );
+    }
+
+    #[test]
+    fn include_cycles_are_errors() {
+        let dir = mk_tmp_dir("exclude_include_cycles");
+        fs::write(dir.join("a.txt"), "^a$\ninclude b.txt\n").unwrap();
+        fs::write(dir.join("b.txt"), "^b$\ninclude a.txt\n").unwrap();
+
+        let err = read_exclude_file(&dir.join("a.txt")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("Include cycle"));
+    }
+
+    #[test]
+    fn parent_patterns_match_the_name_of_the_parent_directory() {
+        let pattern = ExcludePattern::new("parent:^vendor$").unwrap();
+        assert!(pattern.is_match(Path::new("/project/vendor/lib.js")));
+        assert!(!pattern.is_match(Path::new("/project/vendor")));
+        assert!(!pattern.is_match(Path::new("/project/vendor/lib/lib.js")));
+        assert_eq!(pattern.to_string(), "parent:^vendor$");
+
+        let pattern = ExcludePattern::new("^vendor$").unwrap();
+        assert!(pattern.is_match(Path::new("/project/vendor")));
+        assert!(!pattern.is_match(Path::new("/project/vendor/lib.js")));
+    }
+}
@@ -3,3 +3,4 @@ mod data;
 mod engine;
 pub use cli::AddCli;
 pub use engine::get_engine;
+pub(super) use engine::{add_pattern, find_pattern};
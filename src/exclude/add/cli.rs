@@ -10,4 +10,19 @@ pub struct AddCli {
     /// If the pattern is already in exclude.txt,
     /// nothing will happen and you will be warned about it.
     pub pattern: String,
+
+    #[clap(verbatim_doc_comment)]
+    /// A comment describing the pattern, written as a `// <COMMENT>` line
+    /// right before it.
+    #[arg(long)]
+    pub comment: Option<String>,
+
+    #[clap(verbatim_doc_comment)]
+    /// The section of exclude.txt to add the pattern to.
+    ///
+    /// A section starts with a `// === <SECTION> ===` line and ends where the
+    /// next one starts. The pattern is added at the end of the section,
+    /// which is created at the end of exclude.txt if absent.
+    #[arg(long)]
+    pub section: Option<String>,
 }
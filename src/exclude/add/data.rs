@@ -2,21 +2,25 @@ use anyhow::Context;
 
 use super::cli::AddCli;
 use crate::cfg::Cfg;
-use regex::Regex;
+use crate::exclude::ExcludePattern;
 
 #[derive(Debug)]
 pub struct Data {
     pub exclude_pattern: String,
+    pub comment: Option<String>,
+    pub section: Option<String>,
 }
 
 impl Data {
     pub fn new(cli: AddCli, cfg: Cfg) -> anyhow::Result<Self> {
         let _ = cfg;
 
-        Regex::new(&cli.pattern).with_context(|| "The pattern given is not valid.")?;
+        ExcludePattern::new(&cli.pattern).with_context(|| "The pattern given is not valid.")?;
 
         Ok(Data {
             exclude_pattern: cli.pattern,
+            comment: cli.comment,
+            section: cli.section,
         })
     }
 }
@@ -59,6 +63,8 @@ mod tests {
             test_cases.push(TestCase {
                 cli: AddCli {
                     pattern: String::from(pattern),
+                    comment: None,
+                    section: None,
                 },
                 cfg: Cfg {
                     naming_convention: NamingConvention::SnakeCase,
@@ -67,6 +73,9 @@ mod tests {
                     keep_special_chars: false,
                     keep_unicode: false,
                     editor: String::from("vi"),
+                    acronyms: vec![],
+                    recurse_renames_roots: true,
+                    preserve_dates: false,
                 },
             })
         }
@@ -85,6 +94,8 @@ mod tests {
             test_cases.push(TestCase {
                 cli: AddCli {
                     pattern: String::from(pattern),
+                    comment: None,
+                    section: None,
                 },
                 cfg: Cfg {
                     naming_convention: NamingConvention::SnakeCase,
@@ -93,6 +104,9 @@ mod tests {
                     keep_special_chars: false,
                     keep_unicode: false,
                     editor: String::from("vi"),
+                    acronyms: vec![],
+                    recurse_renames_roots: true,
+                    preserve_dates: false,
                 },
             })
         }
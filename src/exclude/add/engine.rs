@@ -2,12 +2,10 @@ use super::cli::AddCli;
 use super::data::Data;
 use crate::cfg::Cfg;
 use crate::engine::Engine;
+use crate::exclude::parse_exclude_line;
 use crate::paths::EXCLUDE_FILE_PATH;
 use anyhow::Context;
-use std::fs::File;
-use std::fs::OpenOptions;
-use std::io::Write;
-use std::io::{BufRead, BufReader};
+use std::fs;
 
 /// Returns the engine for the add subcommand, parameterized by `cli` and `cfg`.
 ///
@@ -39,41 +37,35 @@ impl Engine for AddEngine {
     fn run(&mut self) -> anyhow::Result<()> {
         let exclude_file_path = &*EXCLUDE_FILE_PATH;
 
-        // Check if pattern to add already is in exclude file
-        if exclude_file_path.exists() {
-            let file = File::open(exclude_file_path.clone())?;
-            let reader = BufReader::new(file);
-            for (line_no, line) in reader.lines().enumerate() {
-                let line = line?;
-
-                if line.is_empty() || line.starts_with("//") {
-                    continue;
-                }
-
-                if line == self.data.exclude_pattern {
-                    println!(
-                        "Exclude pattern already in {}, line {}. Nothing done.",
-                        exclude_file_path.to_string_lossy(),
-                        line_no
-                    );
-                    return Ok(());
-                }
-            }
-        }
-
-        // Append new pattern to exclude file
-        let mut exclude_file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(exclude_file_path.clone())
-            .with_context(|| {
+        let content = if exclude_file_path.exists() {
+            fs::read_to_string(exclude_file_path).with_context(|| {
                 format!(
-                    "Failed to open exclude file ({}).",
+                    "Failed to read exclude file ({}).",
                     exclude_file_path.to_string_lossy()
                 )
-            })?;
+            })?
+        } else {
+            String::new()
+        };
 
-        writeln!(exclude_file, "{}", self.data.exclude_pattern).with_context(|| {
+        // Check if pattern to add already is in exclude file
+        if let Some(line_no) = find_pattern(&content, &self.data.exclude_pattern) {
+            println!(
+                "Exclude pattern already in {}, line {}. Nothing done.",
+                exclude_file_path.to_string_lossy(),
+                line_no
+            );
+            return Ok(());
+        }
+
+        // Add new pattern to exclude file
+        let new_content = add_pattern(
+            &content,
+            &self.data.exclude_pattern,
+            self.data.comment.as_deref(),
+            self.data.section.as_deref(),
+        );
+        fs::write(exclude_file_path, new_content).with_context(|| {
             format!(
                 "Failed to write to exclude file ({}).",
                 exclude_file_path.to_string_lossy()
@@ -83,3 +75,124 @@ impl Engine for AddEngine {
         Ok(())
     }
 }
+
+/// Returns the index of the line of `content`, the content of an exclude
+/// file, holding `pattern`, if any.
+pub fn find_pattern(content: &str, pattern: &str) -> Option<usize> {
+    content
+        .lines()
+        .position(|line| parse_exclude_line(line) == Some(pattern))
+}
+
+/// Returns `content`, the content of an exclude file, with `pattern` added
+/// (preceded by `comment` if any) at the end of `section` if any, or at the
+/// end otherwise.
+/// See [AddCli](super::cli::AddCli) for the details.
+pub fn add_pattern(
+    content: &str,
+    pattern: &str,
+    comment: Option<&str>,
+    section: Option<&str>,
+) -> String {
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let mut new_lines = vec![];
+    if let Some(comment) = comment {
+        new_lines.push(format!("// {}", comment));
+    }
+    new_lines.push(pattern.to_string());
+
+    match section {
+        None => lines.extend(new_lines),
+        Some(section) => {
+            let header = format!("// === {} ===", section);
+            match lines.iter().position(|line| *line == header) {
+                Some(header_idx) => {
+                    let section_end = lines[header_idx + 1..]
+                        .iter()
+                        .position(|line| line.starts_with("// === "))
+                        .map_or(lines.len(), |i| header_idx + 1 + i);
+                    // Insert after the last non-blank line of the section, so
+                    // that blank lines keep separating it from the next one.
+                    let insert_idx = lines[header_idx..section_end]
+                        .iter()
+                        .rposition(|line| !line.trim().is_empty())
+                        .map_or(section_end, |i| header_idx + i + 1);
+                    lines.splice(insert_idx..insert_idx, new_lines);
+                }
+                None => {
+                    if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                        lines.push(String::new());
+                    }
+                    lines.push(header);
+                    lines.extend(new_lines);
+                }
+            }
+        }
+    }
+
+    let mut new_content = lines.join("\n");
+    new_content.push('\n');
+    new_content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_are_appended() {
+        assert_eq!(add_pattern("", "^a$", None, None), "^a$\n");
+        // Even if the last line has no newline
+        assert_eq!(add_pattern("^a$", "^b$", None, None), "^a$\n^b$\n");
+        assert_eq!(
+            add_pattern("^a$\n", "^b$", Some("The b file"), None),
+            "^a$\n// The b file\n^b$\n"
+        );
+    }
+
+    #[test]
+    fn patterns_are_added_at_the_end_of_their_section() {
+        let content = "\
+// === Build ===
+^target$
+
+// === Editors ===
+\\.swp$
+";
+        assert_eq!(
+            add_pattern(content, "^dist$", Some("JS bundles"), Some("Build")),
+            "\
+// === Build ===
+^target$
+// JS bundles
+^dist$
+
+// === Editors ===
+\\.swp$
+"
+        );
+        assert_eq!(
+            add_pattern(content, "~$", None, Some("Editors")),
+            "\
+// === Build ===
+^target$
+
+// === Editors ===
+\\.swp$
+~$
+"
+        );
+    }
+
+    #[test]
+    fn missing_sections_are_created_at_the_end() {
+        assert_eq!(
+            add_pattern("^a$\n", "^node_modules$", None, Some("JS")),
+            "^a$\n\n// === JS ===\n^node_modules$\n"
+        );
+        assert_eq!(
+            add_pattern("", "^node_modules$", None, Some("JS")),
+            "// === JS ===\n^node_modules$\n"
+        );
+    }
+}
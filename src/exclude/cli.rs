@@ -4,6 +4,7 @@ use clap::Subcommand;
 use super::add::AddCli;
 use super::del::DelCli;
 use super::edit::EditCli;
+use super::import::ImportCli;
 
 #[derive(Args, Clone, Debug, PartialEq, Eq)]
 #[clap(verbatim_doc_comment)]
@@ -11,7 +12,15 @@ use super::edit::EditCli;
 ///
 /// Exclude patterns are specified in the configuration file exclude.txt.
 /// This subcommand allows to add/remove entries to/from this file from the
-/// command-line, or open it for edition using your favorite editor.
+/// command-line, import them from a .gitignore file, or open it for edition
+/// using your favorite editor.
+///
+/// Patterns are regexes matched against filenames, or against the name of
+/// the parent directory if prefixed with `parent:` (e.g. `parent:^vendor$`
+/// excludes the files directly within any directory named vendor).
+/// A line `include <path>` adds the patterns of another exclude file, the
+/// path being relative to the directory of the including file (e.g.
+/// `include shared/exclude.txt`), to compose exclude lists.
 pub struct ExcludeCli {
     #[command(subcommand)]
     /// The subcommand.
@@ -23,4 +32,5 @@ pub enum ExcludeCommand {
     Add(AddCli),
     Del(DelCli),
     Edit(EditCli),
+    Import(ImportCli),
 }
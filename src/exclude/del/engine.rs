@@ -2,6 +2,7 @@ use super::cli::DelCli;
 use super::data::Data;
 use crate::cfg::Cfg;
 use crate::engine::Engine;
+use crate::exclude::parse_exclude_line;
 use crate::paths::EXCLUDE_FILE_PATH;
 use anyhow::Context;
 use edit_distance::edit_distance;
@@ -65,21 +66,22 @@ impl Engine for DelEngine {
         for line in reader.lines() {
             let line = line?;
 
-            if line.is_empty() || line.starts_with("//") {
+            let Some(pattern) = parse_exclude_line(&line) else {
                 writeln!(tmp_file, "{}", line).with_context(|| "Failed to write to tempfile.")?;
                 continue;
-            }
+            };
 
-            if line == self.data.exclude_pattern {
+            // The inline comment, if any, is removed along with its pattern.
+            if pattern == self.data.exclude_pattern {
                 found = true;
                 continue;
             }
 
             if !found {
-                let dist = edit_distance(&line, &self.data.exclude_pattern);
+                let dist = edit_distance(pattern, &self.data.exclude_pattern);
                 if dist < min_dist {
                     min_dist = dist;
-                    closest_pattern.clone_from(&line);
+                    closest_pattern = pattern.to_owned();
                 }
             }
 
@@ -9,7 +9,7 @@ pub struct Data {
 impl Data {
     pub fn new(cli: EditCli, cfg: Cfg) -> anyhow::Result<Self> {
         Ok(Data {
-            editor: cli.editor.unwrap_or(cfg.editor),
+            editor: cfg.resolve_editor(cli.editor),
         })
     }
 }
@@ -41,6 +41,9 @@ mod tests {
                     keep_special_chars: false,
                     keep_unicode: false,
                     editor: String::from("vi"),
+                    acronyms: vec![],
+                    recurse_renames_roots: true,
+                    preserve_dates: false,
                 },
                 data: Data {
                     editor: String::from("nvim"),
@@ -56,6 +59,9 @@ mod tests {
                     keep_special_chars: false,
                     keep_unicode: false,
                     editor: String::from("emacs"),
+                    acronyms: vec![],
+                    recurse_renames_roots: true,
+                    preserve_dates: false,
                 },
                 data: Data {
                     editor: String::from("emacs"),
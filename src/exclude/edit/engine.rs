@@ -1,11 +1,15 @@
-use anyhow::{anyhow, Context};
+use anyhow::Context;
 
 use super::cli::EditCli;
 use super::data::Data;
 use crate::cfg::Cfg;
 use crate::engine::Engine;
+use crate::exclude::{invalid_patterns_message, validate_exclude_file};
 use crate::paths::EXCLUDE_FILE_PATH;
-use std::process::Command;
+use crate::utils::open_in_editor;
+use crossterm::style::Stylize;
+use std::io::{self, Write};
+use std::path::Path;
 
 /// Returns the engine for the edit subcommand, parameterized by `cli` and `cfg`.
 ///
@@ -36,42 +40,46 @@ impl EditEngine {
 impl Engine for EditEngine {
     fn run(&mut self) -> anyhow::Result<()> {
         let exclude_file_path = &*EXCLUDE_FILE_PATH;
+        open_in_editor(&self.data.editor, exclude_file_path)?;
+        warn_if_invalid(exclude_file_path, &mut io::stderr().lock())
+    }
+}
+
+/// Writes a warning to `writer` listing the invalid patterns in the exclude file
+/// at `path`, if there is any.
+fn warn_if_invalid<W: Write>(path: &Path, writer: &mut W) -> anyhow::Result<()> {
+    if let Err(invalid_patterns) = validate_exclude_file(path) {
+        let warning = format!(
+            "Warning: {}\nThey will make the next formatting fail, please fix them.",
+            invalid_patterns_message(path, &invalid_patterns)
+        );
+        writeln!(writer, "{}", warning.dark_yellow())
+            .with_context(|| "Failed to write warning.")?;
+    }
 
-        let status = if cfg!(windows) {
-            Command::new("cmd")
-                .arg("/c")
-                .arg(self.data.editor.clone())
-                .arg(exclude_file_path.clone())
-                .status()
-                .with_context(|| {
-                    format!(
-                        "Failed to run '{} {}'.",
-                        self.data.editor,
-                        exclude_file_path.to_string_lossy()
-                    )
-                })?
-        } else {
-            Command::new(self.data.editor.clone())
-                .arg(exclude_file_path.clone())
-                .status()
-                .with_context(|| {
-                    format!(
-                        "Failed to run '{} {}'.",
-                        self.data.editor,
-                        exclude_file_path.to_string_lossy()
-                    )
-                })?
-        };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paths::tests::mk_tmp_dir;
+    use std::fs;
+
+    fn warning_for(exclude_file_content: &str) -> String {
+        let path = mk_tmp_dir("warn_if_invalid").join("exclude.txt");
+        fs::write(&path, exclude_file_content).unwrap();
+        let mut out: Vec<u8> = vec![];
+        warn_if_invalid(&path, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
 
-        if !status.success() {
-            return Err(anyhow!(
-                "Command '{} {}' failed. Exited with status {}.",
-                self.data.editor,
-                exclude_file_path.to_string_lossy(),
-                status,
-            ));
-        }
+    #[test]
+    fn saving_invalid_patterns_produces_a_warning() {
+        let warning = warning_for("\\.rs$\n(unclosed\n");
+        assert!(warning.contains("Warning"));
+        assert!(warning.contains("line 2: (unclosed"));
 
-        Ok(())
+        assert_eq!(warning_for("\\.rs$\n"), "");
     }
 }
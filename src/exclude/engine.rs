@@ -2,6 +2,7 @@ use super::add;
 use super::cli::ExcludeCommand;
 use super::del;
 use super::edit;
+use super::import;
 use super::ExcludeCli;
 use crate::cfg::Cfg;
 use crate::engine::Engine;
@@ -22,5 +23,6 @@ pub fn get_engine(cli: ExcludeCli, cfg: Cfg) -> anyhow::Result<Box<dyn Engine>>
         ExcludeCommand::Add(cli) => add::get_engine(cli, cfg),
         ExcludeCommand::Del(cli) => del::get_engine(cli, cfg),
         ExcludeCommand::Edit(cli) => edit::get_engine(cli, cfg),
+        ExcludeCommand::Import(cli) => import::get_engine(cli, cfg),
     }
 }
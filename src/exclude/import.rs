@@ -0,0 +1,5 @@
+mod cli;
+mod data;
+mod engine;
+pub use cli::ImportCli;
+pub use engine::get_engine;
@@ -0,0 +1,26 @@
+use clap::Args;
+use std::path::PathBuf;
+
+#[derive(Args, Clone, Debug, PartialEq, Eq)]
+#[clap(verbatim_doc_comment)]
+/// Add the patterns of a .gitignore file to exclude.txt.
+///
+/// Each gitignore pattern is translated into a regex matching the same
+/// filenames, e.g. "*.log" into "^.*\.log$". Patterns already in exclude.txt
+/// are not added again.
+/// Since exclude patterns match filenames only, a leading "/" and a trailing
+/// "/" are ignored, and "**/" at the beginning too.
+/// Negated patterns (starting with "!") and patterns with a "/" in the middle
+/// can't be translated. They are skipped and you will be warned about them.
+pub struct ImportCli {
+    #[clap(verbatim_doc_comment)]
+    /// The .gitignore file to import.
+    pub gitignore_file: PathBuf,
+
+    #[clap(verbatim_doc_comment)]
+    /// The section of exclude.txt to add the patterns to.
+    ///
+    /// See the same option of the add subcommand.
+    #[arg(long)]
+    pub section: Option<String>,
+}
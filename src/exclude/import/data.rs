@@ -0,0 +1,20 @@
+use super::cli::ImportCli;
+use crate::cfg::Cfg;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub struct Data {
+    pub gitignore_file: PathBuf,
+    pub section: Option<String>,
+}
+
+impl Data {
+    pub fn new(cli: ImportCli, cfg: Cfg) -> anyhow::Result<Self> {
+        let _ = cfg;
+
+        Ok(Data {
+            gitignore_file: cli.gitignore_file,
+            section: cli.section,
+        })
+    }
+}
@@ -0,0 +1,234 @@
+use super::cli::ImportCli;
+use super::data::Data;
+use crate::cfg::Cfg;
+use crate::engine::Engine;
+use crate::exclude::add::{add_pattern, find_pattern};
+use crate::paths::EXCLUDE_FILE_PATH;
+use anyhow::Context;
+use crossterm::style::Stylize;
+use std::fs;
+
+/// Returns the engine for the import subcommand, parameterized by `cli` and `cfg`.
+///
+/// # Parameters
+///
+/// - `cli`: The CLI arguments.
+/// - `cfg`: The configuration values.
+///
+/// # Returns
+///
+/// The parametrized engine for running the import subcommand's logic, or an
+/// error if engine creation failed.
+pub fn get_engine(cli: ImportCli, cfg: Cfg) -> anyhow::Result<Box<dyn Engine>> {
+    Ok(Box::new(ImportEngine::new(cli, cfg)?))
+}
+
+struct ImportEngine {
+    data: Data,
+}
+
+impl ImportEngine {
+    pub fn new(cli: ImportCli, cfg: Cfg) -> anyhow::Result<Self> {
+        let data = Data::new(cli, cfg)?;
+        Ok(Self { data })
+    }
+}
+
+impl Engine for ImportEngine {
+    fn run(&mut self) -> anyhow::Result<()> {
+        let exclude_file_path = &*EXCLUDE_FILE_PATH;
+
+        let gitignore = fs::read_to_string(&self.data.gitignore_file).with_context(|| {
+            format!(
+                "Failed to read gitignore file ({}).",
+                self.data.gitignore_file.to_string_lossy()
+            )
+        })?;
+        let content = if exclude_file_path.exists() {
+            fs::read_to_string(exclude_file_path).with_context(|| {
+                format!(
+                    "Failed to read exclude file ({}).",
+                    exclude_file_path.to_string_lossy()
+                )
+            })?
+        } else {
+            String::new()
+        };
+
+        let import = import_patterns(&content, &gitignore, self.data.section.as_deref());
+        for (line_no, line, reason) in &import.unsupported {
+            eprintln!(
+                "{}",
+                format!("Warning: Skipped line {} ({}), {}.", line_no, line, reason).dark_yellow()
+            );
+        }
+        if import.added > 0 {
+            fs::write(exclude_file_path, import.content).with_context(|| {
+                format!(
+                    "Failed to write to exclude file ({}).",
+                    exclude_file_path.to_string_lossy()
+                )
+            })?;
+        }
+        println!(
+            "Added {} patterns to {} ({} already there).",
+            import.added,
+            exclude_file_path.to_string_lossy(),
+            import.duplicates
+        );
+
+        Ok(())
+    }
+}
+
+/// The outcome of importing a gitignore file into an exclude file.
+struct Import {
+    /// The new content of the exclude file.
+    content: String,
+    /// Number of patterns added.
+    added: usize,
+    /// Number of patterns not added because already there.
+    duplicates: usize,
+    /// The lines that can't be translated, as `(line_no, line, reason)`
+    /// (line numbers starting at 1).
+    unsupported: Vec<(usize, String, &'static str)>,
+}
+
+/// Adds the patterns of `gitignore`, the content of a gitignore file, to
+/// `content`, the content of an exclude file, in `section` if any.
+fn import_patterns(content: &str, gitignore: &str, section: Option<&str>) -> Import {
+    let mut import = Import {
+        content: content.to_string(),
+        added: 0,
+        duplicates: 0,
+        unsupported: vec![],
+    };
+    for (i, line) in gitignore.lines().enumerate() {
+        match gitignore_to_regex(line) {
+            Ok(None) => {}
+            Ok(Some(pattern)) => {
+                if find_pattern(&import.content, &pattern).is_some() {
+                    import.duplicates += 1;
+                } else {
+                    import.content = add_pattern(&import.content, &pattern, None, section);
+                    import.added += 1;
+                }
+            }
+            Err(reason) => import.unsupported.push((i + 1, line.to_string(), reason)),
+        }
+    }
+
+    import
+}
+
+/// Translates `line`, a line of a gitignore file, into an exclude pattern,
+/// see [ImportCli](super::cli::ImportCli).
+///
+/// # Returns
+///
+/// `None` if the line holds no pattern (e.g. a comment), or the reason why
+/// the line can't be translated.
+fn gitignore_to_regex(line: &str) -> Result<Option<String>, &'static str> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    if line.starts_with('!') {
+        return Err("negated patterns are not supported");
+    }
+
+    let glob = line.strip_prefix("**/").unwrap_or(line);
+    let glob = glob.strip_prefix('/').unwrap_or(glob);
+    let glob = glob.strip_suffix('/').unwrap_or(glob);
+    if glob.is_empty() {
+        return Ok(None);
+    }
+    if glob.contains('/') {
+        return Err("patterns with a \"/\" in the middle are not supported");
+    }
+
+    let mut regex = String::from("^");
+    let mut chars = glob.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => {
+                let class: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                let class = match class.strip_prefix('!') {
+                    Some(negated) => format!("^{}", negated),
+                    None => class,
+                };
+                regex.push('[');
+                regex.push_str(&class.replace('\\', "\\\\"));
+                regex.push(']');
+            }
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    regex.push_str(&regex::escape(&escaped.to_string()));
+                }
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+
+    Ok(Some(regex))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gitignore_patterns_are_translated() {
+        let test_cases = [
+            ("# comment", Ok(None)),
+            ("", Ok(None)),
+            ("*.log", Ok(Some(r"^.*\.log$"))),
+            ("/target/", Ok(Some("^target$"))),
+            ("**/node_modules", Ok(Some("^node_modules$"))),
+            ("file?.[ch]", Ok(Some(r"^file.\.[ch]$"))),
+            ("[!a]*", Ok(Some("^[^a].*$"))),
+            (r"\#notes", Ok(Some(r"^\#notes$"))),
+            ("!keep.log", Err("negated patterns are not supported")),
+            (
+                "docs/build",
+                Err("patterns with a \"/\" in the middle are not supported"),
+            ),
+        ];
+        for (line, expected) in test_cases {
+            assert_eq!(
+                gitignore_to_regex(line),
+                expected.map(|pattern| pattern.map(String::from)),
+                "For {:?}.",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn gitignore_files_are_imported_without_duplicates() {
+        let content = "^target$\n";
+        let gitignore = "\
+# Build
+/target/
+*.log
+
+!important.log
+.DS_Store
+";
+        let import = import_patterns(content, gitignore, None);
+        assert_eq!(import.content, "^target$\n^.*\\.log$\n^\\.DS_Store$\n");
+        assert_eq!(import.added, 2);
+        assert_eq!(import.duplicates, 1);
+        assert_eq!(
+            import.unsupported,
+            vec![(
+                5,
+                String::from("!important.log"),
+                "negated patterns are not supported"
+            )]
+        );
+    }
+}
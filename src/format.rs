@@ -0,0 +1,7 @@
+//! Module for the format subcommand.
+
+mod cli;
+mod data;
+mod engine;
+pub use cli::FormatCli;
+pub use engine::get_engine;
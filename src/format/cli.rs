@@ -0,0 +1,40 @@
+use clap::Args;
+
+use crate::cli::ConventionArgs;
+
+#[derive(Args, Clone, Debug, PartialEq, Eq)]
+#[clap(verbatim_doc_comment)]
+/// Format names given as arguments (or read from stdin) and print them.
+///
+/// No file is touched, each name is only formatted then printed on its own line.
+/// This is useful to try naming conventions or to use fmtna in scripts.
+pub struct FormatCli {
+    #[clap(verbatim_doc_comment)]
+    /// The names to format.
+    ///
+    /// If no name is given, they are read from stdin instead, one per line,
+    /// e.g. `echo "My Thing" | fmtna format -n kebab-case`.
+    pub names: Vec<String>,
+
+    #[clap(flatten)]
+    /// Arguments controlling how names are formatted.
+    pub convention: ConventionArgs,
+
+    #[clap(verbatim_doc_comment)]
+    /// Treat the names as filenames.
+    ///
+    /// When set, the extension of each name is left untouched, exactly like
+    /// when formatting the name of a file.
+    #[arg(long)]
+    pub as_filename: bool,
+
+    #[clap(verbatim_doc_comment)]
+    /// Print each step of the formatting of the names, not only the result.
+    ///
+    /// Each name is followed by its intermediate states, one step per line,
+    /// e.g. once transliterated to ASCII, once split into words, and once the
+    /// naming convention applied. Only the steps enabled by the options are
+    /// printed, the last one being the formatted name.
+    #[arg(long)]
+    pub explain: bool,
+}
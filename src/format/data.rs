@@ -0,0 +1,32 @@
+use super::cli::FormatCli;
+use crate::cfg::Cfg;
+use crate::naming_conventions::FormatOptions;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Data {
+    /// Same as [FormatCli::names](crate::format::FormatCli::names)
+    pub names: Vec<String>,
+
+    /// Aggregation of [FormatCli::convention](crate::format::FormatCli::convention)
+    /// and the configuration file.
+    pub format_options: FormatOptions,
+
+    /// Same as [FormatCli::as_filename](crate::format::FormatCli::as_filename)
+    pub as_filename: bool,
+
+    /// Same as [FormatCli::explain](crate::format::FormatCli::explain)
+    pub explain: bool,
+}
+
+impl Data {
+    pub fn new(cli: FormatCli, cfg: Cfg) -> anyhow::Result<Self> {
+        let format_options = FormatOptions::new(cli.convention, &cfg);
+
+        Ok(Data {
+            names: cli.names,
+            format_options,
+            as_filename: cli.as_filename,
+            explain: cli.explain,
+        })
+    }
+}
@@ -0,0 +1,186 @@
+use super::cli::FormatCli;
+use super::data::Data;
+use crate::cfg::Cfg;
+use crate::engine::Engine;
+use anyhow::Context;
+use std::io;
+use std::io::{BufRead, Write};
+
+/// Returns the engine for the format subcommand, parameterized by `cli` and `cfg`.
+///
+/// # Parameters
+///
+/// - `cli`: The CLI arguments.
+/// - `cfg`: The configuration values.
+///
+/// # Returns
+///
+/// The parametrized engine for running the format subcommand's logic, or an
+/// error if engine creation failed.
+pub fn get_engine(cli: FormatCli, cfg: Cfg) -> anyhow::Result<Box<dyn Engine>> {
+    Ok(Box::new(FormatEngine::new(cli, cfg)?))
+}
+
+struct FormatEngine {
+    data: Data,
+}
+
+impl FormatEngine {
+    pub fn new(cli: FormatCli, cfg: Cfg) -> anyhow::Result<Self> {
+        let data = Data::new(cli, cfg)?;
+        Ok(Self { data })
+    }
+
+    fn format(&self, name: &str) -> String {
+        if self.data.as_filename {
+            self.data.format_options.format_filename(name)
+        } else {
+            self.data.format_options.format_name(name)
+        }
+    }
+
+    fn write_formatted<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
+        for name in &self.data.names {
+            if self.data.explain {
+                self.write_explained(name, writer)?;
+            } else {
+                writeln!(writer, "{}", self.format(name))
+                    .with_context(|| "Failed to write formatted name.")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes `name` followed by the steps of its formatting, see
+    /// [FormatCli::explain].
+    fn write_explained<W: Write>(&self, name: &str, writer: &mut W) -> anyhow::Result<()> {
+        let steps = self
+            .data
+            .format_options
+            .explain(name, self.data.as_filename);
+        let width = steps.iter().map(|(step, _)| step.len()).max().unwrap_or(0);
+        writeln!(writer, "{}", name).with_context(|| "Failed to write explained name.")?;
+        for (step, state) in steps {
+            writeln!(writer, "    {:<width$}  {}", step, state, width = width)
+                .with_context(|| "Failed to write explained name.")?;
+        }
+
+        Ok(())
+    }
+
+    /// Formats each line read from `reader` and writes it to `writer`.
+    fn format_lines<R: BufRead, W: Write>(&self, reader: R, writer: &mut W) -> anyhow::Result<()> {
+        for line in reader.lines() {
+            let line = line.with_context(|| "Failed to read name to format.")?;
+            if self.data.explain {
+                self.write_explained(&line, writer)?;
+            } else {
+                writeln!(writer, "{}", self.format(&line))
+                    .with_context(|| "Failed to write formatted name.")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Engine for FormatEngine {
+    fn run(&mut self) -> anyhow::Result<()> {
+        if self.data.names.is_empty() {
+            return self.format_lines(io::stdin().lock(), &mut io::stdout().lock());
+        }
+
+        self.write_formatted(&mut io::stdout().lock())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Command};
+    use clap::Parser;
+
+    fn mk_engine(args: &[&str]) -> FormatEngine {
+        let cli = Cli::try_parse_from(args).expect("Arguments should be valid.");
+        match cli.command {
+            Some(Command::Format(cli)) => FormatEngine::new(cli, Cfg::default())
+                .expect("FormatEngine::new should have succeeded."),
+            _ => panic!("Expected the format subcommand."),
+        }
+    }
+
+    fn output_of(engine: &FormatEngine) -> String {
+        let mut out: Vec<u8> = vec![];
+        engine
+            .write_formatted(&mut out)
+            .expect("Writing to a Vec shouldn't fail.");
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn prints_formatted_names() {
+        let engine = mk_engine(&[
+            "fmtna",
+            "format",
+            "--naming-convention",
+            "kebab-case",
+            "My File",
+        ]);
+        assert_eq!(output_of(&engine), "my-file\n");
+
+        let engine = mk_engine(&["fmtna", "format", "-n", "camelCase", "a_b", "My File"]);
+        assert_eq!(output_of(&engine), "aB\nmyFile\n");
+    }
+
+    #[test]
+    fn as_filename_leaves_extension_untouched() {
+        let engine = mk_engine(&["fmtna", "format", "-n", "snake_case", "My Report.pdf"]);
+        assert_eq!(output_of(&engine), "my_report_pdf\n");
+
+        let engine = mk_engine(&[
+            "fmtna",
+            "format",
+            "-n",
+            "snake_case",
+            "--as-filename",
+            "My Report.pdf",
+        ]);
+        assert_eq!(output_of(&engine), "my_report.pdf\n");
+    }
+
+    #[test]
+    fn formats_lines_read_when_no_name_is_given() {
+        let engine = mk_engine(&["fmtna", "format", "-n", "kebab-case"]);
+        let mut out: Vec<u8> = vec![];
+        engine
+            .format_lines("My Thing\r\nAnother_Thing\n".as_bytes(), &mut out)
+            .expect("Formatting from a slice shouldn't fail.");
+        assert_eq!(String::from_utf8(out).unwrap(), "my-thing\nanother-thing\n");
+    }
+
+    #[test]
+    fn explain_prints_each_step() {
+        let engine = mk_engine(&[
+            "fmtna",
+            "format",
+            "-n",
+            "kebab-case",
+            "--as-filename",
+            "--explain",
+            " Été Report.PDF",
+        ]);
+        assert_eq!(
+            output_of(&engine),
+            " Été Report.PDF\n\
+             \x20   original             Été Report.PDF\n\
+             \x20   normalized          Été Report.PDF\n\
+             \x20   stem                Été Report\n\
+             \x20   extension           PDF\n\
+             \x20   transliterated      Ete Report\n\
+             \x20   words               [Ete, Report]\n\
+             \x20   convention applied  ete-report\n\
+             \x20   final               ete-report.PDF\n"
+        );
+    }
+}
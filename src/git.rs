@@ -0,0 +1,64 @@
+//! Interactions with git, by shelling out to the `git` executable.
+
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+/// Returns whether `path` is tracked by git, i.e. is within a git work tree
+/// and known to the index (for a directory, at least one file within it is).
+///
+/// Returns `false` as well if git can't be run.
+///
+/// # Parameters
+///
+/// - `path`: An absolute path.
+pub fn is_tracked(path: &Path) -> bool {
+    let (Some(dir), Some(filename)) = (path.parent(), path.file_name()) else {
+        return false;
+    };
+
+    let inside_work_tree = Command::new("git")
+        .current_dir(dir)
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .output()
+        .is_ok_and(|output| output.status.success() && output.stdout.starts_with(b"true"));
+    if !inside_work_tree {
+        return false;
+    }
+
+    Command::new("git")
+        .current_dir(dir)
+        .args(["ls-files", "--error-unmatch", "--"])
+        .arg(filename)
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Renames `from` into `to` with `git mv`, so that the renaming is staged.
+///
+/// # Parameters
+///
+/// - `from`: An absolute path tracked by git, see [is_tracked].
+/// - `to`: An absolute path, in the same directory as `from`.
+///
+/// # Errors
+///
+/// Fails if git can't be run or if `git mv` fails, in which case the error
+/// message is what git printed.
+pub fn git_mv(from: &Path, to: &Path) -> io::Result<()> {
+    let dir = from.parent().unwrap_or(Path::new("."));
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(["mv", "--"])
+        .arg(from)
+        .arg(to)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(())
+}
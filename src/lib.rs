@@ -1,13 +1,21 @@
 #![doc = include_str!("../README.md")]
 #![warn(missing_docs)]
 
+pub mod apply_plan;
 pub mod cfg;
 pub mod cli;
+pub mod compare;
 pub mod default;
+pub mod detect;
+pub mod doctor;
 pub mod engine;
 pub mod exclude;
+pub mod format;
+pub mod git;
+pub mod lock;
 pub mod naming_conventions;
 pub mod paths;
 pub mod prompt;
+pub mod rename;
 pub mod revert;
 pub mod utils;
@@ -0,0 +1,232 @@
+//! Advisory lock preventing concurrent runs from racing on the same files.
+
+use anyhow::{anyhow, Context};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::thread;
+use std::time::Duration;
+
+/// How long to wait before reading again a lock file that doesn't hold a PID.
+const UNREADABLE_LOCK_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// A lock held for the duration of a run, released when dropped.
+///
+/// The lock is a file holding the PID of the process holding it. It is only
+/// advisory: runs given --no-lock ignore it.
+#[derive(Debug)]
+pub struct RunLock {
+    path: PathBuf,
+}
+
+impl RunLock {
+    /// Acquires the lock at `path`, creating its parent directory if needed.
+    ///
+    /// A lock left by a run that crashed, i.e. whose process is not running
+    /// anymore, is stale: it is taken over, with a warning written to
+    /// `writer`. A lock file that doesn't hold a PID is considered held, as
+    /// it can't be told whether its process is running.
+    ///
+    /// # Parameters
+    ///
+    /// - `path`: The path to the lock file.
+    /// - `writer`: Where to write warnings.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the lock is held by a running process (or may be), or if the
+    /// lock file can't be read or written.
+    pub fn acquire<W: Write>(path: &Path, writer: &mut W) -> anyhow::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!(
+                    "Failed to create directory of lock file ({}).",
+                    parent.to_string_lossy()
+                )
+            })?;
+        }
+
+        match create_lock_file(path) {
+            Ok(()) => {
+                return Ok(RunLock {
+                    path: path.to_owned(),
+                })
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("Failed to create lock file ({}).", path.to_string_lossy())
+                })
+            }
+        }
+
+        let pid = match read_pid(path) {
+            Ok(None) => {
+                thread::sleep(UNREADABLE_LOCK_RETRY_DELAY);
+                read_pid(path)
+            }
+            res => res,
+        };
+        let pid = match pid {
+            Ok(pid) => pid,
+            // Released in the meantime.
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Self::acquire(path, writer)
+            }
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("Failed to read lock file ({}).", path.to_string_lossy())
+                })
+            }
+        };
+        match pid {
+            None => {
+                return Err(anyhow!(
+                    "Another run of fmtna may be in progress, as {} exists, though it \
+                     doesn't hold a PID.\n\
+                     Remove it if you are sure no other run is in progress, or use \
+                     --no-lock if you are sure the runs won't touch the same files.",
+                    path.to_string_lossy()
+                ))
+            }
+            Some(pid) if is_running(pid) => {
+                return Err(anyhow!(
+                    "Another run of fmtna (PID {}) is in progress, as {} exists.\n\
+                     Wait for it to end, or use --no-lock if you are sure the runs won't \
+                     touch the same files.",
+                    pid,
+                    path.to_string_lossy()
+                ))
+            }
+            Some(_) => {}
+        }
+
+        writeln!(
+            writer,
+            "Warning: Taking over the lock file {} left by a run that didn't end properly.",
+            path.to_string_lossy()
+        )?;
+        fs::remove_file(path).with_context(|| {
+            format!(
+                "Failed to remove stale lock file ({}).",
+                path.to_string_lossy()
+            )
+        })?;
+
+        Self::acquire(path, writer)
+    }
+}
+
+impl Drop for RunLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Creates the lock file at `path`, holding the PID of the current process.
+///
+/// The PID is written to a temporary file first, which is then linked at
+/// `path`, so that other processes never see the lock file without it.
+///
+/// # Errors
+///
+/// Fails with [io::ErrorKind::AlreadyExists] if the lock file exists.
+fn create_lock_file(path: &Path) -> io::Result<()> {
+    let tmp_path = path.with_extension(format!("{}.tmp", process::id()));
+    let mut tmp_file = File::create(&tmp_path)?;
+    let res = write!(tmp_file, "{}", process::id())
+        .and_then(|()| tmp_file.sync_all())
+        .and_then(|()| fs::hard_link(&tmp_path, path));
+    let _ = fs::remove_file(&tmp_path);
+
+    res
+}
+
+/// Returns the PID held by the lock file at `path`, if it holds one.
+fn read_pid(path: &Path) -> io::Result<Option<u32>> {
+    Ok(fs::read_to_string(path)?.trim().parse::<u32>().ok())
+}
+
+/// Returns whether a process with `pid` is running.
+///
+/// In doubt (e.g. if this can't be checked), the process is considered running.
+fn is_running(pid: u32) -> bool {
+    if pid == process::id() {
+        return true;
+    }
+
+    if cfg!(windows) {
+        Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+            .output()
+            .map_or(true, |output| {
+                String::from_utf8_lossy(&output.stdout).contains(&pid.to_string())
+            })
+    } else if pid == 0 || pid > i32::MAX as u32 {
+        // Not a PID, but process groups for kill.
+        false
+    } else {
+        Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .stderr(process::Stdio::null())
+            .status()
+            .map_or(true, |status| status.success())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paths::tests::mk_tmp_dir;
+
+    #[test]
+    fn locks_are_exclusive_until_released() {
+        let path = mk_tmp_dir("run_lock").join("fmtna.lock");
+        let mut err: Vec<u8> = vec![];
+
+        let lock = RunLock::acquire(&path, &mut err).expect("The lock should be free.");
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            process::id().to_string()
+        );
+        assert!(RunLock::acquire(&path, &mut err)
+            .expect_err("The lock should be held.")
+            .to_string()
+            .contains("in progress"));
+
+        drop(lock);
+        assert!(!path.exists());
+        RunLock::acquire(&path, &mut err).expect("The lock should have been released.");
+        assert!(err.is_empty());
+    }
+
+    #[test]
+    fn lock_files_without_a_pid_are_held() {
+        let path = mk_tmp_dir("unreadable_run_lock").join("fmtna.lock");
+        fs::write(&path, "").unwrap();
+        let mut err: Vec<u8> = vec![];
+
+        assert!(RunLock::acquire(&path, &mut err)
+            .expect_err("The lock should be held.")
+            .to_string()
+            .contains("may be in progress"));
+        assert!(path.exists());
+        assert!(err.is_empty());
+    }
+
+    #[test]
+    fn stale_locks_are_taken_over() {
+        let path = mk_tmp_dir("stale_run_lock").join("fmtna.lock");
+        // Above the maximum PID of any system.
+        fs::write(&path, i32::MAX.to_string()).unwrap();
+        let mut err: Vec<u8> = vec![];
+
+        let _lock = RunLock::acquire(&path, &mut err).expect("The lock should be stale.");
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            process::id().to_string()
+        );
+        assert!(String::from_utf8(err).unwrap().contains("Taking over"));
+    }
+}
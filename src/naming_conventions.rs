@@ -1,45 +1,87 @@
 //! Naming conventions and corresponding converters.
 
+use crate::cfg::Cfg;
+use crate::cli::ConventionArgs;
 use clap::ValueEnum;
 use lazy_static::lazy_static;
+use regex::Regex;
 use serde::Deserialize;
 use serde::Serialize;
-use std::fmt::Debug;
+use serde::Serializer;
+use std::borrow::Cow;
+use std::fmt::{self, Debug, Display};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use unicode_segmentation::UnicodeSegmentation;
 use unidecode::unidecode;
 
 lazy_static! {
     static ref SEPARATORS: [char; 7] = ['_', '-', '.', ' ', '\t', '\r', '\n'];
+    static ref ISO_DATE_RE: Regex =
+        Regex::new(r"\d{4}-(0[1-9]|1[0-2])-(0[1-9]|[12]\d|3[01])").unwrap();
 }
 
 #[derive(ValueEnum, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 /// The supported naming conventions a filename can be rewritten into.
+///
+/// Besides their canonical name, they can be given by a shorter alias (e.g.
+/// "snake" for "snake_case"), on the CLI as in the configuration file.
+/// The canonical name is the one written back.
 pub enum NamingConvention {
-    #[serde(rename = "camelCase")]
-    #[value(name = "camelCase")]
+    #[serde(rename = "camelCase", alias = "camel")]
+    #[value(name = "camelCase", alias = "camel")]
     /// The camelCase naming convention.
     CamelCase,
-    #[serde(rename = "kebab-case")]
-    #[value(name = "kebab-case")]
+    #[serde(rename = "kebab-case", alias = "kebab")]
+    #[value(name = "kebab-case", alias = "kebab")]
     /// The kebab-case naming convention.
     KebabCase,
-    #[serde(rename = "snake_case")]
-    #[value(name = "snake_case")]
+    #[serde(rename = "Train-Case", alias = "train")]
+    #[value(name = "Train-Case", alias = "train")]
+    /// The Train-Case naming convention, as used by HTTP headers.
+    TrainCase,
+    #[serde(rename = "snake_case", alias = "snake")]
+    #[value(name = "snake_case", alias = "snake")]
     /// The snake_case naming convention.
     SnakeCase,
-    #[serde(rename = "PascalCase")]
-    #[value(name = "PascalCase")]
+    #[serde(rename = "SCREAMING_SNAKE_CASE", alias = "screaming_snake")]
+    #[value(name = "SCREAMING_SNAKE_CASE", alias = "screaming_snake")]
+    /// The SCREAMING_SNAKE_CASE naming convention.
+    ScreamingSnakeCase,
+    #[serde(rename = "PascalCase", alias = "pascal")]
+    #[value(name = "PascalCase", alias = "pascal")]
     /// The PascalCase naming convention.
     PascalCase,
-    #[serde(rename = "lower")]
-    #[value(name = "lower")]
+    #[serde(rename = "Title Case", alias = "title")]
+    #[value(name = "Title Case", alias = "title")]
+    /// The Title Case naming convention, for human-readable names.
+    TitleCase,
+    #[serde(rename = "lower", alias = "lowercase")]
+    #[value(name = "lower", alias = "lowercase")]
     /// The lowercase naming convention.
     Lower,
-    #[serde(rename = "UPPER")]
-    #[value(name = "UPPER")]
+    #[serde(rename = "UPPER", alias = "upper", alias = "uppercase")]
+    #[value(name = "UPPER", aliases = ["upper", "uppercase"])]
     /// The UPPERCASE naming convention.
     Upper,
 }
 
+impl NamingConvention {
+    /// Returns the separator to put between two words, when appending a word
+    /// to a name already following the naming convention.
+    pub fn word_separator(&self) -> &'static str {
+        match self {
+            NamingConvention::KebabCase | NamingConvention::TrainCase => "-",
+            NamingConvention::SnakeCase
+            | NamingConvention::ScreamingSnakeCase
+            | NamingConvention::Lower
+            | NamingConvention::Upper => "_",
+            NamingConvention::CamelCase | NamingConvention::PascalCase => "",
+            NamingConvention::TitleCase => " ",
+        }
+    }
+}
+
 /// Rewrites `filename` according to the naming convention `nc`.
 ///
 /// # Parameters
@@ -47,12 +89,12 @@ pub enum NamingConvention {
 /// - `nc`
 /// - `filename`
 /// - `keep_dots`: Whether to keep the dots as is in `filename`
-///     or consider them as separators.
+///   or consider them as separators.
 /// - `keep_special_chars`: Whether to keep the special characters
-///     or remove them (or try converting them to a non-accented
-///     version if accented character).
+///   or remove them (or try converting them to a non-accented
+///   version if accented character).
 /// - `keep_unicdoe`: Whether to keep Unicode (more precisely,
-///     non-ASCII characters) characters or to remove them.
+///   non-ASCII characters) characters or to remove them.
 ///
 /// # Returns
 ///
@@ -85,207 +127,1256 @@ pub fn apply_nc(
         NamingConvention::KebabCase => {
             kebab_case(filename, keep_dots, keep_special_chars, keep_unicode)
         }
+        NamingConvention::TrainCase => {
+            train_case(filename, keep_dots, keep_special_chars, keep_unicode)
+        }
         NamingConvention::SnakeCase => {
             snake_case(filename, keep_dots, keep_special_chars, keep_unicode)
         }
+        NamingConvention::ScreamingSnakeCase => {
+            screaming_snake_case(filename, keep_dots, keep_special_chars, keep_unicode)
+        }
         NamingConvention::PascalCase => {
             pascal_case(filename, keep_dots, keep_special_chars, keep_unicode)
         }
+        NamingConvention::TitleCase => {
+            title_case(filename, keep_dots, keep_special_chars, keep_unicode)
+        }
         NamingConvention::Lower => lower(filename, keep_dots, keep_special_chars, keep_unicode),
         NamingConvention::Upper => upper(filename, keep_dots, keep_special_chars, keep_unicode),
     }
 }
 
-fn camel_case(s: &str, keep_dots: bool, keep_special_chars: bool, keep_unicode: bool) -> String {
-    if s.is_empty() {
-        return String::from("");
-    }
-
-    let mut new_s = String::from("");
-    let mut slice = s;
-    // in case we are dealing with a dotfile
-    if s.starts_with('.') {
-        new_s.push('.');
-        slice = &slice[1..];
-    }
-
-    let unidecoded: String;
-    if !keep_unicode {
-        unidecoded = unidecode(slice);
-        slice = unidecoded.as_ref();
-    }
-
-    let mut should_upper = false;
-    for (i, c) in slice.chars().enumerate() {
-        if SEPARATORS.contains(&c) && !(keep_dots && c == '.') && i > 0 && i < slice.len() - 1 {
-            should_upper = true;
-        } else if is_special(&c) {
-            if keep_special_chars {
-                new_s.push(c);
-            } else {
-                continue;
+/// Collapses each run of whitespace characters in `s` into a single space.
+///
+/// # Parameters
+///
+/// - `s`
+///
+/// # Examples
+///
+/// ```rust
+/// use fmtna::naming_conventions::squeeze_whitespace;
+///
+/// assert_eq!(squeeze_whitespace("a  \t b "), "a b ");
+/// ```
+pub fn squeeze_whitespace(s: &str) -> String {
+    let mut squeezed = String::with_capacity(s.len());
+    let mut in_whitespace = false;
+    for c in s.chars() {
+        if c.is_whitespace() {
+            if !in_whitespace {
+                squeezed.push(' ');
             }
-        } else if c.is_uppercase() && i > 0 && slice.chars().nth(i - 1).unwrap().is_lowercase() {
-            new_s.push(c);
-        } else if should_upper {
-            new_s.push(c.to_uppercase().next().unwrap());
-            should_upper = false;
+            in_whitespace = true;
         } else {
-            new_s.push(c.to_lowercase().next().unwrap());
+            squeezed.push(c);
+            in_whitespace = false;
         }
     }
 
-    new_s
+    squeezed
 }
 
-fn kebab_case(s: &str, keep_dots: bool, keep_special_chars: bool, keep_unicode: bool) -> String {
-    if s.is_empty() {
-        return String::from("");
-    }
-
-    let mut new_s = String::from("");
-    let mut slice = s;
-    // in case we are dealing with a dotfile
-    if s.starts_with('.') {
-        new_s.push('.');
-        slice = &slice[1..];
-    }
-
-    let unidecoded: String;
-    if !keep_unicode {
-        unidecoded = unidecode(slice);
-        slice = unidecoded.as_ref();
-    }
-
-    for (i, c) in slice.chars().enumerate() {
-        if c.is_uppercase() {
-            if i > 0 && slice.chars().nth(i - 1).unwrap().is_lowercase() {
-                new_s.push('-');
+/// Separates acronyms from the word following them, by inserting a space
+/// before the last uppercase letter of each run of uppercase letters followed
+/// by a lowercase letter.
+///
+/// Known acronyms are units of their own instead: they are separated from
+/// a following uppercase letter, and never split.
+///
+/// # Parameters
+///
+/// - `s`
+/// - `acronyms`: The known acronyms, matched case-sensitively at the beginning
+///   of runs of uppercase letters (the longest first).
+///
+/// # Examples
+///
+/// ```rust
+/// use fmtna::naming_conventions::split_acronyms;
+///
+/// assert_eq!(split_acronyms("URLParser", &[]), "URL Parser");
+/// assert_eq!(split_acronyms("AString", &[]), "A String");
+/// assert_eq!(split_acronyms("parseURL", &[]), "parseURL");
+///
+/// let acronyms = [String::from("HTTP"), String::from("API")];
+/// assert_eq!(split_acronyms("HTTPAPIs", &[]), "HTTPAP Is");
+/// assert_eq!(split_acronyms("HTTPAPIs", &acronyms), "HTTP APIs");
+/// ```
+pub fn split_acronyms(s: &str, acronyms: &[String]) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    let mut split = String::with_capacity(s.len() + 4);
+    let mut i = 0;
+    let mut after_known_acronym = false;
+    while i < chars.len() {
+        let c = chars[i];
+        let starts_run = i == 0 || !chars[i - 1].is_uppercase() || after_known_acronym;
+        if let Some(len) = starts_run
+            .then(|| known_acronym_len(&chars[i..], acronyms))
+            .flatten()
+        {
+            split.extend(&chars[i..i + len]);
+            i += len;
+            after_known_acronym = chars.get(i).is_some_and(|next| next.is_uppercase());
+            if after_known_acronym {
+                split.push(' ');
             }
-            new_s.push(c.to_lowercase().next().unwrap());
-        } else if SEPARATORS.contains(&c) && !(keep_dots && c == '.') {
-            new_s.push('-');
-        } else if !keep_special_chars && is_special(&c) {
             continue;
-        } else {
-            new_s.push(c.to_lowercase().next().unwrap());
         }
+        after_known_acronym = false;
+
+        let ends_acronym = i > 0
+            && c.is_uppercase()
+            && chars[i - 1].is_uppercase()
+            && chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+        if ends_acronym && !split.ends_with(' ') {
+            split.push(' ');
+        }
+        split.push(c);
+        i += 1;
     }
 
-    new_s
+    split
 }
 
-fn snake_case(s: &str, keep_dots: bool, keep_special_chars: bool, keep_unicode: bool) -> String {
-    if s.is_empty() {
-        return String::from("");
-    }
+/// Returns the length (in characters) of the longest acronym of `acronyms`
+/// that `chars` starts with, if any.
+fn known_acronym_len(chars: &[char], acronyms: &[String]) -> Option<usize> {
+    acronyms
+        .iter()
+        .map(|acronym| acronym.chars().collect::<Vec<_>>())
+        .filter(|acronym| !acronym.is_empty() && chars.starts_with(acronym))
+        .map(|acronym| acronym.len())
+        .max()
+}
 
-    let mut new_s = String::from("");
-    let mut slice = s;
-    // in case we are dealing with a dotfile
-    if s.starts_with('.') {
-        new_s.push('.');
-        slice = &slice[1..];
-    }
+/// Separates the words of `s`, as delimited by Unicode word boundaries
+/// (see [UAX #29](https://www.unicode.org/reports/tr29/)), by inserting a
+/// space between adjacent ones.
+///
+/// # Parameters
+///
+/// - `s`
+///
+/// # Examples
+///
+/// ```rust
+/// use fmtna::naming_conventions::split_unicode_words;
+///
+/// assert_eq!(split_unicode_words("東京report"), "東 京 report");
+/// assert_eq!(split_unicode_words("some words"), "some words");
+/// ```
+pub fn split_unicode_words(s: &str) -> String {
+    let is_word = |segment: &str| segment.chars().any(char::is_alphanumeric);
 
-    let unidecoded: String;
-    if !keep_unicode {
-        unidecoded = unidecode(slice);
-        slice = unidecoded.as_ref();
+    let mut split = String::with_capacity(s.len());
+    let mut previous_is_word = false;
+    for segment in s.split_word_bounds() {
+        let current_is_word = is_word(segment);
+        if previous_is_word && current_is_word {
+            split.push(' ');
+        }
+        split.push_str(segment);
+        previous_is_word = current_is_word;
     }
 
-    for (i, c) in slice.chars().enumerate() {
-        if c.is_uppercase() {
-            if i > 0 && slice.chars().nth(i - 1).unwrap().is_lowercase() {
-                new_s.push('_');
+    split
+}
+
+/// Splits `s` into parts that are either one of the `protected` substrings,
+/// or text in between, telling which are protected.
+///
+/// Occurrences are searched from left to right, and the longest protected
+/// substring wins when several start at the same position.
+///
+/// # Parameters
+///
+/// - `s`
+/// - `protected`: The substrings to keep as is.
+///
+/// # Examples
+///
+/// ```rust
+/// use fmtna::naming_conventions::split_protected;
+///
+/// assert_eq!(
+///     split_protected("Order SKU-1 x", &[String::from("SKU-1")]),
+///     vec![("Order ", false), ("SKU-1", true), (" x", false)]
+/// );
+/// ```
+pub fn split_protected<'a>(s: &'a str, protected: &[String]) -> Vec<(&'a str, bool)> {
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut i = 0;
+    while i < s.len() {
+        let longest = protected
+            .iter()
+            .filter(|p| !p.is_empty() && s[i..].starts_with(p.as_str()))
+            .map(String::len)
+            .max();
+        match longest {
+            Some(len) => {
+                if start < i {
+                    parts.push((&s[start..i], false));
+                }
+                parts.push((&s[i..i + len], true));
+                i += len;
+                start = i;
             }
-            new_s.push(c.to_lowercase().next().unwrap());
-        } else if SEPARATORS.contains(&c) && !(keep_dots && c == '.') {
-            new_s.push('_');
-        } else if !keep_special_chars && is_special(&c) {
-            continue;
-        } else {
-            new_s.push(c.to_lowercase().next().unwrap());
+            None => i += s[i..].chars().next().map_or(1, char::len_utf8),
         }
     }
+    if start < s.len() {
+        parts.push((&s[start..], false));
+    }
 
-    new_s
+    parts
 }
 
-fn pascal_case(s: &str, keep_dots: bool, keep_special_chars: bool, keep_unicode: bool) -> String {
-    capitalize(&camel_case(s, keep_dots, keep_special_chars, keep_unicode))
+/// Removes the separators at the beginning and at the end of `name`, except
+/// for the leading dot of a dotfile.
+///
+/// # Parameters
+///
+/// - `name`
+/// - `keep_dots`: Whether dots are kept as is, i.e. are not separators.
+///
+/// # Examples
+///
+/// ```rust
+/// use fmtna::naming_conventions::trim_separators;
+///
+/// assert_eq!(trim_separators("_foo_bar_", false), "foo_bar");
+/// assert_eq!(trim_separators("._foo-", false), ".foo");
+/// assert_eq!(trim_separators("foo.", true), "foo.");
+/// ```
+pub fn trim_separators(name: &str, keep_dots: bool) -> String {
+    let is_separator = |c: char| SEPARATORS.contains(&c) && !(keep_dots && c == '.');
+    match name.strip_prefix('.') {
+        Some(rest) => format!(".{}", rest.trim_matches(is_separator)),
+        None => name.trim_matches(is_separator).to_string(),
+    }
 }
 
-fn lower(s: &str, keep_dots: bool, keep_special_chars: bool, keep_unicode: bool) -> String {
-    if s.is_empty() {
-        return String::from("");
+/// Returns the naming conventions `name` already follows, i.e. those for which
+/// [apply_nc] leaves `name` unchanged (in declaration order).
+///
+/// # Parameters
+///
+/// - `name`
+/// - `keep_dots`, `keep_special_chars`, `keep_unicode`: See [apply_nc].
+///
+/// # Examples
+///
+/// ```rust
+/// use fmtna::naming_conventions::{detect_ncs, NamingConvention};
+///
+/// assert_eq!(
+///     detect_ncs("my_file", false, false, false),
+///     vec![NamingConvention::SnakeCase, NamingConvention::Lower]
+/// );
+/// ```
+pub fn detect_ncs(
+    name: &str,
+    keep_dots: bool,
+    keep_special_chars: bool,
+    keep_unicode: bool,
+) -> Vec<NamingConvention> {
+    NamingConvention::value_variants()
+        .iter()
+        .filter(|nc| apply_nc(nc, name, keep_dots, keep_special_chars, keep_unicode) == name)
+        .cloned()
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+/// A regex matching dates to keep as is in names, see
+/// [ConventionArgs::preserve_dates](crate::cli::ConventionArgs::preserve_dates).
+pub struct DatePattern(pub Regex);
+
+impl DatePattern {
+    /// Returns the pattern of ISO dates, e.g. "2024-01-15".
+    pub fn iso() -> Self {
+        Self(ISO_DATE_RE.clone())
     }
-    let _ = keep_dots;
+}
 
-    let mut new_s = String::from("");
-    let mut slice = s;
-    // in case we are dealing with a dotfile
-    if s.starts_with('.') {
-        new_s.push('.');
-        slice = &slice[1..];
+impl FromStr for DatePattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Regex::new(s)
+            .map(Self)
+            .map_err(|err| format!("Invalid date pattern. {}", err))
     }
+}
 
-    let unidecoded: String;
-    if !keep_unicode {
-        unidecoded = unidecode(slice);
-        slice = unidecoded.as_ref();
+impl PartialEq for DatePattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
     }
+}
 
-    for c in slice
-        .chars()
-        .filter(|c| keep_special_chars || SEPARATORS.contains(c) || !is_special(c))
-    {
-        new_s.push(c);
+impl Eq for DatePattern {}
+
+impl Serialize for DatePattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str())
     }
+}
 
-    new_s.to_lowercase()
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+/// The options deciding how names are formatted, once the CLI arguments
+/// and the configuration values are aggregated.
+pub struct FormatOptions {
+    /// Same as [ConventionArgs::naming_convention](crate::cli::ConventionArgs::naming_convention)
+    pub naming_convention: NamingConvention,
+
+    /// Same as [ConventionArgs::keep_dots](crate::cli::ConventionArgs::keep_dots)
+    pub keep_dots: bool,
+
+    /// Same as [ConventionArgs::keep_special_chars](crate::cli::ConventionArgs::keep_special_chars)
+    pub keep_special_chars: bool,
+
+    /// Same as [ConventionArgs::keep_unicode](crate::cli::ConventionArgs::keep_unicode)
+    pub keep_unicode: bool,
+
+    /// Same as [ConventionArgs::squeeze_whitespace](crate::cli::ConventionArgs::squeeze_whitespace)
+    pub squeeze_whitespace: bool,
+
+    /// Same as [ConventionArgs::acronym_aware](crate::cli::ConventionArgs::acronym_aware)
+    pub acronym_aware: bool,
+
+    /// Same as [ConventionArgs::unicode_words](crate::cli::ConventionArgs::unicode_words)
+    pub unicode_words: bool,
+
+    /// Same as [ConventionArgs::trim_separators](crate::cli::ConventionArgs::trim_separators)
+    pub trim_separators: bool,
+
+    /// Same as [ConventionArgs::format_dotfiles](crate::cli::ConventionArgs::format_dotfiles)
+    pub format_dotfiles: bool,
+
+    /// Same as [ConventionArgs::normalize_dots](crate::cli::ConventionArgs::normalize_dots)
+    pub normalize_dots: bool,
+
+    /// Same as [ConventionArgs::protect](crate::cli::ConventionArgs::protect)
+    pub protect: Vec<String>,
+
+    /// The patterns of the dates to keep as is, empty unless
+    /// [ConventionArgs::preserve_dates](crate::cli::ConventionArgs::preserve_dates)
+    /// is used.
+    pub date_patterns: Vec<DatePattern>,
+
+    /// Same as [ConventionArgs::case_only](crate::cli::ConventionArgs::case_only)
+    pub case_only: bool,
+
+    /// Same as [ConventionArgs::keep_case](crate::cli::ConventionArgs::keep_case)
+    pub keep_case: bool,
+
+    /// Same as [Cfg::acronyms](crate::cfg::Cfg::acronyms)
+    pub acronyms: Vec<String>,
 }
 
-fn upper(s: &str, keep_dots: bool, keep_special_chars: bool, keep_unicode: bool) -> String {
-    if s.is_empty() {
-        return String::from("");
+impl FormatOptions {
+    /// Aggregates `args` and `cfg` into format options, see [Cfg] for the
+    /// precedence of one over the other.
+    ///
+    /// # Parameters
+    ///
+    /// - `args`: The CLI arguments.
+    /// - `cfg`: The configuration values.
+    pub fn new(args: ConventionArgs, cfg: &Cfg) -> Self {
+        Self {
+            naming_convention: cfg.resolve_naming_convention(args.naming_convention),
+            keep_dots: cfg.resolve_keep_dots(args.keep_dots),
+            keep_special_chars: cfg.resolve_keep_special_chars(args.keep_special_chars),
+            keep_unicode: cfg.resolve_keep_unicode(args.keep_unicode),
+            squeeze_whitespace: args.squeeze_whitespace,
+            acronym_aware: args.acronym_aware,
+            unicode_words: args.unicode_words,
+            trim_separators: args.trim_separators,
+            format_dotfiles: args.format_dotfiles,
+            normalize_dots: args.normalize_dots,
+            protect: args.protect,
+            date_patterns: if cfg.resolve_preserve_dates(args.preserve_dates)
+                || args.date_pattern.is_some()
+            {
+                let mut date_patterns = vec![DatePattern::iso()];
+                date_patterns.extend(args.date_pattern);
+                date_patterns
+            } else {
+                vec![]
+            },
+            case_only: args.case_only,
+            keep_case: args.keep_case,
+            acronyms: cfg.acronyms.clone(),
+        }
     }
-    let _ = keep_dots;
 
-    let mut new_s = String::from("");
-    let mut slice = s;
-    // in case we are dealing with a dotfile
-    if s.starts_with('.') {
-        new_s.push('.');
-        slice = &slice[1..];
+    /// Rewrites `name` as a whole according to the options.
+    ///
+    /// # Parameters
+    ///
+    /// - `name`
+    pub fn format_name(&self, name: &str) -> String {
+        self.format_name_traced(name, &mut Trace(None))
     }
 
-    let unidecoded: String;
-    if !keep_unicode {
-        unidecoded = unidecode(slice);
-        slice = unidecoded.as_ref();
+    /// Like [format_name](FormatOptions::format_name), recording the
+    /// intermediate states of `name` into `trace`.
+    fn format_name_traced(&self, name: &str, trace: &mut Trace) -> String {
+        let trimmed = trim_whitespace(name);
+        if trimmed != name {
+            trace.record("trimmed", || trimmed.to_string());
+        }
+        if self.case_only {
+            let folded = self.fold_case(trimmed);
+            trace.record("case folded", || folded.clone());
+            return folded;
+        }
+
+        let mut name = trimmed.to_string();
+        if self.squeeze_whitespace {
+            name = squeeze_whitespace(&name);
+            trace.record("whitespace squeezed", || name.clone());
+        }
+
+        let protected = self.protected_in(&name);
+        let parts = split_protected(&name, &protected);
+        if parts.iter().any(|(_, protected)| *protected) {
+            trace.record("protected", || {
+                parts
+                    .iter()
+                    .map(|(part, protected)| {
+                        if *protected {
+                            format!("[{}]", part)
+                        } else {
+                            part.to_string()
+                        }
+                    })
+                    .collect()
+            });
+        }
+        let formatted = parts
+            .into_iter()
+            .map(|(part, protected)| {
+                if protected {
+                    part.to_string()
+                } else {
+                    self.format_words(part, trace)
+                }
+            })
+            .collect::<String>();
+        if self.trim_separators {
+            let trimmed = trim_separators(&formatted, self.keep_dots);
+            trace.record("separators trimmed", || trimmed.clone());
+            trimmed
+        } else {
+            formatted
+        }
     }
 
-    for c in slice
-        .chars()
-        .filter(|c| keep_special_chars || SEPARATORS.contains(c) || !is_special(c))
-    {
-        new_s.push(c);
+    /// Returns the substrings to keep as is in `name`, i.e.
+    /// [protect](FormatOptions::protect) and the dates found in it.
+    fn protected_in(&self, name: &str) -> Cow<'_, [String]> {
+        if self.date_patterns.is_empty() {
+            return Cow::Borrowed(&self.protect);
+        }
+
+        let mut protected = self.protect.clone();
+        for pattern in &self.date_patterns {
+            protected.extend(pattern.0.find_iter(name).map(|m| m.as_str().to_string()));
+        }
+        Cow::Owned(protected)
     }
 
-    new_s.to_uppercase()
-}
+    /// Returns the intermediate states of `name` while being formatted, as
+    /// `(step, state)`, the last one being the formatted name.
+    ///
+    /// # Parameters
+    ///
+    /// - `name`
+    /// - `as_filename`: Whether to format `name` like
+    ///   [format_filename](FormatOptions::format_filename) rather than
+    ///   [format_name](FormatOptions::format_name).
+    pub fn explain(&self, name: &str, as_filename: bool) -> Vec<(&'static str, String)> {
+        let mut trace = Trace(Some(vec![("original", name.to_string())]));
+        let formatted = if as_filename {
+            let normalized = self.normalize_filename(name);
+            if normalized != name {
+                trace.record("normalized", || normalized.to_string());
+            }
+            let (stem, ext) = self.split_filename(&normalized);
+            if let Some(ext) = ext {
+                trace.record("stem", || stem.to_string());
+                trace.record("extension", || ext.to_string());
+            }
+            join_filename(&self.format_name_traced(stem, &mut trace), ext)
+        } else {
+            self.format_name_traced(name, &mut trace)
+        };
 
-fn is_special(c: &char) -> bool {
-    !c.is_alphanumeric()
-}
+        let mut steps = trace.0.unwrap_or_default();
+        steps.push(("final", formatted));
+        steps
+    }
 
-fn capitalize(s: &str) -> String {
-    if s.is_empty() {
-        return String::from("");
+    /// Only changes the case of `name`, see
+    /// [case_only](FormatOptions::case_only).
+    fn fold_case(&self, name: &str) -> String {
+        let name = if self.keep_unicode {
+            name.to_string()
+        } else {
+            unidecode(name)
+        };
+
+        match self.naming_convention {
+            NamingConvention::Upper | NamingConvention::ScreamingSnakeCase => name.to_uppercase(),
+            _ => name.to_lowercase(),
+        }
     }
 
-    let mut capitalized = s.to_string();
+    /// Rewrites `name`, free of protected substrings, according to the naming
+    /// convention and the options deciding how words are split.
+    fn format_words(&self, name: &str, trace: &mut Trace) -> String {
+        let mut name = name.to_string();
+        let splits_words = !matches!(
+            self.naming_convention,
+            NamingConvention::Lower | NamingConvention::Upper
+        );
+        if self.acronym_aware && splits_words {
+            name = split_acronyms(&name, &self.acronyms);
+            trace.record("acronyms split", || name.clone());
+        }
+        if self.unicode_words && self.keep_unicode && splits_words {
+            name = split_unicode_words(&name);
+            trace.record("Unicode words split", || name.clone());
+        }
+        // Done within apply_nc, only shown here.
+        let transliterated = || {
+            if self.keep_unicode {
+                name.clone()
+            } else {
+                unidecode(&name)
+            }
+        };
+        if !self.keep_unicode {
+            trace.record("transliterated", transliterated);
+        }
+        trace.record("words", || {
+            let words: Vec<_> = transliterated()
+                .split(|c| SEPARATORS.contains(&c) && !(self.keep_dots && c == '.'))
+                .filter(|word| !word.is_empty())
+                .map(str::to_string)
+                .collect();
+            format!("[{}]", words.join(", "))
+        });
+
+        let formatted = match self.naming_convention {
+            NamingConvention::SnakeCase | NamingConvention::KebabCase if self.keep_case => {
+                separate_words(
+                    &name,
+                    self.naming_convention.word_separator(),
+                    self.keep_dots,
+                    self.keep_special_chars,
+                    self.keep_unicode,
+                )
+            }
+            _ => apply_nc(
+                &self.naming_convention,
+                &name,
+                self.keep_dots,
+                self.keep_special_chars,
+                self.keep_unicode,
+            ),
+        };
+        trace.record("convention applied", || formatted.clone());
+        formatted
+    }
+
+    /// Rewrites `filename` according to the options, leaving its extension
+    /// (if any) untouched.
+    ///
+    /// # Parameters
+    ///
+    /// - `filename`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fmtna::naming_conventions::{FormatOptions, NamingConvention};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let opts = FormatOptions {
+    ///     naming_convention: NamingConvention::KebabCase,
+    ///     keep_dots: false,
+    ///     keep_special_chars: false,
+    ///     keep_unicode: false,
+    ///     squeeze_whitespace: false,
+    ///     acronym_aware: false,
+    ///     unicode_words: false,
+    ///     trim_separators: false,
+    ///     format_dotfiles: false,
+    ///     normalize_dots: false,
+    ///     protect: vec![],
+    ///     date_patterns: vec![],
+    ///     case_only: false,
+    ///     keep_case: false,
+    ///     acronyms: vec![],
+    /// };
+    /// assert_eq!(opts.format_filename("My Report.PDF"), "my-report.PDF");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn format_filename(&self, filename: &str) -> String {
+        let filename = self.normalize_filename(filename);
+        let (stem, ext) = self.split_filename(&filename);
+        join_filename(&self.format_name(stem), ext)
+    }
+
+    /// Prepares `filename` for being split and formatted, i.e. trims its
+    /// whitespace and, with [normalize_dots](FormatOptions::normalize_dots),
+    /// collapses its runs of dots.
+    ///
+    /// # Parameters
+    ///
+    /// - `filename`
+    pub fn normalize_filename<'a>(&self, filename: &'a str) -> Cow<'a, str> {
+        let filename = trim_whitespace(filename);
+        if self.normalize_dots {
+            normalize_dots(filename)
+        } else {
+            Cow::Borrowed(filename)
+        }
+    }
+
+    /// Like [split_filename], except that with
+    /// [format_dotfiles](FormatOptions::format_dotfiles), the leading dot of
+    /// a dotfile is dropped and the rest is the stem, without extension.
+    ///
+    /// # Parameters
+    ///
+    /// - `filename`
+    pub fn split_filename<'a>(&self, filename: &'a str) -> (&'a str, Option<&'a str>) {
+        match filename.strip_prefix('.') {
+            Some(rest) if self.format_dotfiles && !rest.is_empty() => (rest, None),
+            _ => split_filename(filename),
+        }
+    }
+}
+
+/// The intermediate states of a name being formatted, as `(step, state)`,
+/// if recorded at all, see [FormatOptions::explain].
+struct Trace(Option<Vec<(&'static str, String)>>);
+
+impl Trace {
+    /// Records the state of the name after `step`, computing it only if
+    /// recording.
+    fn record(&mut self, step: &'static str, state: impl FnOnce() -> String) {
+        if let Some(steps) = &mut self.0 {
+            steps.push((step, state()));
+        }
+    }
+}
+
+/// Removes the whitespace at the beginning and at the end of `s`, unless `s`
+/// is made of whitespace only (in which case it is returned as is, rather than
+/// emptied).
+///
+/// Leading and trailing whitespace in filenames is almost always accidental,
+/// so it is removed before formatting, whatever the naming convention.
+///
+/// # Parameters
+///
+/// - `s`
+///
+/// # Examples
+///
+/// ```rust
+/// use fmtna::naming_conventions::trim_whitespace;
+///
+/// assert_eq!(trim_whitespace(" \treport "), "report");
+/// assert_eq!(trim_whitespace("  "), "  ");
+/// ```
+pub fn trim_whitespace(s: &str) -> &str {
+    match s.trim() {
+        "" => s,
+        trimmed => trimmed,
+    }
+}
+
+/// Collapses the runs of dots of `filename`, i.e. removes the empty (or
+/// whitespace only) segments between its dots, keeping the leading dot of
+/// dotfiles.
+///
+/// Names made of dots only (like "..") are returned as is.
+///
+/// # Parameters
+///
+/// - `filename`
+///
+/// # Examples
+///
+/// ```rust
+/// use fmtna::naming_conventions::normalize_dots;
+///
+/// assert_eq!(normalize_dots("file..txt"), "file.txt");
+/// assert_eq!(normalize_dots("file.  .txt"), "file.txt");
+/// assert_eq!(normalize_dots(".bashrc"), ".bashrc");
+/// ```
+pub fn normalize_dots(filename: &str) -> Cow<'_, str> {
+    let (prefix, rest) = match filename.strip_prefix('.') {
+        Some(rest) => (".", rest),
+        None => ("", filename),
+    };
+    let segments: Vec<&str> = rest
+        .split('.')
+        .filter(|segment| !segment.trim().is_empty())
+        .collect();
+    if segments.is_empty() {
+        return Cow::Borrowed(filename);
+    }
+
+    let normalized = format!("{}{}", prefix, segments.join("."));
+    if normalized == filename {
+        Cow::Borrowed(filename)
+    } else {
+        Cow::Owned(normalized)
+    }
+}
+
+/// Splits `filename` into its stem and its extension (without the dot),
+/// the same way [`Path::file_stem`](std::path::Path::file_stem) and
+/// [`Path::extension`](std::path::Path::extension) do.
+///
+/// # Parameters
+///
+/// - `filename`
+///
+/// # Examples
+///
+/// ```rust
+/// use fmtna::naming_conventions::split_filename;
+///
+/// assert_eq!(split_filename("archive.tar.gz"), ("archive.tar", Some("gz")));
+/// assert_eq!(split_filename(".bashrc"), (".bashrc", None));
+/// assert_eq!(split_filename("Makefile"), ("Makefile", None));
+/// ```
+pub fn split_filename(filename: &str) -> (&str, Option<&str>) {
+    match filename.rfind('.') {
+        // no dot, or a dotfile without any other dot
+        None | Some(0) => (filename, None),
+        Some(i) => (&filename[..i], Some(&filename[i + 1..])),
+    }
+}
+
+/// Joins `stem` and `ext` back into a filename, the reverse of [`split_filename`].
+///
+/// # Parameters
+///
+/// - `stem`
+/// - `ext`: The extension, without the dot.
+pub fn join_filename(stem: &str, ext: Option<&str>) -> String {
+    let mut filename = stem.to_string();
+    if let Some(ext) = ext {
+        filename.push('.');
+        filename.push_str(ext);
+    }
+
+    filename
+}
+
+/// Why [target_path_for] failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapError {
+    /// The path has no filename, e.g. it ends with "..".
+    NoFilename,
+    /// The filename is not valid Unicode.
+    InvalidUnicode,
+    /// The path has no parent directory, e.g. it is "/".
+    NoParentDirectory,
+}
+
+impl Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::NoFilename => write!(f, "Path has no filename."),
+            MapError::InvalidUnicode => write!(f, "Filename contains invalid unicode characters."),
+            MapError::NoParentDirectory => write!(f, "Path has no parent directory."),
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+/// Returns the path `path` would be renamed into when formatting its filename
+/// with `opts`, or `None` if it doesn't need renaming.
+///
+/// Nothing is read from the filesystem, so whether the new path already exists
+/// is not checked. The default command applies options of its own on top
+/// (e.g. --max-length), which this function doesn't.
+/// Since paths are case-insensitive on Windows, a filename whose case only
+/// would change doesn't need renaming there.
+///
+/// # Parameters
+///
+/// - `path`
+/// - `opts`
+///
+/// # Errors
+///
+/// See [MapError].
+///
+/// # Examples
+///
+/// ```rust
+/// use fmtna::naming_conventions::{target_path_for, FormatOptions, NamingConvention};
+/// use std::path::{Path, PathBuf};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let opts = FormatOptions {
+///     naming_convention: NamingConvention::SnakeCase,
+///     keep_dots: false,
+///     keep_special_chars: false,
+///     keep_unicode: false,
+///     squeeze_whitespace: false,
+///     acronym_aware: false,
+///     unicode_words: false,
+///     trim_separators: false,
+///     format_dotfiles: false,
+///     normalize_dots: false,
+///     protect: vec![],
+///     date_patterns: vec![],
+///     case_only: false,
+///     keep_case: false,
+///     acronyms: vec![],
+/// };
+/// assert_eq!(
+///     target_path_for(Path::new("/docs/My Report.pdf"), &opts)?,
+///     Some(PathBuf::from("/docs/my_report.pdf"))
+/// );
+/// assert_eq!(target_path_for(Path::new("/docs/my_report.pdf"), &opts)?, None);
+/// # Ok(())
+/// # }
+/// ```
+pub fn target_path_for(path: &Path, opts: &FormatOptions) -> Result<Option<PathBuf>, MapError> {
+    let filename = path
+        .file_name()
+        .ok_or(MapError::NoFilename)?
+        .to_str()
+        .ok_or(MapError::InvalidUnicode)?;
+    let parent = path.parent().ok_or(MapError::NoParentDirectory)?;
+
+    let new_filename = opts.format_filename(filename);
+    if new_filename == filename
+        || (cfg!(windows) && new_filename.to_lowercase() == filename.to_lowercase())
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(parent.join(new_filename)))
+}
+
+/// Returns the characters of `s` that can't be transliterated to ASCII,
+/// i.e. that would be dropped when formatting without keeping Unicode.
+///
+/// Combining diacritical marks (U+0300 to U+036F) are not reported, since
+/// dropping them is the expected transliteration (e.g. "e\u{301}" into "e").
+///
+/// # Parameters
+///
+/// - `s`
+///
+/// # Examples
+///
+/// ```rust
+/// use fmtna::naming_conventions::untransliterable_chars;
+///
+/// assert_eq!(untransliterable_chars("Café ✓"), vec!['✓']);
+/// assert!(untransliterable_chars("Crème brûlée").is_empty());
+/// ```
+pub fn untransliterable_chars(s: &str) -> Vec<char> {
+    s.chars()
+        .filter(|c| !c.is_ascii() && !('\u{300}'..='\u{36f}').contains(c))
+        .filter(|c| {
+            let transliterated = unidecode(&c.to_string());
+            transliterated.is_empty() || transliterated.contains("[?]")
+        })
+        .collect()
+}
+
+/// Truncates `name` to at most `max_length` characters, removing the
+/// separators left dangling at the end.
+///
+/// # Parameters
+///
+/// - `name`
+/// - `max_length`: The maximum number of characters of the returned name.
+///
+/// # Examples
+///
+/// ```rust
+/// use fmtna::naming_conventions::truncate_name;
+///
+/// assert_eq!(truncate_name("a_rather_long_name", 9), "a_rather");
+/// assert_eq!(truncate_name("short", 9), "short");
+/// ```
+pub fn truncate_name(name: &str, max_length: usize) -> String {
+    if name.chars().count() <= max_length {
+        return name.to_string();
+    }
+
+    name.chars()
+        .take(max_length)
+        .collect::<String>()
+        .trim_end_matches(|c| SEPARATORS.contains(&c))
+        .to_string()
+}
+
+/// Truncates `name` to at most `max_length` characters like [`truncate_name`],
+/// but makes room at the end for a short hash of `original` (joined with `sep`)
+/// when truncation occurs.
+///
+/// Names sharing a long prefix thus remain distinct once truncated, as long as
+/// their originals are.
+/// The hash is stable across runs and platforms.
+///
+/// # Parameters
+///
+/// - `name`
+/// - `original`: What to compute the hash of, typically the name before formatting.
+/// - `max_length`: The maximum number of characters of the returned name.
+/// - `sep`: The separator put between the truncated name and the hash.
+pub fn truncate_name_with_hash(name: &str, original: &str, max_length: usize, sep: &str) -> String {
+    if name.chars().count() <= max_length {
+        return name.to_string();
+    }
+
+    let hash = short_hash(original);
+    let suffix_len = sep.chars().count() + hash.len();
+    if max_length <= suffix_len {
+        return hash.chars().take(max_length).collect();
+    }
+
+    let prefix = truncate_name(name, max_length - suffix_len);
+    if prefix.is_empty() {
+        hash
+    } else {
+        format!("{}{}{}", prefix, sep, hash)
+    }
+}
+
+/// Returns the first 6 hexadecimal digits of the 64-bit FNV-1a hash of `s`.
+fn short_hash(s: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in s.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    format!("{:016x}", hash)[..6].to_string()
+}
+
+fn camel_case(s: &str, keep_dots: bool, keep_special_chars: bool, keep_unicode: bool) -> String {
+    if s.is_empty() {
+        return String::from("");
+    }
+
+    let mut new_s = String::from("");
+    let mut slice = s;
+    // in case we are dealing with a dotfile
+    if s.starts_with('.') {
+        new_s.push('.');
+        slice = &slice[1..];
+    }
+
+    let unidecoded: String;
+    if !keep_unicode {
+        unidecoded = unidecode(slice);
+        slice = unidecoded.as_ref();
+    }
+
+    // Indexing chars rather than bytes, as Unicode may be kept.
+    let chars: Vec<char> = slice.chars().collect();
+    let mut should_upper = false;
+    for (i, &c) in chars.iter().enumerate() {
+        if SEPARATORS.contains(&c) && !(keep_dots && c == '.') && i > 0 && i < chars.len() - 1 {
+            should_upper = true;
+        } else if is_special(&c) {
+            if keep_special_chars {
+                new_s.push(c);
+            } else {
+                continue;
+            }
+        } else if c.is_uppercase() && i > 0 && chars[i - 1].is_lowercase() {
+            new_s.push(c);
+        } else if should_upper {
+            // Some chars have a multi-char case mapping (e.g. 'ß' into "SS").
+            new_s.extend(c.to_uppercase());
+            should_upper = false;
+        } else {
+            new_s.extend(c.to_lowercase());
+        }
+    }
+
+    new_s
+}
+
+fn kebab_case(s: &str, keep_dots: bool, keep_special_chars: bool, keep_unicode: bool) -> String {
+    if s.is_empty() {
+        return String::from("");
+    }
+
+    let mut new_s = String::from("");
+    let mut slice = s;
+    // in case we are dealing with a dotfile
+    if s.starts_with('.') {
+        new_s.push('.');
+        slice = &slice[1..];
+    }
+
+    let unidecoded: String;
+    if !keep_unicode {
+        unidecoded = unidecode(slice);
+        slice = unidecoded.as_ref();
+    }
+
+    for (i, c) in slice.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 && slice.chars().nth(i - 1).unwrap().is_lowercase() {
+                push_separator(&mut new_s, '-');
+            }
+            new_s.push(c.to_lowercase().next().unwrap());
+        } else if SEPARATORS.contains(&c) && !(keep_dots && c == '.') {
+            push_separator(&mut new_s, '-');
+        } else if !keep_special_chars && is_special(&c) {
+            continue;
+        } else {
+            new_s.push(c.to_lowercase().next().unwrap());
+        }
+    }
+
+    new_s
+}
+
+/// Like [kebab_case], with the first letter of each word uppercased.
+fn train_case(s: &str, keep_dots: bool, keep_special_chars: bool, keep_unicode: bool) -> String {
+    let kebab = kebab_case(s, keep_dots, keep_special_chars, keep_unicode);
+    let (mut new_s, slice) = match kebab.strip_prefix('.') {
+        Some(rest) => (String::from("."), rest),
+        None => (String::from(""), kebab.as_str()),
+    };
+
+    let mut word_start = true;
+    for c in slice.chars() {
+        if word_start {
+            // Some chars have a multi-char case mapping (e.g. 'ß' into "SS").
+            new_s.extend(c.to_uppercase());
+        } else {
+            new_s.push(c);
+        }
+        word_start = c == '-';
+    }
+
+    new_s
+}
+
+fn snake_case(s: &str, keep_dots: bool, keep_special_chars: bool, keep_unicode: bool) -> String {
+    if s.is_empty() {
+        return String::from("");
+    }
+
+    let mut new_s = String::from("");
+    let mut slice = s;
+    // in case we are dealing with a dotfile
+    if s.starts_with('.') {
+        new_s.push('.');
+        slice = &slice[1..];
+    }
+
+    let unidecoded: String;
+    if !keep_unicode {
+        unidecoded = unidecode(slice);
+        slice = unidecoded.as_ref();
+    }
+
+    for (i, c) in slice.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 && slice.chars().nth(i - 1).unwrap().is_lowercase() {
+                push_separator(&mut new_s, '_');
+            }
+            new_s.push(c.to_lowercase().next().unwrap());
+        } else if SEPARATORS.contains(&c) && !(keep_dots && c == '.') {
+            push_separator(&mut new_s, '_');
+        } else if !keep_special_chars && is_special(&c) {
+            continue;
+        } else {
+            new_s.push(c.to_lowercase().next().unwrap());
+        }
+    }
+
+    new_s
+}
+
+/// Like [snake_case] and [kebab_case], with `sep` as separator, but keeping
+/// the case of letters, see
+/// [ConventionArgs::keep_case](crate::cli::ConventionArgs::keep_case).
+fn separate_words(
+    s: &str,
+    sep: &str,
+    keep_dots: bool,
+    keep_special_chars: bool,
+    keep_unicode: bool,
+) -> String {
+    if s.is_empty() {
+        return String::from("");
+    }
+    let sep = sep.chars().next().unwrap_or('_');
+
+    let mut new_s = String::from("");
+    let mut slice = s;
+    // in case we are dealing with a dotfile
+    if s.starts_with('.') {
+        new_s.push('.');
+        slice = &slice[1..];
+    }
+
+    let unidecoded: String;
+    if !keep_unicode {
+        unidecoded = unidecode(slice);
+        slice = unidecoded.as_ref();
+    }
+
+    let chars: Vec<char> = slice.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 && chars[i - 1].is_lowercase() {
+                push_separator(&mut new_s, sep);
+            }
+            new_s.push(c);
+        } else if SEPARATORS.contains(&c) && !(keep_dots && c == '.') {
+            push_separator(&mut new_s, sep);
+        } else if !keep_special_chars && is_special(&c) {
+            continue;
+        } else {
+            new_s.push(c);
+        }
+    }
+
+    new_s
+}
+
+fn pascal_case(s: &str, keep_dots: bool, keep_special_chars: bool, keep_unicode: bool) -> String {
+    capitalize(&camel_case(s, keep_dots, keep_special_chars, keep_unicode))
+}
+
+/// Like [snake_case], with the first letter of each word uppercased and
+/// words separated by spaces.
+fn title_case(s: &str, keep_dots: bool, keep_special_chars: bool, keep_unicode: bool) -> String {
+    let snake = snake_case(s, keep_dots, keep_special_chars, keep_unicode);
+    let (prefix, slice) = match snake.strip_prefix('.') {
+        Some(rest) => (".", rest),
+        None => ("", snake.as_str()),
+    };
+
+    let words: Vec<_> = slice
+        .split('_')
+        .filter(|word| !word.is_empty())
+        .map(capitalize)
+        .collect();
+
+    format!("{}{}", prefix, words.join(" "))
+}
+
+/// Like [snake_case], with every character uppercased.
+fn screaming_snake_case(
+    s: &str,
+    keep_dots: bool,
+    keep_special_chars: bool,
+    keep_unicode: bool,
+) -> String {
+    snake_case(s, keep_dots, keep_special_chars, keep_unicode).to_uppercase()
+}
+
+fn lower(s: &str, keep_dots: bool, keep_special_chars: bool, keep_unicode: bool) -> String {
+    if s.is_empty() {
+        return String::from("");
+    }
+    let _ = keep_dots;
+
+    let mut new_s = String::from("");
+    let mut slice = s;
+    // in case we are dealing with a dotfile
+    if s.starts_with('.') {
+        new_s.push('.');
+        slice = &slice[1..];
+    }
+
+    let unidecoded: String;
+    if !keep_unicode {
+        unidecoded = unidecode(slice);
+        slice = unidecoded.as_ref();
+    }
+
+    for c in slice
+        .chars()
+        .filter(|c| keep_special_chars || SEPARATORS.contains(c) || !is_special(c))
+    {
+        new_s.push(c);
+    }
+
+    new_s.to_lowercase()
+}
+
+fn upper(s: &str, keep_dots: bool, keep_special_chars: bool, keep_unicode: bool) -> String {
+    if s.is_empty() {
+        return String::from("");
+    }
+    let _ = keep_dots;
+
+    let mut new_s = String::from("");
+    let mut slice = s;
+    // in case we are dealing with a dotfile
+    if s.starts_with('.') {
+        new_s.push('.');
+        slice = &slice[1..];
+    }
+
+    let unidecoded: String;
+    if !keep_unicode {
+        unidecoded = unidecode(slice);
+        slice = unidecoded.as_ref();
+    }
+
+    for c in slice
+        .chars()
+        .filter(|c| keep_special_chars || SEPARATORS.contains(c) || !is_special(c))
+    {
+        new_s.push(c);
+    }
+
+    new_s.to_uppercase()
+}
+
+/// Pushes `sep` into `s`, unless `s` already ends with it, so that words are
+/// never separated by more than one separator.
+fn push_separator(s: &mut String, sep: char) {
+    if !s.ends_with(sep) {
+        s.push(sep);
+    }
+}
+
+fn is_special(c: &char) -> bool {
+    !c.is_alphanumeric()
+}
+
+fn capitalize(s: &str) -> String {
+    if s.is_empty() {
+        return String::from("");
+    }
+
+    let mut capitalized = s.to_string();
     capitalized = capitalized.remove(0).to_uppercase().to_string() + &capitalized;
     capitalized
 }
@@ -294,115 +1385,546 @@ fn capitalize(s: &str) -> String {
 mod test {
     use super::*;
 
-    #[derive(Debug)]
-    struct TestCase<'a> {
-        s: &'a str,
-        keep_dots: bool,
-        keep_special_chars: bool,
-        keep_unicode: bool,
-        expected_output: &'a str,
+    #[derive(Debug)]
+    struct TestCase<'a> {
+        s: &'a str,
+        keep_dots: bool,
+        keep_special_chars: bool,
+        keep_unicode: bool,
+        expected_output: &'a str,
+    }
+
+    #[test]
+    fn test_train_case() {
+        let test_cases = vec![
+            TestCase {
+                s: "",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "",
+            },
+            TestCase {
+                s: "a",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "A",
+            },
+            TestCase {
+                s: "A",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "A",
+            },
+            TestCase {
+                s: "from_snake_case",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "From-Snake-Case",
+            },
+            TestCase {
+                s: "FROM_UPPERCASE",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "From-Uppercase",
+            },
+            TestCase {
+                s: "fromCamelCase",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "From-Camel-Case",
+            },
+            TestCase {
+                s: "FromPascalCase",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "From-Pascal-Case",
+            },
+            TestCase {
+                s: "from-kebab-case",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "From-Kebab-Case",
+            },
+            TestCase {
+                s: "From-Train-Case",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "From-Train-Case",
+            },
+            TestCase {
+                s: "from  -_ many__separators",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "From-Many-Separators",
+            },
+            TestCase {
+                s: "my.file name",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "My-File-Name",
+            },
+            TestCase {
+                s: "my.file name",
+                keep_dots: true,
+                keep_special_chars: true,
+                keep_unicode: false,
+                expected_output: "My.file-Name",
+            },
+            TestCase {
+                s: "l'été",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "Lete",
+            },
+            TestCase {
+                s: "l'été",
+                keep_dots: false,
+                keep_special_chars: true,
+                keep_unicode: false,
+                expected_output: "L'ete",
+            },
+            TestCase {
+                s: "l'été",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: true,
+                expected_output: "Lété",
+            },
+            TestCase {
+                s: ".dotfile",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: ".Dotfile",
+            },
+            TestCase {
+                s: "fooBar_Baz",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "Foo-Bar-Baz",
+            },
+        ];
+
+        for TestCase {
+            s,
+            keep_dots,
+            keep_special_chars,
+            keep_unicode,
+            expected_output,
+        } in test_cases
+        {
+            assert_eq!(
+                train_case(s, keep_dots, keep_special_chars, keep_unicode),
+                expected_output
+            );
+        }
     }
 
     #[test]
     fn test_snake_case() {
         let test_cases = vec![
             TestCase {
-                s: "",
+                s: "",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "",
+            },
+            TestCase {
+                s: "a",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "a",
+            },
+            TestCase {
+                s: "A",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "a",
+            },
+            TestCase {
+                s: "from_snake_case",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "from_snake_case",
+            },
+            TestCase {
+                s: "FROM_UPPERCASE",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "from_uppercase",
+            },
+            TestCase {
+                s: "fromlowercase",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "fromlowercase",
+            },
+            TestCase {
+                s: "fromCamelCase",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "from_camel_case",
+            },
+            TestCase {
+                s: "FromPascalCase",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "from_pascal_case",
+            },
+            TestCase {
+                s: "from-kebab-case",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "from_kebab_case",
+            },
+            TestCase {
+                s: "fRo_m`WHAT.ev-eR!",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "f_ro_mwhat_ev_e_r",
+            },
+            TestCase {
+                s: "fRo_m`WHAT.ev-eR!",
+                keep_dots: true,
+                keep_special_chars: true,
+                keep_unicode: false,
+                expected_output: "f_ro_m`what.ev_e_r!",
+            },
+            TestCase {
+                s: "é çà devrait être 'asciifié'",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "e_ca_devrait_etre_asciifie",
+            },
+            TestCase {
+                s: "é çà devrait être 'asciifié' mais en gardant les guillemets",
+                keep_dots: false,
+                keep_special_chars: true,
+                keep_unicode: false,
+                expected_output: "e_ca_devrait_etre_'asciifie'_mais_en_gardant_les_guillemets",
+            },
+            TestCase {
+                s: ".dotfile",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: ".dotfile",
+            },
+            TestCase {
+                s: "My_fileName",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "my_file_name",
+            },
+            TestCase {
+                s: "a_B",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "a_b",
+            },
+            TestCase {
+                s: "a-B",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "a_b",
+            },
+            TestCase {
+                s: "fooBar_Baz",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "foo_bar_baz",
+            },
+        ];
+
+        for TestCase {
+            s,
+            keep_dots,
+            keep_special_chars,
+            keep_unicode,
+            expected_output,
+        } in test_cases
+        {
+            assert_eq!(
+                snake_case(s, keep_dots, keep_special_chars, keep_unicode),
+                expected_output
+            );
+        }
+    }
+
+    #[test]
+    fn test_screaming_snake_case() {
+        let test_cases = vec![
+            TestCase {
+                s: "",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "",
+            },
+            TestCase {
+                s: "a",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "A",
+            },
+            TestCase {
+                s: "A",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "A",
+            },
+            TestCase {
+                s: "from_snake_case",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "FROM_SNAKE_CASE",
+            },
+            TestCase {
+                s: "FROM_UPPERCASE",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "FROM_UPPERCASE",
+            },
+            TestCase {
+                s: "fromlowercase",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "FROMLOWERCASE",
+            },
+            TestCase {
+                s: "fromCamelCase",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "FROM_CAMEL_CASE",
+            },
+            TestCase {
+                s: "FromPascalCase",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "FROM_PASCAL_CASE",
+            },
+            TestCase {
+                s: "from-kebab-case",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "FROM_KEBAB_CASE",
+            },
+            TestCase {
+                s: "fRo_m`WHAT.ev-eR!",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "F_RO_MWHAT_EV_E_R",
+            },
+            TestCase {
+                s: "fRo_m`WHAT.ev-eR!",
+                keep_dots: true,
+                keep_special_chars: true,
+                keep_unicode: false,
+                expected_output: "F_RO_M`WHAT.EV_E_R!",
+            },
+            TestCase {
+                s: "é çà devrait être 'asciifié'",
                 keep_dots: false,
                 keep_special_chars: false,
                 keep_unicode: false,
-                expected_output: "",
+                expected_output: "E_CA_DEVRAIT_ETRE_ASCIIFIE",
             },
             TestCase {
-                s: "a",
+                s: "é çà devrait être 'asciifié' mais en gardant les guillemets",
+                keep_dots: false,
+                keep_special_chars: true,
+                keep_unicode: false,
+                expected_output: "E_CA_DEVRAIT_ETRE_'ASCIIFIE'_MAIS_EN_GARDANT_LES_GUILLEMETS",
+            },
+            TestCase {
+                s: ".dotfile",
                 keep_dots: false,
                 keep_special_chars: false,
                 keep_unicode: false,
-                expected_output: "a",
+                expected_output: ".DOTFILE",
             },
             TestCase {
-                s: "A",
+                s: "My_fileName",
                 keep_dots: false,
                 keep_special_chars: false,
                 keep_unicode: false,
-                expected_output: "a",
+                expected_output: "MY_FILE_NAME",
             },
             TestCase {
-                s: "from_snake_case",
+                s: "a_B",
                 keep_dots: false,
                 keep_special_chars: false,
                 keep_unicode: false,
-                expected_output: "from_snake_case",
+                expected_output: "A_B",
             },
             TestCase {
-                s: "FROM_UPPERCASE",
+                s: "a-B",
                 keep_dots: false,
                 keep_special_chars: false,
                 keep_unicode: false,
-                expected_output: "from_uppercase",
+                expected_output: "A_B",
             },
             TestCase {
-                s: "fromlowercase",
+                s: "fooBar_Baz",
                 keep_dots: false,
                 keep_special_chars: false,
                 keep_unicode: false,
-                expected_output: "fromlowercase",
+                expected_output: "FOO_BAR_BAZ",
+            },
+        ];
+
+        for TestCase {
+            s,
+            keep_dots,
+            keep_special_chars,
+            keep_unicode,
+            expected_output,
+        } in test_cases
+        {
+            assert_eq!(
+                screaming_snake_case(s, keep_dots, keep_special_chars, keep_unicode),
+                expected_output
+            );
+        }
+    }
+
+    #[test]
+    fn test_title_case() {
+        let test_cases = vec![
+            TestCase {
+                s: "",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "",
+            },
+            TestCase {
+                s: "a",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "A",
+            },
+            TestCase {
+                s: "from_snake_case",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "From Snake Case",
+            },
+            TestCase {
+                s: "FROM_UPPERCASE",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "From Uppercase",
             },
             TestCase {
                 s: "fromCamelCase",
                 keep_dots: false,
                 keep_special_chars: false,
                 keep_unicode: false,
-                expected_output: "from_camel_case",
+                expected_output: "From Camel Case",
             },
             TestCase {
                 s: "FromPascalCase",
                 keep_dots: false,
                 keep_special_chars: false,
                 keep_unicode: false,
-                expected_output: "from_pascal_case",
+                expected_output: "From Pascal Case",
             },
             TestCase {
                 s: "from-kebab-case",
                 keep_dots: false,
                 keep_special_chars: false,
                 keep_unicode: false,
-                expected_output: "from_kebab_case",
+                expected_output: "From Kebab Case",
             },
             TestCase {
-                s: "fRo_m`WHAT.ev-eR!",
+                s: "_from  many--separators_",
                 keep_dots: false,
                 keep_special_chars: false,
                 keep_unicode: false,
-                expected_output: "f_ro_mwhat_ev_e_r",
+                expected_output: "From Many Separators",
             },
             TestCase {
-                s: "fRo_m`WHAT.ev-eR!",
+                s: "my.file name",
                 keep_dots: true,
                 keep_special_chars: true,
                 keep_unicode: false,
-                expected_output: "f_ro_m`what.ev_e_r!",
+                expected_output: "My.file Name",
             },
             TestCase {
                 s: "é çà devrait être 'asciifié'",
                 keep_dots: false,
                 keep_special_chars: false,
                 keep_unicode: false,
-                expected_output: "e_ca_devrait_etre_asciifie",
+                expected_output: "E Ca Devrait Etre Asciifie",
             },
             TestCase {
-                s: "é çà devrait être 'asciifié' mais en gardant les guillemets",
+                s: "é çà devrait être 'asciifié'",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: true,
+                expected_output: "É Çà Devrait Être Asciifié",
+            },
+            TestCase {
+                s: "!?",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "",
+            },
+            TestCase {
+                s: "!?",
                 keep_dots: false,
                 keep_special_chars: true,
                 keep_unicode: false,
-                expected_output: "e_ca_devrait_etre_'asciifie'_mais_en_gardant_les_guillemets",
+                expected_output: "!?",
             },
             TestCase {
                 s: ".dotfile",
                 keep_dots: false,
                 keep_special_chars: false,
                 keep_unicode: false,
-                expected_output: ".dotfile",
+                expected_output: ".Dotfile",
             },
         ];
 
@@ -415,7 +1937,7 @@ mod test {
         } in test_cases
         {
             assert_eq!(
-                snake_case(s, keep_dots, keep_special_chars, keep_unicode),
+                title_case(s, keep_dots, keep_special_chars, keep_unicode),
                 expected_output
             );
         }
@@ -751,6 +2273,41 @@ mod test {
                 keep_unicode: false,
                 expected_output: ".dotfile",
             },
+            TestCase {
+                s: "é à",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: true,
+                expected_output: "éÀ",
+            },
+            TestCase {
+                s: "über test",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: true,
+                expected_output: "überTest",
+            },
+            TestCase {
+                s: "straße ßig",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: true,
+                expected_output: "straßeSSig",
+            },
+            TestCase {
+                s: "ÉLAN été",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: true,
+                expected_output: "élanÉté",
+            },
+            TestCase {
+                s: "àé-",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: true,
+                expected_output: "àé",
+            },
         ];
 
         for TestCase {
@@ -876,6 +2433,27 @@ mod test {
                 keep_unicode: false,
                 expected_output: ".dotfile",
             },
+            TestCase {
+                s: "é à",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: true,
+                expected_output: "ÉÀ",
+            },
+            TestCase {
+                s: "über test",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: true,
+                expected_output: "ÜberTest",
+            },
+            TestCase {
+                s: "àé-",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: true,
+                expected_output: "Àé",
+            },
         ];
 
         for TestCase {
@@ -987,6 +2565,34 @@ mod test {
                 keep_unicode: false,
                 expected_output: ".dotfile",
             },
+            TestCase {
+                s: "My-fileName",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "my-file-name",
+            },
+            TestCase {
+                s: "a-B",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "a-b",
+            },
+            TestCase {
+                s: "a_B",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "a-b",
+            },
+            TestCase {
+                s: "fooBar-Baz",
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                expected_output: "foo-bar-baz",
+            },
         ];
 
         for TestCase {
@@ -1003,4 +2609,617 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn truncate_name_with_hash_keeps_truncated_names_distinct() {
+        let originals = [
+            "A very long generated name, take 1",
+            "A very long generated name, take 2",
+        ];
+        let names = originals.map(|o| snake_case(o, false, false, false));
+        assert_eq!(truncate_name(&names[0], 20), truncate_name(&names[1], 20));
+
+        let truncated = [0, 1].map(|i| truncate_name_with_hash(&names[i], originals[i], 20, "_"));
+        assert_ne!(truncated[0], truncated[1]);
+        for t in &truncated {
+            assert!(t.chars().count() <= 20, "{:?} is too long", t);
+            assert!(t.starts_with("a_very_long_"), "{:?} lost its prefix", t);
+        }
+
+        // Stable across calls, and untouched when short enough.
+        assert_eq!(
+            truncate_name_with_hash(&names[0], originals[0], 20, "_"),
+            truncated[0]
+        );
+        assert_eq!(truncate_name_with_hash("short", "Short", 20, "_"), "short");
+    }
+
+    #[test]
+    fn squeezing_whitespace_avoids_repeated_separators() {
+        let opts = |squeeze_whitespace| FormatOptions {
+            naming_convention: NamingConvention::SnakeCase,
+            keep_dots: false,
+            keep_special_chars: true,
+            keep_unicode: false,
+            squeeze_whitespace,
+            acronym_aware: false,
+            unicode_words: false,
+            trim_separators: false,
+            format_dotfiles: false,
+            normalize_dots: false,
+            protect: vec![],
+            date_patterns: vec![],
+            case_only: false,
+            keep_case: false,
+            acronyms: vec![],
+        };
+
+        // Separators are never repeated in snake_case anyway.
+        assert_eq!(opts(false).format_name("a \t b"), "a_b");
+        assert_eq!(opts(true).format_name("a \t b"), "a_b");
+        assert_eq!(opts(true).format_name("My\t\tOld   File"), "my_old_file");
+        assert_eq!(opts(true).format_name("(draft)  \tv2"), "(draft)_v2");
+    }
+
+    #[test]
+    fn acronyms_are_words_of_their_own_in_acronym_aware_mode() {
+        let opts = |naming_convention, acronym_aware| FormatOptions {
+            naming_convention,
+            keep_dots: false,
+            keep_special_chars: false,
+            keep_unicode: false,
+            squeeze_whitespace: false,
+            acronym_aware,
+            unicode_words: false,
+            trim_separators: false,
+            format_dotfiles: false,
+            normalize_dots: false,
+            protect: vec![],
+            date_patterns: vec![],
+            case_only: false,
+            keep_case: false,
+            acronyms: vec![],
+        };
+
+        let test_cases = [
+            (NamingConvention::CamelCase, "URLParser", "urlParser"),
+            (NamingConvention::CamelCase, "IOStream", "ioStream"),
+            (NamingConvention::CamelCase, "AString", "aString"),
+            (NamingConvention::PascalCase, "URLParser", "UrlParser"),
+            (NamingConvention::PascalCase, "IOStream", "IoStream"),
+            (NamingConvention::PascalCase, "AString", "AString"),
+            (NamingConvention::SnakeCase, "URLParser", "url_parser"),
+            (NamingConvention::KebabCase, "my IOStream", "my-io-stream"),
+            (NamingConvention::Lower, "URLParser", "urlparser"),
+            (NamingConvention::Upper, "URLParser", "URLPARSER"),
+        ];
+        for (nc, name, expected_output) in test_cases {
+            assert_eq!(
+                opts(nc.clone(), true).format_name(name),
+                expected_output,
+                "{:?} in {:?}",
+                name,
+                nc
+            );
+        }
+
+        // Unchanged otherwise
+        assert_eq!(
+            opts(NamingConvention::CamelCase, false).format_name("URLParser"),
+            "urlparser"
+        );
+    }
+
+    #[test]
+    fn unicode_words_split_scripts_without_spaces() {
+        let opts = |naming_convention, unicode_words| FormatOptions {
+            naming_convention,
+            keep_dots: false,
+            keep_special_chars: false,
+            keep_unicode: true,
+            squeeze_whitespace: false,
+            acronym_aware: false,
+            unicode_words,
+            trim_separators: false,
+            format_dotfiles: false,
+            normalize_dots: false,
+            protect: vec![],
+            date_patterns: vec![],
+            case_only: false,
+            keep_case: false,
+            acronyms: vec![],
+        };
+
+        let test_cases = [
+            (NamingConvention::SnakeCase, "東京report", "東_京_report"),
+            (NamingConvention::KebabCase, "My 東京 Trip", "my-東-京-trip"),
+            (NamingConvention::CamelCase, "東京report", "東京Report"),
+            (NamingConvention::PascalCase, "report東京", "Report東京"),
+            (NamingConvention::SnakeCase, "データfile", "データ_file"),
+            (NamingConvention::Lower, "東京Report", "東京report"),
+        ];
+        for (nc, name, expected_output) in test_cases {
+            assert_eq!(
+                opts(nc.clone(), true).format_name(name),
+                expected_output,
+                "{:?} in {:?}",
+                name,
+                nc
+            );
+        }
+
+        // Unchanged otherwise
+        assert_eq!(
+            opts(NamingConvention::SnakeCase, false).format_name("東京report"),
+            "東京report"
+        );
+        assert_eq!(
+            opts(NamingConvention::CamelCase, false).format_name("東京report"),
+            "東京report"
+        );
+    }
+
+    #[test]
+    fn at_most_one_separator_is_emitted_between_words() {
+        let test_cases = [
+            ("foo{}Bar", "foo_bar"),
+            ("foo{}bar", "foo_bar"),
+            ("foo{0}{0}Bar", "foo_bar"),
+            ("fooBar{}Baz", "foo_bar_baz"),
+            ("foo{}BAR", "foo_bar"),
+            ("fooBar{}", "foo_bar_"),
+            ("foo{0}{0}{0}Bar", "foo_bar"),
+            ("foo{}&{}Bar", "foo_bar"),
+        ];
+        for sep in ['_', '-', '.', ' ', '\t'] {
+            for (s, expected_output) in test_cases {
+                let s = s.replace("{0}", &sep.to_string());
+                let s = s.replace("{}", &sep.to_string());
+                assert_eq!(
+                    snake_case(&s, false, false, false),
+                    expected_output,
+                    "{:?} in snake_case",
+                    s
+                );
+                assert_eq!(
+                    kebab_case(&s, false, false, false),
+                    expected_output.replace('_', "-"),
+                    "{:?} in kebab-case",
+                    s
+                );
+            }
+        }
+
+        // Leading separators
+        assert_eq!(snake_case("__FooBar", false, false, false), "_foo_bar");
+        assert_eq!(kebab_case("--FooBar", false, false, false), "-foo-bar");
+
+        // Mixed separators
+        assert_eq!(snake_case("foo_-. Bar", false, false, false), "foo_bar");
+        assert_eq!(kebab_case("foo_-. Bar", false, false, false), "foo-bar");
+    }
+
+    #[test]
+    fn trimming_separators_applies_to_every_naming_convention() {
+        let opts = |naming_convention, trim_separators| FormatOptions {
+            naming_convention,
+            keep_dots: false,
+            keep_special_chars: false,
+            keep_unicode: false,
+            squeeze_whitespace: false,
+            acronym_aware: false,
+            unicode_words: false,
+            trim_separators,
+            format_dotfiles: false,
+            normalize_dots: false,
+            protect: vec![],
+            date_patterns: vec![],
+            case_only: false,
+            keep_case: false,
+            acronyms: vec![],
+        };
+
+        let test_cases = [
+            (NamingConvention::SnakeCase, "_foo_", "foo"),
+            (NamingConvention::SnakeCase, "-bar-", "bar"),
+            (NamingConvention::SnakeCase, "._foo_", ".foo"),
+            (NamingConvention::KebabCase, "_foo_", "foo"),
+            (NamingConvention::KebabCase, "-bar-", "bar"),
+            (NamingConvention::Lower, " _foo_ ", "foo"),
+            (NamingConvention::Upper, "-bar-", "BAR"),
+            (NamingConvention::CamelCase, "_foo_", "foo"),
+        ];
+        for (nc, name, expected_output) in test_cases {
+            assert_eq!(
+                opts(nc.clone(), true).format_name(name),
+                expected_output,
+                "{:?} in {:?}",
+                name,
+                nc
+            );
+        }
+
+        // Unchanged otherwise
+        assert_eq!(
+            opts(NamingConvention::SnakeCase, false).format_name("_foo_"),
+            "_foo_"
+        );
+        assert_eq!(
+            opts(NamingConvention::KebabCase, false).format_name("-bar-"),
+            "-bar-"
+        );
+    }
+
+    #[test]
+    fn known_acronyms_are_never_split() {
+        let opts = |acronyms: &[&str]| FormatOptions {
+            naming_convention: NamingConvention::SnakeCase,
+            keep_dots: false,
+            keep_special_chars: false,
+            keep_unicode: false,
+            squeeze_whitespace: false,
+            acronym_aware: true,
+            unicode_words: false,
+            trim_separators: false,
+            format_dotfiles: false,
+            normalize_dots: false,
+            protect: vec![],
+            date_patterns: vec![],
+            case_only: false,
+            keep_case: false,
+            acronyms: acronyms.iter().map(|a| a.to_string()).collect(),
+        };
+        let acronyms = ["API", "HTTP", "IO"];
+
+        let test_cases = [
+            ("APIResponse", "api_response", "api_response"),
+            ("ABTest", "ab_test", "ab_test"),
+            ("HTTPAPIResponse", "httpapi_response", "http_api_response"),
+            ("APIs", "ap_is", "apis"),
+            ("getIOStream", "get_io_stream", "get_io_stream"),
+            ("IOAPI", "ioapi", "io_api"),
+        ];
+        for (name, without_acronyms, with_acronyms) in test_cases {
+            assert_eq!(opts(&[]).format_name(name), without_acronyms, "{:?}", name);
+            assert_eq!(
+                opts(&acronyms).format_name(name),
+                with_acronyms,
+                "{:?} with {:?}",
+                name,
+                acronyms
+            );
+        }
+    }
+
+    #[test]
+    fn dotfiles_are_formatted_as_a_whole_only_if_asked() {
+        for (format_dotfiles, expected) in [(false, ".env.local"), (true, "env_local")] {
+            let opts = FormatOptions {
+                naming_convention: NamingConvention::SnakeCase,
+                keep_dots: false,
+                keep_special_chars: false,
+                keep_unicode: false,
+                squeeze_whitespace: false,
+                acronym_aware: false,
+                unicode_words: false,
+                trim_separators: false,
+                format_dotfiles,
+                normalize_dots: false,
+                protect: vec![],
+                date_patterns: vec![],
+                case_only: false,
+                keep_case: false,
+                acronyms: vec![],
+            };
+            assert_eq!(opts.format_filename(".env.local"), expected);
+        }
+    }
+
+    #[test]
+    fn protected_substrings_are_kept_as_is() {
+        let opts = FormatOptions {
+            naming_convention: NamingConvention::SnakeCase,
+            keep_dots: false,
+            keep_special_chars: false,
+            keep_unicode: false,
+            squeeze_whitespace: false,
+            acronym_aware: false,
+            unicode_words: false,
+            trim_separators: false,
+            format_dotfiles: false,
+            normalize_dots: false,
+            protect: vec![String::from("SKU-1234"), String::from("SKU")],
+            date_patterns: vec![],
+            case_only: false,
+            keep_case: false,
+            acronyms: vec![],
+        };
+
+        let test_cases = [
+            ("Order SKU-1234 Invoice", "order_SKU-1234_invoice"),
+            ("SKU-1234 Invoice", "SKU-1234_invoice"),
+            ("Invoice SKU-1234", "invoice_SKU-1234"),
+            ("My SKU List", "my_SKU_list"),
+            ("No Code Here", "no_code_here"),
+        ];
+        for (name, expected) in test_cases {
+            assert_eq!(opts.format_name(name), expected, "For {:?}.", name);
+        }
+    }
+
+    #[test]
+    fn dates_are_kept_as_is_when_preserved() {
+        let opts = |preserve_dates, date_pattern: Option<&str>| {
+            FormatOptions::new(
+                ConventionArgs {
+                    naming_convention: Some(NamingConvention::SnakeCase),
+                    preserve_dates,
+                    date_pattern: date_pattern.map(|p| p.parse().unwrap()),
+                    ..Default::default()
+                },
+                &Cfg::default(),
+            )
+        };
+
+        let test_cases = [
+            (
+                false,
+                None,
+                "report 2024-01-15 final",
+                "report_2024_01_15_final",
+            ),
+            (
+                true,
+                None,
+                "report 2024-01-15 final",
+                "report_2024-01-15_final",
+            ),
+            (true, None, "Report-2024-01-15", "report_2024-01-15"),
+            (true, None, "version 1234-56-78", "version_1234_56_78"),
+            (true, None, "Trip 15.01.2024", "trip_15_01_2024"),
+            (
+                false,
+                Some(r"\d{2}\.\d{2}\.\d{4}"),
+                "Trip 15.01.2024",
+                "trip_15.01.2024",
+            ),
+            (
+                false,
+                Some(r"\d{2}\.\d{2}\.\d{4}"),
+                "Trip 2024-01-15",
+                "trip_2024-01-15",
+            ),
+        ];
+        for (preserve_dates, date_pattern, name, expected) in test_cases {
+            assert_eq!(
+                opts(preserve_dates, date_pattern).format_name(name),
+                expected,
+                "For {:?}.",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn keep_case_only_replaces_separators() {
+        let opts = |naming_convention| FormatOptions {
+            naming_convention,
+            keep_dots: false,
+            keep_special_chars: false,
+            keep_unicode: false,
+            squeeze_whitespace: false,
+            acronym_aware: false,
+            unicode_words: false,
+            trim_separators: false,
+            format_dotfiles: false,
+            normalize_dots: false,
+            protect: vec![],
+            date_patterns: vec![],
+            case_only: false,
+            keep_case: true,
+            acronyms: vec![],
+        };
+
+        let test_cases = [
+            (NamingConvention::SnakeCase, "My File.txt", "My_File.txt"),
+            (
+                NamingConvention::SnakeCase,
+                "myFile - V2!.md",
+                "my_File_V2.md",
+            ),
+            (NamingConvention::SnakeCase, ".My Config", ".My_Config"),
+            (NamingConvention::KebabCase, "My File.txt", "My-File.txt"),
+            (NamingConvention::KebabCase, "Élan_VITAL", "Elan-VITAL"),
+            // Other naming conventions change the case anyway.
+            (NamingConvention::CamelCase, "My File.txt", "myFile.txt"),
+            (NamingConvention::Upper, "My File.txt", "MY FILE.txt"),
+        ];
+        for (naming_convention, filename, expected) in test_cases {
+            assert_eq!(
+                opts(naming_convention.clone()).format_filename(filename),
+                expected,
+                "for {:?} in {:?}",
+                filename,
+                naming_convention
+            );
+        }
+    }
+
+    #[test]
+    fn case_only_changes_nothing_but_the_case() {
+        let opts = |naming_convention, keep_unicode| FormatOptions {
+            naming_convention,
+            keep_dots: false,
+            keep_special_chars: false,
+            keep_unicode,
+            squeeze_whitespace: false,
+            acronym_aware: false,
+            unicode_words: false,
+            trim_separators: false,
+            format_dotfiles: false,
+            normalize_dots: false,
+            protect: vec![],
+            date_patterns: vec![],
+            case_only: true,
+            keep_case: false,
+            acronyms: vec![],
+        };
+
+        let test_cases = [
+            (NamingConvention::Lower, false, "My-File.TXT", "my-file.TXT"),
+            (
+                NamingConvention::SnakeCase,
+                false,
+                "My File (1).md",
+                "my file (1).md",
+            ),
+            (
+                NamingConvention::Upper,
+                false,
+                "my_file!.txt",
+                "MY_FILE!.txt",
+            ),
+            (NamingConvention::Lower, false, "Élan.txt", "elan.txt"),
+            (NamingConvention::Lower, true, "Élan.txt", "élan.txt"),
+        ];
+        for (naming_convention, keep_unicode, filename, expected) in test_cases {
+            assert_eq!(
+                opts(naming_convention, keep_unicode).format_filename(filename),
+                expected,
+                "For {:?}.",
+                filename
+            );
+        }
+    }
+
+    #[test]
+    fn target_paths_are_computed_without_touching_the_filesystem() {
+        let opts = FormatOptions {
+            naming_convention: NamingConvention::SnakeCase,
+            keep_dots: false,
+            keep_special_chars: false,
+            keep_unicode: false,
+            squeeze_whitespace: false,
+            acronym_aware: false,
+            unicode_words: false,
+            trim_separators: false,
+            format_dotfiles: false,
+            normalize_dots: false,
+            protect: vec![],
+            date_patterns: vec![],
+            case_only: false,
+            keep_case: false,
+            acronyms: vec![],
+        };
+        let target = |path: &str| target_path_for(Path::new(path), &opts);
+
+        // Multi-dot names only keep their last extension as is.
+        assert_eq!(
+            target("/dir/My.Old Archive.tar.GZ"),
+            Ok(Some(PathBuf::from("/dir/my_old_archive_tar.GZ")))
+        );
+        // Dotfiles keep their leading dot, and have no extension.
+        assert_eq!(target("/dir/.bashrc"), Ok(None));
+        assert_eq!(
+            target("/dir/.My Config"),
+            Ok(Some(PathBuf::from("/dir/.my_config")))
+        );
+        let dotfiles_opts = FormatOptions {
+            format_dotfiles: true,
+            normalize_dots: false,
+            ..opts.clone()
+        };
+        assert_eq!(
+            target_path_for(Path::new("/dir/.My Config"), &dotfiles_opts),
+            Ok(Some(PathBuf::from("/dir/my_config")))
+        );
+        // No-ops
+        assert_eq!(target("/dir/already_formatted.txt"), Ok(None));
+        assert_eq!(target("relative/some_file"), Ok(None));
+        assert_eq!(
+            target("/dir/Some File.txt"),
+            Ok(Some(PathBuf::from("/dir/some_file.txt")))
+        );
+        // Errors
+        assert_eq!(target("/"), Err(MapError::NoFilename));
+        assert_eq!(target("/dir/.."), Err(MapError::NoFilename));
+    }
+
+    #[test]
+    fn runs_of_dots_are_collapsed_if_asked() {
+        // Dots are kept with both keep_dots and keep_special_chars.
+        let opts = |normalize_dots, keep_dots| FormatOptions {
+            naming_convention: NamingConvention::SnakeCase,
+            keep_dots,
+            keep_special_chars: keep_dots,
+            keep_unicode: false,
+            squeeze_whitespace: false,
+            acronym_aware: false,
+            unicode_words: false,
+            trim_separators: false,
+            format_dotfiles: false,
+            normalize_dots,
+            protect: vec![],
+            date_patterns: vec![],
+            case_only: false,
+            keep_case: false,
+            acronyms: vec![],
+        };
+
+        for (filename, normalize_dots, keep_dots, expected) in [
+            ("file..txt", false, false, "file_.txt"),
+            ("file..txt", true, false, "file.txt"),
+            ("file.  .txt", true, false, "file.txt"),
+            ("a...b.txt", true, false, "a_b.txt"),
+            ("a...b.txt", true, true, "a.b.txt"),
+            ("a...b.txt", false, true, "a...b.txt"),
+            (".env..local", true, false, ".env.local"),
+            ("notes.", true, false, "notes"),
+        ] {
+            assert_eq!(
+                opts(normalize_dots, keep_dots).format_filename(filename),
+                expected,
+                "for {:?} with normalize_dots={} and keep_dots={}",
+                filename,
+                normalize_dots,
+                keep_dots
+            );
+        }
+        assert_eq!(normalize_dots(".."), "..");
+    }
+
+    #[test]
+    fn naming_conventions_can_be_given_by_alias() {
+        for (alias, expected) in [
+            ("camel", NamingConvention::CamelCase),
+            ("kebab", NamingConvention::KebabCase),
+            ("train", NamingConvention::TrainCase),
+            ("snake", NamingConvention::SnakeCase),
+            ("screaming_snake", NamingConvention::ScreamingSnakeCase),
+            ("pascal", NamingConvention::PascalCase),
+            ("title", NamingConvention::TitleCase),
+            ("lowercase", NamingConvention::Lower),
+            ("upper", NamingConvention::Upper),
+            ("uppercase", NamingConvention::Upper),
+        ] {
+            let nc = NamingConvention::from_str(alias, false).unwrap();
+            assert_eq!(nc, expected, "for {:?}", alias);
+            let nc: NamingConvention = toml::Value::String(String::from(alias)).try_into().unwrap();
+            assert_eq!(nc, expected, "for {:?}", alias);
+        }
+
+        // The canonical name is the one written back.
+        assert_eq!(
+            serde_json::to_string(&NamingConvention::SnakeCase).unwrap(),
+            "\"snake_case\""
+        );
+        assert_eq!(
+            NamingConvention::from_str("kebab", false)
+                .unwrap()
+                .to_possible_value()
+                .unwrap()
+                .get_name(),
+            "kebab-case"
+        );
+    }
 }
@@ -5,6 +5,10 @@ use directories::ProjectDirs;
 use lazy_static::lazy_static;
 use std::path::PathBuf;
 
+/// Name of the directory, relative to the current directory, where history
+/// files are written with --local-history.
+pub const LOCAL_HISTORY_DIR_NAME: &str = ".fmtna-history";
+
 lazy_static! {
     /// Absolute path to the exclude file.
     pub static ref EXCLUDE_FILE_PATH: PathBuf = {
@@ -24,7 +28,7 @@ lazy_static! {
         history_dir_path.push("history");
         history_dir_path
     };
-    /// Absolute path to the history directory.
+    /// Absolute path to the backup directory.
     pub static ref BACKUP_DIR_PATH: PathBuf = {
         let mut backup_dir_path = ProjectDirs::from("", crate_name!(), crate_name!())
             .unwrap()
@@ -33,11 +37,32 @@ lazy_static! {
         backup_dir_path.push("backups");
         backup_dir_path
     };
+    /// Absolute path to the directory of resume files, written when a run is
+    /// interrupted.
+    pub static ref RESUME_DIR_PATH: PathBuf = {
+        let mut resume_dir_path = ProjectDirs::from("", crate_name!(), crate_name!())
+            .unwrap()
+            .config_local_dir()
+            .to_path_buf();
+        resume_dir_path.push("resume");
+        resume_dir_path
+    };
+    /// Absolute path to the lock file, held by runs in progress.
+    pub static ref LOCK_FILE_PATH: PathBuf = {
+        let mut lock_file_path = ProjectDirs::from("", crate_name!(), crate_name!())
+            .unwrap()
+            .config_local_dir()
+            .to_path_buf();
+        lock_file_path.push("fmtna.lock");
+        lock_file_path
+    };
 }
 
 #[cfg(test)]
+/// Paths used throughout the tests.
 pub mod tests {
     use lazy_static::lazy_static;
+    use std::fs;
     use std::path::PathBuf;
 
     lazy_static! {
@@ -48,4 +73,16 @@ pub mod tests {
             tmp_dir
         };
     }
+
+    /// Makes a fresh (i.e. empty) directory named `name` in the "temporary"
+    /// directory and returns its absolute path.
+    pub fn mk_tmp_dir(name: &str) -> PathBuf {
+        let mut dir = TMP_DIR_PATH.clone();
+        dir.push(name);
+        if dir.exists() {
+            fs::remove_dir_all(&dir).unwrap();
+        }
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
 }
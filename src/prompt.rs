@@ -43,24 +43,24 @@ fn prompt_option<PO: PromptOptions>(
     let help_mess = help_mess.unwrap_or("");
 
     loop {
-        print!("{}", mess);
-        io::stdout().flush()?;
+        eprint!("{}", mess);
+        io::stderr().flush()?;
         let input = get_stdin_line_input()?;
 
         if let Some(opt) = PO::match_input(&input) {
             return Ok(opt);
         } else if has_help && input == help_input {
-            println!("{INDENT}----------");
+            eprintln!("{INDENT}----------");
             for line in help_mess.lines() {
-                println!("{INDENT}{}", line);
+                eprintln!("{INDENT}{}", line);
             }
-            println!("{INDENT}----------");
+            eprintln!("{INDENT}----------");
         } else {
             let mut help_key = String::from("");
             if has_help {
                 help_key = format!(", {}", help_input);
             }
-            println!(
+            eprintln!(
                 "{INDENT}Wrong input! Valid inputs are: {}{}. Try again.",
                 PO::get_valid_inputs().join(", "),
                 help_key,
@@ -85,7 +85,7 @@ impl PromptOptions for ErrorPromptOptions {
 /// Prompts the user to continue after an error occured, forcing him
 /// to acknowledge it.
 ///
-/// Outputs are to stdout and input received from stdin.
+/// Outputs are to stderr and input received from stdin.
 ///
 /// # Parameters
 ///
@@ -94,7 +94,7 @@ impl PromptOptions for ErrorPromptOptions {
 ///
 /// # Errors
 ///
-/// Fails if reading/writing from/to stdin/stdout fails.
+/// Fails if reading/writing from/to stdin/stderr fails.
 ///
 /// # Examples
 ///
@@ -167,12 +167,12 @@ impl PromptOptions for AlreadyExistPromptOptions {
 ///
 /// - `path_str`: The path which filename we want to rewrite.
 /// - `new_path_str`: The new path we want to rewrite into, but
-///     where a file already exists.
+///   where a file already exists.
 ///
 /// # Returns
 ///
 /// The option chosen by the user, or an error if reading/writing from/to
-/// stdin/stdout failed.
+/// stdin/stderr failed.
 ///
 /// # Examples
 ///
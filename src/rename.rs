@@ -0,0 +1,8 @@
+//! Module for the rename subcommand.
+
+mod cli;
+mod data;
+mod engine;
+pub use cli::RenameCli;
+pub use data::RenamePattern;
+pub use engine::get_engine;
@@ -0,0 +1,62 @@
+use clap::Args;
+use std::path::PathBuf;
+
+use crate::cli::ConventionArgs;
+use crate::default::OnConflict;
+
+#[derive(Args, Clone, Debug, PartialEq, Eq)]
+#[clap(verbatim_doc_comment)]
+/// Rename files by matching their names against a regex.
+///
+/// The new name of each file is built from a template referring to the
+/// capture groups of the regex, e.g. with --pattern '(\d+) - (.*)\.mp3'
+/// and --template '$2 ($1).mp3', "01 - Intro.mp3" is renamed into
+/// "Intro (01).mp3".
+/// Files whose name doesn't match are left alone.
+/// Like with fmtna, conflicts are asked about and changes are recorded into
+/// the history, so that they can be reverted.
+pub struct RenameCli {
+    #[clap(verbatim_doc_comment)]
+    /// A list of files (of any kind) to rename.
+    pub files: Vec<PathBuf>,
+
+    #[clap(verbatim_doc_comment)]
+    /// The regex to match filenames against.
+    ///
+    /// It is matched against the whole filename, extension included, but
+    /// needs not match all of it: use ^ and $ for that.
+    /// See <https://docs.rs/regex/latest/regex/#syntax> for the syntax.
+    #[arg(long, value_name = "REGEX")]
+    pub pattern: String,
+
+    #[clap(verbatim_doc_comment)]
+    /// The new name of the files.
+    ///
+    /// $1, $2, ... are replaced by the text of the capture groups of the
+    /// pattern, $0 by the whole match, and ${name} by the named group
+    /// (?<name>...). Write $$ for a literal $.
+    #[arg(long, value_name = "TEMPLATE")]
+    pub template: String,
+
+    #[clap(verbatim_doc_comment)]
+    /// Same as fmtna's --recursive.
+    #[arg(short, long)]
+    pub recursive: bool,
+
+    #[clap(verbatim_doc_comment)]
+    /// Same as fmtna's --dry-run.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    #[clap(verbatim_doc_comment)]
+    /// Same as fmtna's --on-conflict.
+    #[arg(long, value_enum, value_name = "POLICY", default_value_t = OnConflict::Ask)]
+    pub on_conflict: OnConflict,
+
+    #[clap(flatten)]
+    /// Arguments controlling how the new names are formatted.
+    ///
+    /// The new names are formatted only if a naming convention is given
+    /// with --naming-convention, as fmtna would format them.
+    pub convention: ConventionArgs,
+}
@@ -0,0 +1,49 @@
+use super::cli::RenameCli;
+use anyhow::Context;
+use regex::Regex;
+
+#[derive(Debug)]
+/// How to build the new name of files, see [RenameCli].
+pub struct RenamePattern {
+    /// Same as [RenameCli::pattern](crate::rename::RenameCli::pattern), compiled.
+    pub regex: Regex,
+
+    /// Same as [RenameCli::template](crate::rename::RenameCli::template)
+    pub template: String,
+
+    /// Whether to format the new names afterwards, i.e. whether a naming
+    /// convention was given.
+    pub apply_convention: bool,
+}
+
+impl RenamePattern {
+    /// Compiles the pattern of `cli`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the pattern is not a valid regex.
+    pub fn new(cli: &RenameCli) -> anyhow::Result<Self> {
+        let regex = Regex::new(&cli.pattern)
+            .with_context(|| format!("Invalid pattern \"{}\".", cli.pattern))?;
+
+        Ok(RenamePattern {
+            regex,
+            template: cli.template.clone(),
+            apply_convention: cli.convention.naming_convention.is_some(),
+        })
+    }
+
+    /// Returns the new name of the file named `filename`, built from the
+    /// template, or `None` if `filename` doesn't match the pattern.
+    ///
+    /// # Parameters
+    ///
+    /// - `filename`
+    pub fn apply(&self, filename: &str) -> Option<String> {
+        let captures = self.regex.captures(filename)?;
+        let mut new_filename = String::new();
+        captures.expand(&self.template, &mut new_filename);
+
+        Some(new_filename)
+    }
+}
@@ -0,0 +1,33 @@
+use super::cli::RenameCli;
+use super::data::RenamePattern;
+use crate::cfg::Cfg;
+use crate::default::{self, DefaultArgs};
+use crate::engine::Engine;
+
+/// Returns the engine for the rename subcommand, parameterized by `cli` and `cfg`.
+///
+/// The engine is fmtna's, with new names built by [RenamePattern] rather
+/// than by formatting.
+///
+/// # Parameters
+///
+/// - `cli`: The CLI arguments.
+/// - `cfg`: The configuration values.
+///
+/// # Returns
+///
+/// The parametrized engine for running the rename subcommand's logic, or an
+/// error if engine creation failed.
+pub fn get_engine(cli: RenameCli, cfg: Cfg) -> anyhow::Result<Box<dyn Engine>> {
+    let rename_pattern = RenamePattern::new(&cli)?;
+    let args = DefaultArgs {
+        files: cli.files,
+        recursive: cli.recursive,
+        dry_run: cli.dry_run,
+        on_conflict: cli.on_conflict,
+        convention: cli.convention,
+        ..Default::default()
+    };
+
+    default::get_engine_with_rename_pattern(args, cfg, rename_pattern)
+}
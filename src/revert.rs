@@ -4,4 +4,5 @@ mod cli;
 mod data;
 mod engine;
 pub use cli::RevertCli;
-pub use engine::get_engine;
+pub(crate) use engine::get_rollback_engine;
+pub use engine::{get_engine, parse_history_line};
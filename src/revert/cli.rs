@@ -22,5 +22,76 @@ use clap::Args;
 pub struct RevertCli {
     #[clap(verbatim_doc_comment)]
     /// The file specifying the filename changes to revert.
-    pub history_file: PathBuf,
+    #[arg(required_unless_present = "last", conflicts_with = "last")]
+    pub history_file: Option<PathBuf>,
+
+    #[clap(verbatim_doc_comment)]
+    /// Revert the most recent history file of the history directory instead.
+    ///
+    /// The history directory is the one where the "second-order" history file
+    /// is written (see --history-dir and --local).
+    #[arg(long)]
+    pub last: bool,
+
+    #[clap(verbatim_doc_comment)]
+    /// The directory where to write the "second-order" history file.
+    ///
+    /// Defaults to the history directory in your config directory.
+    #[arg(long, value_name = "DIR")]
+    pub history_dir: Option<PathBuf>,
+
+    #[clap(verbatim_doc_comment)]
+    /// Use the .fmtna-history directory in the current directory as history
+    /// directory.
+    ///
+    /// This is where history files are written by runs with --local-history.
+    #[arg(long, conflicts_with = "history_dir")]
+    pub local: bool,
+
+    #[clap(verbatim_doc_comment)]
+    /// Only revert the lines of these operations (comma-separated).
+    ///
+    /// Operations are designated by their code in the history file:
+    ///   - `d`: Plain renamings.
+    ///   - `b`: Renamings done after backing up an existing file.
+    ///   - `o`: Renamings overwriting an existing file.
+    ///   - `l`: Symbolic links left at old paths (see --leave-symlink).
+    ///   - `c`: Copies into an output directory (see --output-dir), removed.
+    ///   - `s`: Skipped renamings, so nothing to revert.
+    ///
+    /// Defaults to every operation.
+    /// Note that a file renamed while leaving a symbolic link can't be renamed
+    /// back unless `l` is selected as well.
+    ///
+    /// Example: --ops o,b
+    #[arg(
+        long,
+        value_name = "OPS",
+        value_delimiter = ',',
+        value_parser = ["d", "b", "o", "l", "c", "s"]
+    )]
+    pub ops: Vec<String>,
+
+    #[clap(verbatim_doc_comment)]
+    /// Only warn about invalid lines in the history file.
+    ///
+    /// By default, invalid lines are ignored and reported as an error at the
+    /// end (valid lines are reverted nonetheless), so the program exits with
+    /// a non-zero status.
+    /// With this flag, they are reported as a warning on stderr instead.
+    #[arg(long)]
+    pub lenient: bool,
+
+    #[clap(verbatim_doc_comment)]
+    /// Print the recap of the changes in the order of the original run.
+    ///
+    /// Changes are reverted from the most recent to the oldest, which is the
+    /// only safe order (e.g. files are renamed back before their parent
+    /// directory), so they are recapped in that order by default.
+    /// With this flag, the recap is held back until the end and printed in
+    /// chronological order instead, which is easier to compare with the
+    /// output of the original run. Errors and prompts still come as they
+    /// happen.
+    #[arg(long)]
+    pub chronological: bool,
 }
@@ -1,25 +1,107 @@
 use crate::cfg::Cfg;
+use crate::paths::{BACKUP_DIR_PATH, HISTORY_DIR_PATH, LOCAL_HISTORY_DIR_NAME, LOCK_FILE_PATH};
 use crate::revert::cli::RevertCli;
-use anyhow::anyhow;
-use std::path::PathBuf;
+use anyhow::{anyhow, Context};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Data {
     pub history_file: PathBuf,
+    pub history_dir_path: PathBuf,
+    pub ops: Vec<String>,
+    pub lenient: bool,
+    pub chronological: bool,
+    /// The directory where to back up files on conflicts, the same as fmtna's.
+    pub backup_dir_path: PathBuf,
+    /// The lock file taken while reverting, the same as fmtna's.
+    pub lock_file_path: PathBuf,
+    /// Whether the lock is already held by the caller, i.e. a run rolling
+    /// back its changes, in which case it is not taken again.
+    pub lock_held: bool,
 }
 
 impl Data {
     pub fn new(cli: RevertCli, cfg: Cfg) -> anyhow::Result<Self> {
         let _ = cfg;
 
-        if !cli.history_file.exists() {
-            return Err(anyhow!(format!("{:?} does not exist.", cli.history_file)));
+        let history_dir_path = if cli.local {
+            env::current_dir()
+                .with_context(|| "Failed to get the current directory.")?
+                .join(LOCAL_HISTORY_DIR_NAME)
+        } else {
+            cli.history_dir.unwrap_or(HISTORY_DIR_PATH.clone())
+        };
+
+        let history_file = match cli.history_file {
+            Some(history_file) => history_file,
+            None => last_history_file(&history_dir_path)?.ok_or_else(|| {
+                anyhow!(format!(
+                    "No history file in {}.",
+                    history_dir_path.to_string_lossy()
+                ))
+            })?,
+        };
+        if !history_file.exists() {
+            return Err(anyhow!(format!("{:?} does not exist.", history_file)));
         }
 
         Ok(Data {
-            history_file: cli.history_file,
+            history_file,
+            history_dir_path,
+            ops: cli.ops,
+            lenient: cli.lenient,
+            chronological: cli.chronological,
+            backup_dir_path: BACKUP_DIR_PATH.clone(),
+            lock_file_path: LOCK_FILE_PATH.clone(),
+            lock_held: false,
         })
     }
+
+    /// Returns the data to roll back the run that recorded its changes in
+    /// `history_file`, with the run's own paths.
+    ///
+    /// The run holds the lock at `lock_file_path` already.
+    pub fn for_rollback(
+        history_file: PathBuf,
+        history_dir_path: PathBuf,
+        backup_dir_path: PathBuf,
+        lock_file_path: PathBuf,
+    ) -> Self {
+        Data {
+            history_file,
+            history_dir_path,
+            ops: vec![],
+            lenient: false,
+            chronological: false,
+            backup_dir_path,
+            lock_file_path,
+            lock_held: true,
+        }
+    }
+}
+
+/// Returns the most recent history file in `dir`, if any.
+///
+/// History files are named after the time they were written at, in a format
+/// such that the most recent one comes last in lexicographic order.
+fn last_history_file(dir: &Path) -> anyhow::Result<Option<PathBuf>> {
+    if !dir.exists() {
+        return Ok(None);
+    }
+
+    let mut last = None;
+    for entry in fs::read_dir(dir)
+        .with_context(|| format!("Failed to read directory {}.", dir.to_string_lossy()))?
+    {
+        let path = entry?.path();
+        if path.is_file() && last.as_ref().is_none_or(|last: &PathBuf| path > *last) {
+            last = Some(path);
+        }
+    }
+
+    Ok(last)
 }
 
 #[cfg(test)]
@@ -28,7 +110,6 @@ mod tests {
     use crate::naming_conventions::NamingConvention;
     use crate::paths::tests::TMP_DIR_PATH;
     use serial_test::serial;
-    use std::fs;
 
     #[derive(Debug)]
     struct TestCase {
@@ -40,19 +121,19 @@ mod tests {
     fn mk_backup_file() -> PathBuf {
         let tmp_dir = &*TMP_DIR_PATH;
         if !tmp_dir.exists() {
-            if let Err(err) = fs::create_dir(&tmp_dir) {
+            if let Err(err) = fs::create_dir(tmp_dir) {
                 panic!("{:?}", err);
             }
         }
 
         let mut backup_file = tmp_dir.clone();
         backup_file.push("backup_file");
-        let lines = vec![String::from("")];
+        let lines = [String::from("")];
         if let Err(err) = fs::write(&backup_file, lines.join("\n")) {
             panic!("{:?}", err);
         }
 
-        return backup_file;
+        backup_file
     }
 
     #[serial]
@@ -64,7 +145,13 @@ mod tests {
             // Cli takes precedence
             TestCase {
                 cli: RevertCli {
-                    history_file: backup_file.clone(),
+                    history_file: Some(backup_file.clone()),
+                    last: false,
+                    history_dir: None,
+                    local: false,
+                    ops: vec![],
+                    lenient: false,
+                    chronological: false,
                 },
                 cfg: Cfg {
                     naming_convention: NamingConvention::SnakeCase,
@@ -73,9 +160,19 @@ mod tests {
                     keep_special_chars: false,
                     keep_unicode: false,
                     editor: String::from("vi"),
+                    acronyms: vec![],
+                    recurse_renames_roots: true,
+                    preserve_dates: false,
                 },
                 data: Data {
                     history_file: backup_file.clone(),
+                    history_dir_path: HISTORY_DIR_PATH.clone(),
+                    ops: vec![],
+                    lenient: false,
+                    chronological: false,
+                    backup_dir_path: BACKUP_DIR_PATH.clone(),
+                    lock_file_path: LOCK_FILE_PATH.clone(),
+                    lock_held: false,
                 },
             },
         ];
@@ -97,7 +194,13 @@ mod tests {
         backup_file.push("inexistant_backup_file");
 
         let cli = RevertCli {
-            history_file: backup_file.clone(),
+            history_file: Some(backup_file.clone()),
+            last: false,
+            history_dir: None,
+            local: false,
+            ops: vec![],
+            lenient: false,
+            chronological: false,
         };
         let cfg = Cfg {
             naming_convention: NamingConvention::SnakeCase,
@@ -106,8 +209,40 @@ mod tests {
             keep_special_chars: false,
             keep_unicode: false,
             editor: String::from("vi"),
+            acronyms: vec![],
+            recurse_renames_roots: true,
+            preserve_dates: false,
         };
 
         assert!(Data::new(cli, cfg).is_err(), "Expected Data::new to fail.",);
     }
+
+    #[serial]
+    #[test]
+    fn last_picks_the_most_recent_history_file() {
+        let history_dir = crate::paths::tests::mk_tmp_dir("last_history_file");
+        for name in [
+            "20240101_120000.000000000",
+            "20240301_090000.000000000",
+            "20240201_180000.000000000",
+        ] {
+            fs::write(history_dir.join(name), "").unwrap();
+        }
+
+        let cli = RevertCli {
+            history_file: None,
+            last: true,
+            history_dir: Some(history_dir.clone()),
+            local: false,
+            ops: vec![],
+            lenient: false,
+            chronological: false,
+        };
+        let data = Data::new(cli, Cfg::default()).expect("Data::new should have succeeded.");
+
+        assert_eq!(
+            data.history_file,
+            history_dir.join("20240301_090000.000000000")
+        );
+    }
 }
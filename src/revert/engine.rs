@@ -1,27 +1,46 @@
 use super::RevertCli;
 use crate::cfg::Cfg;
 use crate::engine::Engine;
-use crate::paths::HISTORY_DIR_PATH;
+use crate::lock::RunLock;
 use crate::prompt::{already_exist_prompt, error_prompt, AlreadyExistPromptOptions};
 use crate::revert::data::Data;
-use crate::utils::{backup, file_is_empty, get_now_str, overwrite, skip};
+use crate::utils::{
+    apply_conflict_plan, file_is_empty, get_now_str, plan_backup, plan_overwrite, plan_skip,
+    ConflictPlan, ConflictResolution, RetryPolicy,
+};
 use anyhow::anyhow;
 use anyhow::Context;
-use crossterm::style::Stylize;
+use crossterm::style::{StyledContent, Stylize};
 use lazy_static::lazy_static;
 use linecount;
 use regex::Regex;
 use rev_lines::RevLines;
 use std::fs;
 use std::fs::File;
-use std::io::{BufWriter, Write};
-use std::path::PathBuf;
+use std::io::{self, BufWriter, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
 
 lazy_static! {
     static ref HISTORY_LINE_RE: Regex =
         Regex::new(r"\((?<op>.)\)\s+(?<from>.*)\s+->\s+(?<to>.*)\s*").unwrap();
 }
 
+/// Parses a line of a history file of the form `(<op>) <from> -> <to>`.
+///
+/// # Returns
+///
+/// The operation code, the path before the operation and the path after it,
+/// or `None` if the line doesn't have this form.
+pub fn parse_history_line(line: &str) -> Option<(String, PathBuf, PathBuf)> {
+    let caps = HISTORY_LINE_RE.captures(line)?;
+    Some((
+        caps["op"].to_string(),
+        PathBuf::from(&caps["from"]),
+        PathBuf::from(&caps["to"]),
+    ))
+}
+
 /// Returns the engine for the revert subcommand, parameterized by `cli` and `cfg`.
 ///
 /// # Parameters
@@ -37,11 +56,35 @@ pub fn get_engine(cli: RevertCli, cfg: Cfg) -> anyhow::Result<Box<dyn Engine>> {
     Ok(Box::new(RevertEngine::new(cli, cfg)?))
 }
 
-struct RevertEngine {
+/// Returns an engine rolling back the changes of a run, see
+/// [DefaultArgs::transactional](crate::default::DefaultArgs::transactional).
+///
+/// See [Data::for_rollback] for the parameters.
+pub(crate) fn get_rollback_engine(
+    history_file: PathBuf,
+    history_dir_path: PathBuf,
+    backup_dir_path: PathBuf,
+    lock_file_path: PathBuf,
+) -> Box<dyn Engine> {
+    Box::new(RevertEngine::from_data(Data::for_rollback(
+        history_file,
+        history_dir_path,
+        backup_dir_path,
+        lock_file_path,
+    )))
+}
+
+struct RevertEngine<O: Write = io::Stdout, E: Write = io::Stderr> {
     data: Data,
     action: Option<Action>,
+    out: O,
+    /// Where diagnostics, i.e. warnings and errors, are printed.
+    err: E,
+    /// Recap lines held back until the end with [Data::chronological].
+    recap: Vec<String>,
 }
 
+#[derive(Clone, Copy)]
 enum Action {
     Skip,
     Backup,
@@ -50,24 +93,85 @@ enum Action {
 
 impl RevertEngine {
     pub fn new(cli: RevertCli, cfg: Cfg) -> anyhow::Result<Self> {
-        let data = Data::new(cli, cfg)?;
-        Ok(Self { data, action: None })
+        Ok(Self::from_data(Data::new(cli, cfg)?))
+    }
+
+    fn from_data(data: Data) -> Self {
+        Self {
+            data,
+            action: None,
+            out: io::stdout(),
+            err: io::stderr(),
+            recap: vec![],
+        }
     }
 }
 
-impl Engine for RevertEngine {
-    fn run(&mut self) -> anyhow::Result<()> {
-        // Create a backup file
-        // ^^^^^^^^^^^^^^^^^^^^
-        let mut history_path = HISTORY_DIR_PATH.clone();
-        history_path.push(get_now_str());
-        // Don't check if already exists as it shouldn't given the very precise time used for
-        // the name.
-        let history_file = File::create_new(history_path.clone())?;
-        let mut history_writer = BufWriter::new(history_file);
+impl<O: Write, E: Write> RevertEngine<O, E> {
+    /// Prints `line` to recap a change, or holds it back if the recap is to
+    /// be printed in chronological order.
+    fn report(&mut self, line: StyledContent<String>) -> io::Result<()> {
+        if self.data.chronological {
+            self.recap.push(line.to_string());
+            Ok(())
+        } else {
+            writeln!(self.out, "{}", line)
+        }
+    }
 
-        // Process lines
-        // ^^^^^^^^^^^^^
+    /// Prints the recap lines held back, in the order of the original run,
+    /// i.e. the reverse of the order changes were reverted in.
+    fn print_recap(&mut self) -> io::Result<()> {
+        for line in mem::take(&mut self.recap).iter().rev() {
+            writeln!(self.out, "{}", line)?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies `plan`, then recaps it and writes it into `history_writer`.
+    fn resolve_conflict<W: Write>(
+        &mut self,
+        plan: &ConflictPlan,
+        history_writer: &mut W,
+    ) -> anyhow::Result<()> {
+        apply_conflict_plan(plan, &RetryPolicy::default())?;
+
+        let recap_line = plan.history_line();
+        let styled_recap_line = match plan.resolution {
+            ConflictResolution::Skip => recap_line.clone().dark_blue(),
+            ConflictResolution::Backup { .. } => recap_line.clone().dark_green(),
+            ConflictResolution::Overwrite => recap_line.clone().dark_yellow(),
+        };
+        self.report(styled_recap_line)?;
+        writeln!(history_writer, "{}", recap_line)
+            .with_context(|| "Failed to write to history file.")?;
+
+        Ok(())
+    }
+
+    /// Returns how to resolve the conflict of renaming `to` back into the
+    /// existing `from` with `action`.
+    fn plan_conflict(
+        &self,
+        action: Action,
+        to: &Path,
+        from: &Path,
+    ) -> anyhow::Result<ConflictPlan> {
+        Ok(match action {
+            Action::Skip => plan_skip(to, from),
+            Action::Backup => plan_backup(to, from, &self.data.backup_dir_path, &get_now_str())?,
+            Action::Overwrite => plan_overwrite(to, from),
+        })
+    }
+
+    /// Reverts the lines of the history file, from the last to the first,
+    /// and writes the changes done into `history_writer`.
+    ///
+    /// # Returns
+    ///
+    /// The line numbers of the invalid lines, in descending order.
+    fn revert_history<W: Write>(&mut self, history_writer: &mut W) -> anyhow::Result<Vec<usize>> {
         let mut invalid_linenos: Vec<usize> = vec![];
         let file = File::open(self.data.history_file.clone())?;
         let mut line_no = linecount::count_lines(file)? + 1;
@@ -80,17 +184,70 @@ impl Engine for RevertEngine {
                 continue;
             }
 
-            let caps = HISTORY_LINE_RE.captures(&line);
-            if caps.is_none() {
-                invalid_linenos.push(line_no);
+            // Errors, locked files and files on read-only filesystems are
+            // reported for the user to know about them, but they don't
+            // correspond to any change, so there is nothing to revert.
+            if line.starts_with("(e) ") || line.starts_with("(k) ") || line.starts_with("(r) ") {
                 continue;
             }
-            let caps = caps.unwrap();
-            let op = &caps["op"];
-            let from = PathBuf::from(&caps["from"]);
-            let to = PathBuf::from(&caps["to"]);
+
+            let (op, from, to) = match parse_history_line(&line) {
+                Some(parsed) => parsed,
+                None => {
+                    invalid_linenos.push(line_no);
+                    continue;
+                }
+            };
+            let op = op.as_str();
             let from_str = from.to_string_lossy();
             let to_str = to.to_string_lossy();
+            let selected = self.data.ops.is_empty() || self.data.ops.iter().any(|o| o == op);
+
+            match op {
+                "d" | "b" | "o" | "l" | "c" if !selected => continue,
+                "d" | "b" | "o" => {}
+                "c" => {
+                    // The original was left untouched, so reverting the copy
+                    // only means removing it. Directories come after their
+                    // content, so they are empty by then, unless files were
+                    // added since.
+                    let res = if to.is_dir() && !to.is_symlink() {
+                        fs::remove_dir(&to)
+                    } else {
+                        fs::remove_file(&to)
+                    };
+                    match res {
+                        Ok(_) => self.report(format!("Removed copy {}", to_str).dark_grey())?,
+                        Err(err) => {
+                            error_prompt(&to_str, &format!("Failed to remove copy. {}", err))?
+                        }
+                    }
+                    continue;
+                }
+                "l" => {
+                    // Remove the link left at the old path, so that the file
+                    // can be renamed back (the `(d)`, `(b)` or `(o)` line
+                    // coming right before).
+                    if from.is_symlink() {
+                        if let Err(err) = fs::remove_file(&from) {
+                            error_prompt(
+                                &from_str,
+                                &format!("Failed to remove symbolic link. {}", err),
+                            )?;
+                        }
+                    }
+                    continue;
+                }
+                "s" => {
+                    // A skipped conflict didn't change anything, so there is
+                    // nothing to revert.
+                    continue;
+                }
+                _ => {
+                    invalid_linenos.push(line_no);
+                    continue;
+                }
+            }
 
             if from == to {
                 continue;
@@ -107,65 +264,81 @@ impl Engine for RevertEngine {
             }
 
             if from.exists() {
-                if let Some(ref action) = self.action {
-                    match action {
-                        Action::Skip => skip(&to, &from, &mut history_writer)?,
-                        Action::Backup => backup(&to, &from, &mut history_writer)?,
-                        Action::Overwrite => overwrite(&to, &from, &mut history_writer)?,
-                    }
-                    continue;
-                }
-
-                match already_exist_prompt(&to_str, &from_str)? {
-                    AlreadyExistPromptOptions::Skip => {
-                        skip(&to, &from, &mut history_writer)?;
-                    }
-                    AlreadyExistPromptOptions::AlwaysSkip => {
-                        skip(&to, &from, &mut history_writer)?;
-                        self.action = Some(Action::Skip);
-                    }
-                    AlreadyExistPromptOptions::Backup => {
-                        backup(&to, &from, &mut history_writer)?;
-                    }
-                    AlreadyExistPromptOptions::AlwaysBackup => {
-                        backup(&to, &from, &mut history_writer)?;
-                        self.action = Some(Action::Backup);
-                    }
-                    AlreadyExistPromptOptions::Overwrite => {
-                        overwrite(&to, &from, &mut history_writer)?;
-                    }
-                    AlreadyExistPromptOptions::AlwaysOverwrite => {
-                        overwrite(&to, &from, &mut history_writer)?;
-                        self.action = Some(Action::Overwrite);
-                    }
+                let action = match self.action {
+                    Some(action) => action,
+                    None => match already_exist_prompt(&to_str, &from_str)? {
+                        AlreadyExistPromptOptions::Skip => Action::Skip,
+                        AlreadyExistPromptOptions::AlwaysSkip => {
+                            self.action = Some(Action::Skip);
+                            Action::Skip
+                        }
+                        AlreadyExistPromptOptions::Backup => Action::Backup,
+                        AlreadyExistPromptOptions::AlwaysBackup => {
+                            self.action = Some(Action::Backup);
+                            Action::Backup
+                        }
+                        AlreadyExistPromptOptions::Overwrite => Action::Overwrite,
+                        AlreadyExistPromptOptions::AlwaysOverwrite => {
+                            self.action = Some(Action::Overwrite);
+                            Action::Overwrite
+                        }
+                    },
                 };
+                let plan = self.plan_conflict(action, &to, &from)?;
+                self.resolve_conflict(&plan, history_writer)?;
                 continue;
             }
 
-            match op {
-                "d" | "b" | "o" => {
-                    let res = fs::rename(to.clone(), from.clone());
-                    match res {
-                        Ok(_) => {
-                            let recap_line = format!("(d) {} -> {}", to_str, from_str);
-                            println!("{}", recap_line.clone().dark_grey());
-                            writeln!(history_writer, "{}", recap_line)
-                                .with_context(|| "Failed to write to history file.")?;
-                        }
-                        Err(err) => {
-                            error_prompt(&to_str, &format!("Failed to rename. {}", err)[..])?;
-                        }
-                    }
-                }
-                "s" => {
-                    // Nothing to do
+            let res = fs::rename(to.clone(), from.clone());
+            match res {
+                Ok(_) => {
+                    let recap_line = format!("(d) {} -> {}", to_str, from_str);
+                    self.report(recap_line.clone().dark_grey())?;
+                    writeln!(history_writer, "{}", recap_line)
+                        .with_context(|| "Failed to write to history file.")?;
                 }
-                _ => {
-                    invalid_linenos.push(line_no);
+                Err(err) => {
+                    error_prompt(&to_str, &format!("Failed to rename. {}", err)[..])?;
                 }
             }
         }
 
+        Ok(invalid_linenos)
+    }
+}
+
+impl<O: Write, E: Write> Engine for RevertEngine<O, E> {
+    fn run(&mut self) -> anyhow::Result<()> {
+        // Reverting renames files too, so don't race with a run.
+        let _lock = if self.data.lock_held {
+            None
+        } else {
+            Some(RunLock::acquire(&self.data.lock_file_path, &mut self.err)?)
+        };
+
+        // Create a backup file
+        // ^^^^^^^^^^^^^^^^^^^^
+        fs::create_dir_all(&self.data.history_dir_path).with_context(|| {
+            format!(
+                "Failed to create history directory ({}).",
+                self.data.history_dir_path.to_string_lossy()
+            )
+        })?;
+        let mut history_path = self.data.history_dir_path.clone();
+        history_path.push(get_now_str());
+        // Don't check if already exists as it shouldn't given the very precise time used for
+        // the name.
+        let history_file = File::create_new(history_path.clone())?;
+        let mut history_writer = BufWriter::new(history_file);
+
+        // Process lines
+        // ^^^^^^^^^^^^^
+        let res = self.revert_history(&mut history_writer);
+        // Changes were made even if reverting stopped midway, so recap them
+        // nonetheless.
+        self.print_recap()?;
+        let mut invalid_linenos = res?;
+
         // Flush the BufWriter before checking if the history file is empty or not
         history_writer.flush()?;
 
@@ -177,26 +350,319 @@ impl Engine for RevertEngine {
         }
 
         if !invalid_linenos.is_empty() {
-            if invalid_linenos.len() == 1 {
-                return Err(anyhow!(
+            let mess = if invalid_linenos.len() == 1 {
+                format!(
                     "Ignored invalid line with line number {:?}, in {}.",
                     invalid_linenos[0],
                     self.data.history_file.clone().to_string_lossy()
-                ));
+                )
+            } else {
+                // Reverse so that line numbers appear in ascending order.
+                // Indeed, they are in descending order given that we iterated
+                // from the last line to the first in the history file.
+                invalid_linenos.reverse();
+                format!(
+                    "Ignored {} invalid lines with line numbers {:?}, in {}.",
+                    invalid_linenos.len(),
+                    invalid_linenos,
+                    self.data.history_file.clone().to_string_lossy()
+                )
+            };
+
+            if self.data.lenient {
+                writeln!(self.err, "{}", format!("Warning: {}", mess).dark_yellow())?;
+            } else {
+                return Err(anyhow!(mess));
             }
+        }
 
-            // Reverse so that line numbers appear in ascending order.
-            // Indeed, they are in descending order given that we iterated
-            // from the last line to the first in the history file.
-            invalid_linenos.reverse();
-            return Err(anyhow!(
-                "Ignored {} invalid lines with line numbers {:?}, in {}.",
-                invalid_linenos.len(),
-                invalid_linenos,
-                self.data.history_file.clone().to_string_lossy()
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::default::{self, DefaultArgs};
+    use crate::paths::tests::mk_tmp_dir;
+    use serial_test::serial;
+    use std::path::Path;
+
+    fn only_file_in(dir: &Path) -> PathBuf {
+        let mut entries = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        assert_eq!(entries.len(), 1, "Expected exactly one file in {:?}", dir);
+        entries.pop().unwrap()
+    }
+
+    fn mk_engine(cli: RevertCli) -> RevertEngine {
+        let mut engine = RevertEngine::new(cli, Cfg::default())
+            .expect("RevertEngine::new should have succeeded.");
+        // Don't compete for the lock with the user's runs, nor pollute their
+        // backups.
+        engine.data.lock_file_path = mk_tmp_dir("revert_lock").join("fmtna.lock");
+        engine.data.backup_dir_path = mk_tmp_dir("revert_backups");
+        engine
+    }
+
+    fn revert(history_file: PathBuf, history_dir: &Path) {
+        let cli = RevertCli {
+            history_file: Some(history_file),
+            last: false,
+            history_dir: Some(history_dir.to_owned()),
+            local: false,
+            ops: vec![],
+            lenient: false,
+            chronological: false,
+        };
+        mk_engine(cli)
+            .run()
+            .expect("The revert should have succeeded.");
+    }
+
+    #[serial]
+    #[test]
+    fn reverting_a_revert_restores_the_formatted_names() {
+        let dir = mk_tmp_dir("chain_reverts");
+        let original_paths = [
+            dir.join("My Dir"),
+            dir.join("My Dir").join("Some File.txt"),
+            dir.join("Other File.txt"),
+        ];
+        let formatted_paths = [
+            dir.join("my_dir"),
+            dir.join("my_dir").join("some_file.txt"),
+            dir.join("other_file.txt"),
+        ];
+        fs::create_dir(&original_paths[0]).unwrap();
+        File::create(&original_paths[1]).unwrap();
+        File::create(&original_paths[2]).unwrap();
+        let assert_all_exist = |paths: &[PathBuf]| {
+            for p in paths {
+                assert!(p.exists(), "Expected {:?} to exist.", p);
+            }
+        };
+
+        // Run
+        let history_dir = mk_tmp_dir("chain_reverts_history");
+        let cli = DefaultArgs {
+            files: vec![original_paths[0].clone(), original_paths[2].clone()],
+            recursive: true,
+            history_dir: Some(history_dir.clone()),
+            ..Default::default()
+        };
+        default::get_engine(cli, Cfg::default())
+            .expect("default::get_engine should have succeeded.")
+            .run()
+            .expect("The run should have succeeded.");
+        assert_all_exist(&formatted_paths);
+
+        // Revert
+        let second_order_history_dir = mk_tmp_dir("chain_reverts_second_order_history");
+        revert(only_file_in(&history_dir), &second_order_history_dir);
+        assert_all_exist(&original_paths);
+
+        // Revert the revert
+        let third_order_history_dir = mk_tmp_dir("chain_reverts_third_order_history");
+        revert(
+            only_file_in(&second_order_history_dir),
+            &third_order_history_dir,
+        );
+        assert_all_exist(&formatted_paths);
+        for p in &original_paths {
+            assert!(!p.exists(), "Expected {:?} to not exist anymore.", p);
+        }
+    }
+
+    #[serial]
+    #[test]
+    fn invalid_lines_are_fatal_unless_lenient() {
+        for lenient in [false, true] {
+            let dir = mk_tmp_dir("invalid_history_lines");
+            let original = dir.join("A File.txt");
+            let formatted = dir.join("a_file.txt");
+            File::create(&formatted).unwrap();
+            let history_file = dir.join("history");
+            fs::write(
+                &history_file,
+                format!(
+                    "(d) {} -> {}\nA stray note.\n",
+                    original.to_string_lossy(),
+                    formatted.to_string_lossy()
+                ),
+            )
+            .unwrap();
+
+            let cli = RevertCli {
+                history_file: Some(history_file),
+                last: false,
+                history_dir: Some(mk_tmp_dir("invalid_history_lines_history")),
+                local: false,
+                ops: vec![],
+                lenient,
+                chronological: false,
+            };
+            let mut engine = RevertEngine {
+                data: mk_engine(cli).data,
+                action: None,
+                out: vec![],
+                err: vec![],
+                recap: vec![],
+            };
+            let res = engine.run();
+
+            assert_eq!(res.is_ok(), lenient);
+            let err = String::from_utf8(engine.err).unwrap();
+            assert_eq!(err.contains("Warning: Ignored invalid line"), lenient);
+            // Valid lines are reverted in both modes.
+            assert!(original.exists());
+            assert!(!formatted.exists());
+        }
+    }
+
+    #[cfg(unix)]
+    #[serial]
+    #[test]
+    fn reverting_removes_the_symlinks_left() {
+        let dir = mk_tmp_dir("revert_left_symlinks");
+        let original = dir.join("Some File.txt");
+        File::create(&original).unwrap();
+
+        let history_dir = mk_tmp_dir("revert_left_symlinks_history");
+        let cli = DefaultArgs {
+            files: vec![original.clone()],
+            leave_symlink: true,
+            history_dir: Some(history_dir.clone()),
+            ..Default::default()
+        };
+        default::get_engine(cli, Cfg::default())
+            .expect("default::get_engine should have succeeded.")
+            .run()
+            .expect("The run should have succeeded.");
+        assert!(original.is_symlink());
+
+        revert(
+            only_file_in(&history_dir),
+            &mk_tmp_dir("revert_left_symlinks_second_order_history"),
+        );
+        assert!(original.is_file() && !original.is_symlink());
+        assert!(!dir.join("some_file.txt").exists());
+    }
+
+    #[serial]
+    #[test]
+    fn only_selected_ops_are_reverted() {
+        let dir = mk_tmp_dir("revert_ops");
+        let renamed = (dir.join("A File.txt"), dir.join("a_file.txt"));
+        let overwritten = (dir.join("B File.txt"), dir.join("b_file.txt"));
+        let backed_up = (dir.join("C File.txt"), dir.join("c_file.txt"));
+        let history_file = dir.join("history");
+        let mut history = String::new();
+        for (op, (from, to)) in [("d", &renamed), ("o", &overwritten), ("b", &backed_up)] {
+            File::create(to).unwrap();
+            history.push_str(&format!(
+                "({}) {} -> {}\n",
+                op,
+                from.to_string_lossy(),
+                to.to_string_lossy()
             ));
         }
+        fs::write(&history_file, history).unwrap();
 
-        Ok(())
+        let second_order_history_dir = mk_tmp_dir("revert_ops_history");
+        let cli = RevertCli {
+            history_file: Some(history_file),
+            last: false,
+            history_dir: Some(second_order_history_dir.clone()),
+            local: false,
+            ops: vec![String::from("o"), String::from("b")],
+            lenient: false,
+            chronological: false,
+        };
+        mk_engine(cli)
+            .run()
+            .expect("The revert should have succeeded.");
+
+        assert!(renamed.1.exists() && !renamed.0.exists());
+        assert!(overwritten.0.exists() && !overwritten.1.exists());
+        assert!(backed_up.0.exists() && !backed_up.1.exists());
+        let second_order_history =
+            fs::read_to_string(only_file_in(&second_order_history_dir)).unwrap();
+        assert_eq!(
+            second_order_history,
+            format!(
+                "(d) {} -> {}\n(d) {} -> {}\n",
+                backed_up.1.to_string_lossy(),
+                backed_up.0.to_string_lossy(),
+                overwritten.1.to_string_lossy(),
+                overwritten.0.to_string_lossy()
+            )
+        );
+    }
+
+    #[serial]
+    #[test]
+    fn the_recap_can_be_chronological_while_reverting_in_reverse() {
+        let dir = mk_tmp_dir("revert_chronological");
+        let original = (dir.join("My Dir"), dir.join("My Dir").join("Some File.txt"));
+        let formatted = (dir.join("my_dir"), dir.join("my_dir").join("some_file.txt"));
+        fs::create_dir(&formatted.0).unwrap();
+        File::create(&formatted.1).unwrap();
+        // As written by a run, the directory is renamed before its content.
+        let history_file = dir.join("history");
+        fs::write(
+            &history_file,
+            format!(
+                "(d) {} -> {}\n(d) {} -> {}\n",
+                original.0.to_string_lossy(),
+                formatted.0.to_string_lossy(),
+                formatted.0.join("Some File.txt").to_string_lossy(),
+                formatted.1.to_string_lossy()
+            ),
+        )
+        .unwrap();
+
+        let second_order_history_dir = mk_tmp_dir("revert_chronological_history");
+        let cli = RevertCli {
+            history_file: Some(history_file),
+            last: false,
+            history_dir: Some(second_order_history_dir.clone()),
+            local: false,
+            ops: vec![],
+            lenient: false,
+            chronological: true,
+        };
+        let mut engine = RevertEngine {
+            data: mk_engine(cli).data,
+            action: None,
+            out: vec![],
+            err: vec![],
+            recap: vec![],
+        };
+        engine.run().expect("The revert should have succeeded.");
+
+        assert!(original.1.exists());
+        let content_line = format!(
+            "(d) {} -> {}",
+            formatted.1.to_string_lossy(),
+            formatted.0.join("Some File.txt").to_string_lossy()
+        );
+        let dir_line = format!(
+            "(d) {} -> {}",
+            formatted.0.to_string_lossy(),
+            original.0.to_string_lossy()
+        );
+        let ansi_escapes = Regex::new(r"\x1b\[[0-9;]*m").unwrap();
+        let out = String::from_utf8(engine.out).unwrap();
+        assert_eq!(
+            ansi_escapes.replace_all(&out, ""),
+            format!("{}\n{}\n", dir_line, content_line)
+        );
+        assert_eq!(
+            fs::read_to_string(only_file_in(&second_order_history_dir)).unwrap(),
+            format!("{}\n{}\n", content_line, dir_line)
+        );
     }
 }
@@ -1,12 +1,17 @@
 //! Utilities.
 
 use crate::paths::BACKUP_DIR_PATH;
-use anyhow::Context;
+use anyhow::{anyhow, Context};
 use crossterm::style::Stylize;
+use serde::Serialize;
 use std::fs;
 use std::io;
-use std::io::Write;
-use std::path::Path;
+use std::io::{Read, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
 
 /// Removes the newline (in a cross-platfrom way) at the end of `s` if there is one.
 ///
@@ -36,6 +41,206 @@ pub fn get_now_str() -> String {
     chrono::Local::now().format("%Y%m%d_%H%M%S%.9f").to_string()
 }
 
+/// How to resolve a conflict, i.e. a path to rewrite into an existing file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// Leave both files as they are.
+    Skip,
+    /// Move the existing file to `backup_path`, then rewrite.
+    Backup {
+        /// Where to move the existing file.
+        backup_path: PathBuf,
+    },
+    /// Rewrite anyway, replacing the existing file.
+    Overwrite,
+}
+
+/// A planned resolution of a conflict, computed without touching the
+/// filesystem, then performed with [apply_conflict_plan].
+///
+/// This allows library users to present conflicts the way they want,
+/// while reusing fmtna's logic.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConflictPlan {
+    /// The path you are trying to rewrite.
+    pub path: PathBuf,
+
+    /// The path you want to rewrite into, but where an existing file
+    /// already exists.
+    pub new_path: PathBuf,
+
+    /// How to resolve the conflict.
+    pub resolution: ConflictResolution,
+}
+
+impl ConflictPlan {
+    /// Returns the line recording the resolution in history files, i.e.
+    /// one of:
+    ///
+    /// ```text
+    /// (s) <path> -> <new_path>
+    /// (b) <path> -> <new_path>
+    /// (o) <path> -> <new_path>
+    /// ```
+    pub fn history_line(&self) -> String {
+        let op = match self.resolution {
+            ConflictResolution::Skip => 's',
+            ConflictResolution::Backup { .. } => 'b',
+            ConflictResolution::Overwrite => 'o',
+        };
+
+        format!(
+            "({}) {} -> {}",
+            op,
+            self.path.to_string_lossy(),
+            self.new_path.to_string_lossy()
+        )
+    }
+}
+
+/// Plans to skip the conflict, see [ConflictResolution::Skip].
+///
+/// # Parameters
+///
+/// - `path`: The path you are trying to rewrite.
+/// - `new_path`: The path you want to rewrite into, but where an existing file
+///   already exists.
+pub fn plan_skip(path: &Path, new_path: &Path) -> ConflictPlan {
+    ConflictPlan {
+        path: path.to_owned(),
+        new_path: new_path.to_owned(),
+        resolution: ConflictResolution::Skip,
+    }
+}
+
+/// Plans to back up the existing file, see [ConflictResolution::Backup].
+///
+/// The backup is named after the existing file, suffixed with `_backup_<now_str>`
+/// (before the extension).
+///
+/// # Parameters
+///
+/// - `path`: The path you are trying to rewrite.
+/// - `new_path`: The path you want to rewrite into, but where an existing file
+///   already exists.
+/// - `backup_dir`: The directory where to put the backup.
+/// - `now_str`: The current date, see [get_now_str].
+///
+/// # Errors
+///
+/// Fails if `new_path` has no stem.
+pub fn plan_backup(
+    path: &Path,
+    new_path: &Path,
+    backup_dir: &Path,
+    now_str: &str,
+) -> anyhow::Result<ConflictPlan> {
+    let file_stem = new_path
+        .file_stem()
+        .with_context(|| "Expected new file to have a stem.")?;
+    let mut backup_name = format!("{}_backup_{}", file_stem.to_string_lossy(), now_str);
+    if let Some(extension) = new_path.extension() {
+        backup_name.push('.');
+        backup_name.push_str(&extension.to_string_lossy());
+    }
+
+    Ok(ConflictPlan {
+        path: path.to_owned(),
+        new_path: new_path.to_owned(),
+        resolution: ConflictResolution::Backup {
+            backup_path: backup_dir.join(backup_name),
+        },
+    })
+}
+
+/// Plans to overwrite the existing file, see [ConflictResolution::Overwrite].
+///
+/// # Parameters
+///
+/// - `path`: The path you are trying to rewrite.
+/// - `new_path`: The path you want to rewrite into, but where an existing file
+///   already exists.
+pub fn plan_overwrite(path: &Path, new_path: &Path) -> ConflictPlan {
+    ConflictPlan {
+        path: path.to_owned(),
+        new_path: new_path.to_owned(),
+        resolution: ConflictResolution::Overwrite,
+    }
+}
+
+/// Performs the filesystem changes planned by `plan`, and nothing else
+/// (no feedback is written anywhere).
+///
+/// # Parameters
+///
+/// - `plan`
+/// - `retry_policy`: How to retry renamings failing transiently.
+///
+/// # Errors
+///
+/// Fails when:
+///
+/// - The existing file fails to be backed up, i.e. fails to be moved
+///   to the backup directory.
+/// - The rewriting/renaming fails. When backing up, the existing file is
+///   moved back from the backup directory first.
+pub fn apply_conflict_plan(plan: &ConflictPlan, retry_policy: &RetryPolicy) -> anyhow::Result<()> {
+    match plan.resolution {
+        ConflictResolution::Skip => {}
+        ConflictResolution::Backup { ref backup_path } => {
+            rename_with_retry(&plan.new_path, backup_path, retry_policy).with_context(|| {
+                format!(
+                    "Failed to backup! Couldn't move {} to {}.",
+                    plan.new_path.display(),
+                    backup_path.display()
+                )
+            })?;
+            if let Err(err) = rename_with_retry(&plan.path, &plan.new_path, retry_policy) {
+                // E.g. `path` was removed in the meantime by another process.
+                // Restore the backed up file rather than leaving `new_path` missing.
+                rename_with_retry(backup_path, &plan.new_path, retry_policy).with_context(
+                    || {
+                        format!(
+                            "Failed to rename ({}), then failed to restore {} from its backup {}.",
+                            err,
+                            plan.new_path.display(),
+                            backup_path.display()
+                        )
+                    },
+                )?;
+                return Err(anyhow::Error::new(err).context("Failed to rename."));
+            }
+        }
+        ConflictResolution::Overwrite => {
+            rename_with_retry(&plan.path, &plan.new_path, retry_policy)
+                .with_context(|| "Failed to rename.")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Applies `plan`, then writes feedback into stdout and `history_writer`.
+fn resolve_conflict<W: Write>(
+    plan: &ConflictPlan,
+    history_writer: &mut W,
+    retry_policy: &RetryPolicy,
+) -> anyhow::Result<()> {
+    apply_conflict_plan(plan, retry_policy)?;
+
+    let recap_line = plan.history_line();
+    let styled_recap_line = match plan.resolution {
+        ConflictResolution::Skip => recap_line.clone().dark_blue(),
+        ConflictResolution::Backup { .. } => recap_line.clone().dark_green(),
+        ConflictResolution::Overwrite => recap_line.clone().dark_yellow(),
+    };
+    println!("{}", styled_recap_line);
+    writeln!(history_writer, "{}", recap_line)
+        .with_context(|| "Failed to write to history file.")?;
+
+    Ok(())
+}
+
 /// Skips filename rewriting when conflict encountered, i.e. when `new_path`
 /// points to an existing file.
 ///
@@ -51,19 +256,14 @@ pub fn get_now_str() -> String {
 ///
 /// - `path`: The path you are trying to rewrite.
 /// - `new_path`: The path you want to rewrite into, but where an existing file
-///     already exists.
+///   already exists.
 /// - `history_writer`: Where to write feeback to, in addition to stdout.
 pub fn skip<W: Write>(path: &Path, new_path: &Path, history_writer: &mut W) -> anyhow::Result<()> {
-    let recap_line = format!(
-        "(s) {} -> {}",
-        path.to_string_lossy(),
-        new_path.to_string_lossy()
-    );
-    println!("{}", recap_line.clone().dark_blue());
-    writeln!(history_writer, "{}", recap_line)
-        .with_context(|| "Failed to write to history file.")?;
-
-    Ok(())
+    resolve_conflict(
+        &plan_skip(path, new_path),
+        history_writer,
+        &RetryPolicy::default(),
+    )
 }
 
 /// Backs up the existing file at path `new_path`, then rewrites `path`
@@ -81,8 +281,9 @@ pub fn skip<W: Write>(path: &Path, new_path: &Path, history_writer: &mut W) -> a
 ///
 /// - `path`: The path you are trying to rewrite.
 /// - `new_path`: The path you want to rewrite into, but where an existing file
-///     already exists.
+///   already exists.
 /// - `history_writer`: Where to write feeback to, in addition to stdout.
+/// - `retry_policy`: How to retry renamings failing transiently.
 ///
 /// # Errors
 ///
@@ -99,45 +300,10 @@ pub fn backup<W: Write>(
     path: &Path,
     new_path: &Path,
     history_writer: &mut W,
+    retry_policy: &RetryPolicy,
 ) -> anyhow::Result<()> {
-    // Figure out the backup's filename
-    // ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
-    let mut new_name;
-    let file_stem = new_path
-        .file_stem()
-        .with_context(|| "Expected new file to have a stem.")?;
-    new_name = format!("{}_backup_{}", file_stem.to_string_lossy(), get_now_str());
-    if let Some(extension) = new_path.extension() {
-        new_name.push('.');
-        new_name.push_str(&extension.to_string_lossy());
-    }
-    let mut backup_path = BACKUP_DIR_PATH.clone();
-    backup_path.push(new_name);
-
-    // Make the backup
-    // ^^^^^^^^^^^^^^^
-    fs::rename(new_path, backup_path.clone()).with_context(|| {
-        format!(
-            "Failed to backup! Couldn't move {} to {}.",
-            new_path.display(),
-            backup_path.display()
-        )
-    })?;
-
-    fs::rename(path, new_path).with_context(|| "Failed to rename.")?;
-
-    // Report to the user
-    // ^^^^^^^^^^^^^^^^^^
-    let recap_line = format!(
-        "(b) {} -> {}",
-        path.to_string_lossy(),
-        new_path.to_string_lossy()
-    );
-    println!("{}", recap_line.clone().dark_green());
-    writeln!(history_writer, "{}", recap_line)
-        .with_context(|| "Failed to write to history file.")?;
-
-    Ok(())
+    let plan = plan_backup(path, new_path, &BACKUP_DIR_PATH, &get_now_str())?;
+    resolve_conflict(&plan, history_writer, retry_policy)
 }
 
 /// Overwrites existing file at path `new_path` by rewriting
@@ -155,8 +321,9 @@ pub fn backup<W: Write>(
 ///
 /// - `path`: The path you are trying to rewrite.
 /// - `new_path`: The path you want to rewrite into, but where an existing file
-///     already exists.
+///   already exists.
 /// - `history_writer`: Where to write feeback to, in addition to stdout.
+/// - `retry_policy`: How to retry renamings failing transiently.
 ///
 /// # Errors
 ///
@@ -172,17 +339,549 @@ pub fn overwrite<W: Write>(
     path: &Path,
     new_path: &Path,
     history_writer: &mut W,
+    retry_policy: &RetryPolicy,
 ) -> anyhow::Result<()> {
-    fs::rename(path, new_path).with_context(|| "Failed to rename.")?;
+    resolve_conflict(
+        &plan_overwrite(path, new_path),
+        history_writer,
+        retry_policy,
+    )
+}
+
+/// How to retry renamings failing transiently, see [rename_with_retry].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct RetryPolicy {
+    /// How many times to retry (0 means no retry).
+    pub retries: usize,
+
+    /// How long to wait before each retry, in milliseconds.
+    pub delay_ms: u64,
+}
+
+/// Renames `from` into `to` like [fs::rename], but retries according to
+/// `retry_policy` when failing transiently.
+///
+/// When `from` is a file and `to` is on another device (e.g. the backup
+/// directory is on another filesystem), falls back to [move_by_copy].
+///
+/// # Parameters
+///
+/// - `from`
+/// - `to`
+/// - `retry_policy`
+pub fn rename_with_retry(from: &Path, to: &Path, retry_policy: &RetryPolicy) -> io::Result<()> {
+    with_retry(retry_policy, || fs::rename(from, to)).or_else(|err| {
+        if crosses_devices(&err) && from.is_file() && !from.is_symlink() {
+            move_by_copy(from, to)
+        } else {
+            Err(err)
+        }
+    })
+}
+
+/// Returns whether `err` means that a renaming failed because it was across
+/// devices.
+fn crosses_devices(err: &io::Error) -> bool {
+    // EXDEV on Unix, ERROR_NOT_SAME_DEVICE on Windows
+    (cfg!(unix) && err.raw_os_error() == Some(18))
+        || (cfg!(windows) && err.raw_os_error() == Some(17))
+}
+
+/// Moves the file `from` to `to` by copying it then removing it, for when it
+/// can't be renamed.
+///
+/// The permissions and the access and modification times are preserved, as
+/// well as the ownership on Unix when permitted. A warning is printed to
+/// stderr for what fails to be preserved.
+///
+/// # Parameters
+///
+/// - `from`: A file (not a directory).
+/// - `to`
+///
+/// # Errors
+///
+/// Fails if copying, setting the permissions or removing `from` fails.
+pub fn move_by_copy(from: &Path, to: &Path) -> io::Result<()> {
+    let metadata = fs::metadata(from)?;
+    fs::copy(from, to)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        if let Err(err) = std::os::unix::fs::chown(to, Some(metadata.uid()), Some(metadata.gid())) {
+            warn(to, &format!("Failed to preserve the ownership. {}", err));
+        }
+    }
+    let times = metadata.accessed().and_then(|accessed| {
+        Ok(fs::FileTimes::new()
+            .set_accessed(accessed)
+            .set_modified(metadata.modified()?))
+    });
+    let res = times.and_then(|times| {
+        let file = if cfg!(windows) {
+            fs::File::options().write(true).open(to)?
+        } else {
+            fs::File::open(to)?
+        };
+        file.set_times(times)
+    });
+    if let Err(err) = res {
+        warn(
+            to,
+            &format!("Failed to preserve the modification time. {}", err),
+        );
+    }
+    fs::set_permissions(to, metadata.permissions())?;
+
+    fs::remove_file(from)
+}
 
-    let recap_line = format!(
-        "(o) {} -> {}",
-        path.to_string_lossy(),
-        new_path.to_string_lossy()
+/// Prints the warning `mess` about `path` to stderr.
+fn warn(path: &Path, mess: &str) {
+    eprintln!(
+        "{}",
+        format!("(w) {}: {}", path.display(), mess).dark_yellow()
     );
-    println!("{}", recap_line.clone().dark_yellow());
-    writeln!(history_writer, "{}", recap_line)
-        .with_context(|| "Failed to write to history file.")?;
+}
+
+/// Runs `op` and retries it according to `retry_policy` for as long as it
+/// fails with an error that is plausibly transient (e.g. interrupted or timed out).
+///
+/// # Parameters
+///
+/// - `retry_policy`
+/// - `op`: The fallible operation.
+///
+/// # Returns
+///
+/// The result of the last attempt.
+pub fn with_retry<T, F>(retry_policy: &RetryPolicy, mut op: F) -> io::Result<T>
+where
+    F: FnMut() -> io::Result<T>,
+{
+    let mut retries_left = retry_policy.retries;
+    loop {
+        match op() {
+            Err(err) if retries_left > 0 && is_transient(&err) => {
+                retries_left -= 1;
+                thread::sleep(Duration::from_millis(retry_policy.delay_ms));
+            }
+            res => return res,
+        }
+    }
+}
+
+fn is_transient(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::Interrupted
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::ResourceBusy
+    )
+}
+
+/// Returns whether `a` and `b` are paths to the same existing file, e.g. paths
+/// differing only by case on a case-insensitive filesystem.
+///
+/// # Parameters
+///
+/// - `a`
+/// - `b`
+pub fn is_same_file(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        match (fs::metadata(a), fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        match (fs::canonicalize(a), fs::canonicalize(b)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Returns the MIME type of the file at `path`, guessed from its first bytes.
+///
+/// Content not recognized by its magic bytes but made of valid UTF-8 is
+/// considered to be "text/plain", and any other to be
+/// "application/octet-stream".
+///
+/// # Parameters
+///
+/// - `path`: The path of the file to sniff.
+pub fn sniff_mime_type(path: &Path) -> io::Result<&'static str> {
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    fs::File::open(path)?
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut head)?;
+    if let Some(kind) = infer::get(&head) {
+        return Ok(kind.mime_type());
+    }
+    // The read may have cut a multibyte character in two.
+    match std::str::from_utf8(&head) {
+        Err(err) if err.error_len().is_some() => Ok("application/octet-stream"),
+        _ => Ok("text/plain"),
+    }
+}
+
+/// Number of bytes read by [sniff_mime_type].
+const SNIFF_LEN: usize = 8192;
+
+/// Returns whether `mime_type` matches `filter`, either exactly or by its
+/// top-level type (e.g. "image/png" matches both "image/png" and "image").
+/// The comparison is case-insensitive.
+///
+/// # Parameters
+///
+/// - `mime_type`: A full MIME type, like the ones [sniff_mime_type] returns.
+/// - `filter`: A full MIME type or a top-level type.
+pub fn mime_type_matches(mime_type: &str, filter: &str) -> bool {
+    mime_type.eq_ignore_ascii_case(filter)
+        || mime_type
+            .split_once('/')
+            .is_some_and(|(top_level, _)| top_level.eq_ignore_ascii_case(filter))
+}
+
+/// Creates a symbolic link at `link` pointing to `target`.
+///
+/// On Windows, creating symbolic links requires either administrator
+/// privileges or the Developer Mode to be enabled.
+///
+/// # Parameters
+///
+/// - `target`: What the link points to. If relative, it is relative to the
+///   parent directory of `link`.
+/// - `link`: Where to create the link.
+pub fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(target, link)
+    }
+
+    #[cfg(windows)]
+    {
+        let resolved_target = link.parent().map_or(target.to_owned(), |p| p.join(target));
+        if resolved_target.is_dir() {
+            std::os::windows::fs::symlink_dir(target, link)
+        } else {
+            std::os::windows::fs::symlink_file(target, link)
+        }
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = (target, link);
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Symbolic links are not supported on this platform.",
+        ))
+    }
+}
+
+/// Opens `path` with `editor`, waiting for it to be closed.
+///
+/// On Windows, `editor` is run through `cmd /c`.
+///
+/// # Parameters
+///
+/// - `editor`: The command of the editor.
+/// - `path`: The file to edit.
+///
+/// # Errors
+///
+/// Fails if the editor can't be run or exits with a failure status.
+pub fn open_in_editor(editor: &str, path: &Path) -> anyhow::Result<()> {
+    let status = if cfg!(windows) {
+        Command::new("cmd").arg("/c").arg(editor).arg(path).status()
+    } else {
+        Command::new(editor).arg(path).status()
+    }
+    .with_context(|| format!("Failed to run '{} {}'.", editor, path.to_string_lossy()))?;
+
+    if !status.success() {
+        return Err(anyhow!(
+            "Command '{} {}' failed. Exited with status {}.",
+            editor,
+            path.to_string_lossy(),
+            status,
+        ));
+    }
 
     Ok(())
 }
+
+/// A piece of the difference between two strings, see [diff_chars].
+#[derive(Debug, PartialEq, Eq)]
+pub enum DiffChunk {
+    /// Characters present in both strings.
+    Same(String),
+    /// Characters present only in the old string.
+    Removed(String),
+    /// Characters present only in the new string.
+    Added(String),
+}
+
+impl DiffChunk {
+    fn text_mut(&mut self) -> &mut String {
+        match self {
+            DiffChunk::Same(s) | DiffChunk::Removed(s) | DiffChunk::Added(s) => s,
+        }
+    }
+}
+
+/// Computes the character-wise difference between `old` and `new`.
+///
+/// The difference is based on the longest common subsequence of characters,
+/// which is fine for strings as short as filenames.
+/// Consecutive characters of the same kind are grouped in one chunk, and
+/// removals come before additions.
+///
+/// # Parameters
+///
+/// - `old`: The string before the change.
+/// - `new`: The string after the change.
+///
+/// # Examples
+///
+/// ```rust
+/// use fmtna::utils::{diff_chars, DiffChunk};
+///
+/// assert_eq!(
+///     diff_chars("a b", "a_b"),
+///     vec![
+///         DiffChunk::Same(String::from("a")),
+///         DiffChunk::Removed(String::from(" ")),
+///         DiffChunk::Added(String::from("_")),
+///         DiffChunk::Same(String::from("b")),
+///     ]
+/// );
+/// ```
+pub fn diff_chars(old: &str, new: &str) -> Vec<DiffChunk> {
+    let old: Vec<char> = old.chars().collect();
+    let new: Vec<char> = new.chars().collect();
+
+    // lcs[i][j] is the length of the longest common subsequence of old[i..]
+    // and new[j..].
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut chunks = vec![];
+    let mut push = |chunk: fn(String) -> DiffChunk, c: char| {
+        let chunk = chunk(c.to_string());
+        match chunks.last_mut() {
+            Some(last) if mem::discriminant(last) == mem::discriminant(&chunk) => {
+                last.text_mut().push(c)
+            }
+            _ => chunks.push(chunk),
+        }
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            push(DiffChunk::Same, old[i]);
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            push(DiffChunk::Removed, old[i]);
+            i += 1;
+        } else {
+            push(DiffChunk::Added, new[j]);
+            j += 1;
+        }
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paths::tests::mk_tmp_dir;
+    use serial_test::serial;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// Returns an operation failing with `errors` (in order), then succeeding,
+    /// along with the number of times it was called.
+    fn flaky_op(errors: Vec<io::ErrorKind>) -> (impl FnMut() -> io::Result<()>, Rc<Cell<usize>>) {
+        let calls = Rc::new(Cell::new(0));
+        let calls_clone = calls.clone();
+        let op = move || {
+            let call = calls_clone.get();
+            calls_clone.set(call + 1);
+            match errors.get(call) {
+                Some(kind) => Err(io::Error::from(*kind)),
+                None => Ok(()),
+            }
+        };
+        (op, calls)
+    }
+
+    #[test]
+    fn conflict_plans_record_the_resolution() {
+        let path = Path::new("/dir/Some File.txt");
+        let new_path = Path::new("/dir/some_file.txt");
+
+        let plan = plan_skip(path, new_path);
+        assert_eq!(plan.resolution, ConflictResolution::Skip);
+        assert_eq!(
+            plan.history_line(),
+            "(s) /dir/Some File.txt -> /dir/some_file.txt"
+        );
+
+        let plan = plan_overwrite(path, new_path);
+        assert_eq!(plan.resolution, ConflictResolution::Overwrite);
+        assert_eq!(
+            plan.history_line(),
+            "(o) /dir/Some File.txt -> /dir/some_file.txt"
+        );
+
+        let plan = plan_backup(path, new_path, Path::new("/backups"), "20240101_000000").unwrap();
+        assert_eq!(
+            plan.resolution,
+            ConflictResolution::Backup {
+                backup_path: PathBuf::from("/backups/some_file_backup_20240101_000000.txt")
+            }
+        );
+        assert_eq!(
+            plan.history_line(),
+            "(b) /dir/Some File.txt -> /dir/some_file.txt"
+        );
+    }
+
+    #[test]
+    fn backups_of_files_without_extension_have_none() {
+        let plan = plan_backup(
+            Path::new("/dir/Makefile2"),
+            Path::new("/dir/makefile2"),
+            Path::new("/backups"),
+            "now",
+        )
+        .unwrap();
+        assert_eq!(
+            plan.resolution,
+            ConflictResolution::Backup {
+                backup_path: PathBuf::from("/backups/makefile2_backup_now")
+            }
+        );
+    }
+
+    #[test]
+    fn transient_failures_are_retried() {
+        let retry_policy = RetryPolicy {
+            retries: 3,
+            delay_ms: 1,
+        };
+        let (op, calls) = flaky_op(vec![io::ErrorKind::Interrupted, io::ErrorKind::TimedOut]);
+        assert!(with_retry(&retry_policy, op).is_ok());
+        assert_eq!(calls.get(), 3);
+
+        // Gives up after the last retry
+        let retry_policy = RetryPolicy {
+            retries: 1,
+            delay_ms: 1,
+        };
+        let (op, calls) = flaky_op(vec![io::ErrorKind::Interrupted, io::ErrorKind::TimedOut]);
+        let err = with_retry(&retry_policy, op).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn other_failures_are_not_retried() {
+        let retry_policy = RetryPolicy {
+            retries: 3,
+            delay_ms: 1,
+        };
+        let (op, calls) = flaky_op(vec![io::ErrorKind::AlreadyExists]);
+        let err = with_retry(&retry_policy, op).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[serial]
+    #[test]
+    fn backups_are_restored_if_the_renaming_fails() {
+        let dir = mk_tmp_dir("backup_rollback");
+        let backup_dir = mk_tmp_dir("backup_rollback_backups");
+        // The file to rename was removed since the conflict was detected.
+        let path = dir.join("Some File.txt");
+        let new_path = dir.join("some_file.txt");
+        fs::write(&new_path, "existing").unwrap();
+
+        let plan = plan_backup(&path, &new_path, &backup_dir, "now").unwrap();
+        assert!(apply_conflict_plan(&plan, &RetryPolicy::default()).is_err());
+        assert_eq!(fs::read_to_string(&new_path).unwrap(), "existing");
+        assert_eq!(fs::read_dir(&backup_dir).unwrap().count(), 0);
+    }
+
+    #[cfg(unix)]
+    #[serial]
+    #[test]
+    fn moving_by_copy_preserves_permissions_and_times() {
+        use std::os::unix::fs::PermissionsExt;
+        use std::time::SystemTime;
+
+        let dir = mk_tmp_dir("move_by_copy");
+        let from = dir.join("from.txt");
+        let to = dir.join("to.txt");
+        fs::write(&from, "content").unwrap();
+        fs::set_permissions(&from, fs::Permissions::from_mode(0o640)).unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        fs::File::open(&from)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        move_by_copy(&from, &to).unwrap();
+        assert!(!from.exists());
+        assert_eq!(fs::read_to_string(&to).unwrap(), "content");
+        let metadata = fs::metadata(&to).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o640);
+        assert_eq!(metadata.modified().unwrap(), modified);
+    }
+
+    #[test]
+    fn diffs_group_consecutive_changes() {
+        use DiffChunk::*;
+
+        assert_eq!(diff_chars("", ""), vec![]);
+        assert_eq!(diff_chars("abc", "abc"), vec![Same(String::from("abc"))]);
+        assert_eq!(
+            diff_chars("My File", "my_file"),
+            vec![
+                Removed(String::from("M")),
+                Added(String::from("m")),
+                Same(String::from("y")),
+                Removed(String::from(" F")),
+                Added(String::from("_f")),
+                Same(String::from("ile")),
+            ]
+        );
+        assert_eq!(
+            diff_chars("file.txt", "a_file"),
+            vec![
+                Added(String::from("a_")),
+                Same(String::from("file")),
+                Removed(String::from(".txt")),
+            ]
+        );
+    }
+}